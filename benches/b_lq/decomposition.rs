@@ -4,6 +4,7 @@ use faer::dyn_stack::{MemBuffer, MemStack};
 use faer::linalg::qr::no_pivoting::factor;
 use faer::{Mat, Par};
 use stellar::decomposition::lq::AutumnDecomp;
+use stellar::decomposition::wy::WyLqDecomp;
 use stellar::random::generation::generate_random_matrix;
 
 pub fn bench_decomposition(c: &mut Criterion) {
@@ -17,6 +18,12 @@ pub fn bench_decomposition(c: &mut Criterion) {
                 |matrix| black_box(AutumnDecomp::new(matrix)),
             );
         });
+        group.bench_with_input(BenchmarkId::new("Wy_Decomposition", n), &n, |b, &n| {
+            b.iter_with_setup(
+                || generate_random_matrix(n, n),
+                |matrix| black_box(WyLqDecomp::new(matrix)),
+            );
+        });
         group.bench_with_input(BenchmarkId::new("Faer_Decomposition", n), &n, |b, &n| {
             b.iter_with_setup(
                 || {
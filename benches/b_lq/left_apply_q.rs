@@ -6,6 +6,7 @@ use faer::linalg::householder;
 use faer::linalg::qr::no_pivoting::factor;
 use faer::{Conj, Par};
 use stellar::decomposition::lq::AutumnDecomp;
+use stellar::decomposition::wy::WyLqDecomp;
 use stellar::random::generation::generate_random_matrix;
 
 pub fn bench_apply_left_q(c: &mut Criterion) {
@@ -26,6 +27,16 @@ pub fn bench_apply_left_q(c: &mut Criterion) {
                 },
             );
         });
+        group.bench_with_input(BenchmarkId::new("Wy_Left_Apply_Q", n), &n, |b, &n| {
+            b.iter_with_setup(
+                || {
+                    let decomp = WyLqDecomp::new(generate_random_matrix(n, n));
+                    let target = generate_random_matrix(n, n);
+                    (decomp, target)
+                },
+                |(decomp, mut target)| black_box(decomp.left_apply_q(&mut target)),
+            );
+        });
 
         group.bench_with_input(BenchmarkId::new("Faer_Left_Apply_Q", n), &n, |b, &n| {
     b.iter_with_setup(
@@ -96,6 +107,16 @@ pub fn bench_apply_left_qt(c: &mut Criterion) {
                 },
             );
         });
+        group.bench_with_input(BenchmarkId::new("Wy_Left_Apply_Qt", n), &n, |b, &n| {
+            b.iter_with_setup(
+                || {
+                    let decomp = WyLqDecomp::new(generate_random_matrix(n, n));
+                    let target = generate_random_matrix(n, n);
+                    (decomp, target)
+                },
+                |(decomp, mut target)| black_box(decomp.left_apply_qt(&mut target)),
+            );
+        });
 
         group.bench_with_input(BenchmarkId::new("Faer_Left_Apply_Qt", n), &n, |b, &n| {
     b.iter_with_setup(
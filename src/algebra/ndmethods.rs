@@ -168,6 +168,35 @@ pub fn in_place_sub(x: &mut NdArray, y: &NdArray) {
     }
 }
 
+// sum of alpha_k X_k
+pub(crate) fn combine(terms: &[(f32, &NdArray)]) -> NdArray {
+    let mut result = NdArray::new(terms[0].1.dims.clone(), vec![0f32; terms[0].1.data.len()]);
+    for (alpha, x) in terms {
+        debug_assert_eq!(result.dims, x.dims);
+        for (r, v) in result.data.iter_mut().zip(&x.data) {
+            *r += alpha * v;
+        }
+    }
+    result
+}
+
+pub(crate) fn frobenius(a: &NdArray) -> f32 {
+    a.data.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+// X <- (X + X') / 2
+pub(crate) fn symmetrize(x: &mut NdArray) {
+    let n = x.dims[0];
+    debug_assert_eq!(n, x.dims[1]);
+    for i in 0..n {
+        for j in 0..i {
+            let mean = 0.5 * (x.data[i * n + j] + x.data[j * n + i]);
+            x.data[i * n + j] = mean;
+            x.data[j * n + i] = mean;
+        }
+    }
+}
+
 pub fn lt_tensor_mult(blocksize: usize, x: &NdArray, y: &NdArray) -> NdArray {
     // transpose basis
     // X'Y
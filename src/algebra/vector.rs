@@ -27,7 +27,10 @@ pub fn scalar_product(lambda: f32, vector: &[f32]) -> Vec<f32> {
 }
 
 pub fn dot_product(x: &[f32], y: &[f32]) -> f32 {
-    x.iter().zip(y.iter()).map(|(&x, &y)| x * y).sum()
+    x.iter()
+        .zip(y.iter())
+        .map(|(&x, &y)| x as f64 * y as f64)
+        .sum::<f64>() as f32
 }
 
 pub fn distance_squared(x: &[f32], y: &[f32]) -> f32 {
@@ -42,11 +45,16 @@ pub fn magnitude_squared(x: &[f32]) -> f32 {
 }
 
 pub fn magnitude(x: &[f32]) -> f32 {
-    x.iter()
-        .zip(x.iter())
-        .map(|(&x, &y)| x * y)
-        .sum::<f32>()
-        .sqrt()
+    dot_product(x, x).sqrt()
+}
+
+pub fn norm_inf(x: &[f32]) -> f32 {
+    x.iter().fold(0f32, |acc, &x| acc.max(x.abs()))
+}
+
+// y <- y + alpha x
+pub fn axpy(alpha: f32, x: &[f32], y: &mut [f32]) {
+    y.iter_mut().zip(x).for_each(|(y, &x)| *y += alpha * x);
 }
 
 pub fn vec_in_place_add(x: &mut Vec<f32>, y: &Vec<f32>) {
@@ -44,6 +44,37 @@ impl Cholesky {
             },
         }
     }
+    // Ax = b;
+    // Lz = b;
+    // L'x = z;
+    pub fn solve_inplace_vec(&self, y: &mut [f32]) {
+        self.solve_lower_inplace_vec(y);
+        self.solve_upper_inplace_vec(y);
+    }
+    // Lz = b;
+    pub fn solve_lower_inplace_vec(&self, y: &mut [f32]) {
+        let n = self.l.dims[0];
+        debug_assert_eq!(n, y.len());
+        let l = &self.l.data;
+        for i in 0..n {
+            for k in 0..i {
+                y[i] -= l[i * n + k] * y[k];
+            }
+            y[i] /= l[i * n + i];
+        }
+    }
+    // L'x = z;
+    pub fn solve_upper_inplace_vec(&self, y: &mut [f32]) {
+        let n = self.l.dims[0];
+        debug_assert_eq!(n, y.len());
+        let l = &self.l.data;
+        for i in (0..n).rev() {
+            for k in i + 1..n {
+                y[i] -= l[k * n + i] * y[k];
+            }
+            y[i] /= l[i * n + i];
+        }
+    }
 }
 // impl Cholesky {
 //     pub fn new(a: NdArray) -> Self {
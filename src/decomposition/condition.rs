@@ -0,0 +1,346 @@
+use crate::decomposition::cholesky::Cholesky;
+use crate::decomposition::lower_upper::LuPivotDecompose;
+use crate::decomposition::qr::QrDecomposition;
+use crate::structure::ndarray::NdArray;
+
+const MAX_ITERS: usize = 5;
+const MIN_IMPROVEMENT: f32 = 0.5;
+
+/// SolveReport
+/// accuracy of a refined solution of Ax = b
+///
+/// * x: solution after refinement
+/// * normwise_backward_error: ||r||_inf / (||A||_inf ||x||_inf + ||b||_inf)
+/// * componentwise_backward_error: max_i |r_i| / (|A||x| + |b|)_i
+/// * forward_error_bound: estimated bound on ||x - x*||_inf / ||x||_inf
+/// * condition: estimated one norm condition number of A
+/// * refinements: iterative refinement steps taken
+#[derive(Debug)]
+pub struct SolveReport {
+    pub x: Vec<f32>,
+    pub normwise_backward_error: f32,
+    pub componentwise_backward_error: f32,
+    pub forward_error_bound: f32,
+    pub condition: f32,
+    pub refinements: usize,
+}
+
+/// one_norm
+/// maximum absolute column sum
+pub fn one_norm(a: &NdArray) -> f32 {
+    let cols = a.dims[1];
+    let mut sums = vec![0f32; cols];
+    for row in a.data.chunks(cols) {
+        for (s, v) in sums.iter_mut().zip(row) {
+            *s += v.abs();
+        }
+    }
+    sums.into_iter().fold(0f32, f32::max)
+}
+
+fn inf_norm(x: &[f32]) -> f32 {
+    x.iter().fold(0f32, |m, v| m.max(v.abs()))
+}
+
+/// estimate_one_norm
+/// hager / higham estimate of ||M||_1 from products with M and M' only, a lower bound that is
+/// rarely off by more than a factor of three
+///
+/// * n: dimension of M
+/// * apply: x <- Mx
+/// * apply_transpose: x <- M'x
+pub fn estimate_one_norm(
+    n: usize,
+    apply: impl Fn(&mut [f32]),
+    apply_transpose: impl Fn(&mut [f32]),
+) -> f32 {
+    if n == 0 {
+        return 0f32;
+    }
+    let mut probe = vec![1f32 / n as f32; n];
+    let mut y = probe.clone();
+    apply(&mut y);
+    let mut estimate: f32 = y.iter().map(|v| v.abs()).sum();
+    for _ in 0..MAX_ITERS {
+        // subgradient z = M' sign(y), stop once no unit vector can improve on the probe
+        let mut z: Vec<f32> = y
+            .iter()
+            .map(|&v| if v < 0f32 { -1f32 } else { 1f32 })
+            .collect();
+        apply_transpose(&mut z);
+        let j = (0..n)
+            .max_by(|&a, &b| z[a].abs().total_cmp(&z[b].abs()))
+            .unwrap();
+        let ztx: f32 = z.iter().zip(&probe).map(|(a, b)| a * b).sum();
+        if z[j].abs() <= ztx {
+            break;
+        }
+        probe.fill(0f32);
+        probe[j] = 1f32;
+        y.copy_from_slice(&probe);
+        apply(&mut y);
+        let next: f32 = y.iter().map(|v| v.abs()).sum();
+        if next <= estimate {
+            break;
+        }
+        estimate = next;
+    }
+    // higham's alternating vector catches matrices that fool the gradient ascent
+    let mut alt: Vec<f32> = (0..n)
+        .map(|i| {
+            let sign = if i & 1 == 0 { 1f32 } else { -1f32 };
+            sign * (1f32 + i as f32 / (n.max(2) - 1) as f32)
+        })
+        .collect();
+    apply(&mut alt);
+    let alt_estimate = 2f32 * alt.iter().map(|v| v.abs()).sum::<f32>() / (3 * n) as f32;
+    estimate.max(alt_estimate)
+}
+
+/// refine
+/// mixed precision iterative refinement, residuals r = b - Ax accumulate in f64 and the
+/// corrections reuse the f32 factorization, followed by backward and forward error estimates
+///
+/// * a: system matrix ~ n x n
+/// * b: right hand side
+/// * solve: x <- A^-1 x
+/// * solve_transpose: x <- A^-T x
+/// * max_refinements: refinement steps, zero reports on the plain solve
+pub fn refine(
+    a: &NdArray,
+    b: &[f32],
+    solve: impl Fn(&mut [f32]),
+    solve_transpose: impl Fn(&mut [f32]),
+    max_refinements: usize,
+) -> SolveReport {
+    let n = a.dims[0];
+    debug_assert!(a.dims[1] == n && b.len() == n);
+    let residual = |x: &[f32]| -> Vec<f64> {
+        a.data
+            .chunks(n)
+            .zip(b)
+            .map(|(row, &bi)| {
+                bi as f64
+                    - row
+                        .iter()
+                        .zip(x)
+                        .map(|(&r, &v)| r as f64 * v as f64)
+                        .sum::<f64>()
+            })
+            .collect()
+    };
+    let mut x = b.to_vec();
+    solve(&mut x);
+    let mut refinements = 0;
+    let mut last = f32::INFINITY;
+    while refinements < max_refinements {
+        let mut d: Vec<f32> = residual(&x).into_iter().map(|v| v as f32).collect();
+        solve(&mut d);
+        let step = inf_norm(&d);
+        x.iter_mut().zip(&d).for_each(|(xi, di)| *xi += di);
+        refinements += 1;
+        // converged, or stagnating once the correction stops shrinking
+        if step <= f32::EPSILON * inf_norm(&x) || step > MIN_IMPROVEMENT * last {
+            break;
+        }
+        last = step;
+    }
+    let r = residual(&x);
+    // |A||x| + |b|
+    let magnitude: Vec<f64> = a
+        .data
+        .chunks(n)
+        .zip(b)
+        .map(|(row, &bi)| {
+            row.iter()
+                .zip(&x)
+                .map(|(&r, &v)| (r as f64 * v as f64).abs())
+                .sum::<f64>()
+                + (bi as f64).abs()
+        })
+        .collect();
+    let componentwise = r.iter().zip(&magnitude).fold(0f64, |m, (&ri, &di)| {
+        m.max(if ri == 0f64 { 0f64 } else { ri.abs() / di })
+    });
+    let a_inf = a
+        .data
+        .chunks(n)
+        .map(|row| row.iter().map(|v| v.abs()).sum::<f32>())
+        .fold(0f32, f32::max);
+    let x_inf = inf_norm(&x);
+    let r_inf = r.iter().fold(0f64, |m, v| m.max(v.abs())) as f32;
+    let normwise = r_inf / (a_inf * x_inf + inf_norm(b)).max(f32::MIN_POSITIVE);
+    // lapack style bound || |A^-1| (|r| + (n + 1) eps (|A||x| + |b|)) ||_inf / ||x||_inf
+    // with ||A^-1 W||_inf = ||W A^-T||_1
+    let eps = f32::EPSILON as f64;
+    let w: Vec<f32> = r
+        .iter()
+        .zip(&magnitude)
+        .map(|(ri, di)| (ri.abs() + (n + 1) as f64 * eps * di) as f32)
+        .collect();
+    let bound = estimate_one_norm(
+        n,
+        |v| {
+            solve_transpose(v);
+            v.iter_mut().zip(&w).for_each(|(vi, wi)| *vi *= wi);
+        },
+        |v| {
+            v.iter_mut().zip(&w).for_each(|(vi, wi)| *vi *= wi);
+            solve(v);
+        },
+    );
+    let condition = one_norm(a) * estimate_one_norm(n, &solve, &solve_transpose);
+    SolveReport {
+        x,
+        normwise_backward_error: normwise,
+        componentwise_backward_error: componentwise as f32,
+        forward_error_bound: bound / x_inf.max(f32::MIN_POSITIVE),
+        condition,
+        refinements,
+    }
+}
+
+impl LuPivotDecompose {
+    /// condition_estimate
+    /// ||A||_1 ||A^-1||_1 with the inverse norm estimated from O(n^2) triangular solves
+    ///
+    /// * a: matrix that was factored
+    pub fn condition_estimate(&self, a: &NdArray) -> f32 {
+        let n = self.matrix.dims[0];
+        one_norm(a)
+            * estimate_one_norm(
+                n,
+                |x| self.solve_inplace_vec(x),
+                |x| self.solve_transpose_inplace_vec(x),
+            )
+    }
+    pub fn solve_with_report(&self, a: &NdArray, b: &[f32], max_refinements: usize) -> SolveReport {
+        refine(
+            a,
+            b,
+            |x| self.solve_inplace_vec(x),
+            |x| self.solve_transpose_inplace_vec(x),
+            max_refinements,
+        )
+    }
+}
+
+impl Cholesky {
+    /// condition_estimate
+    /// ||A||_1 ||A^-1||_1 of a symmetric positive definite matrix, A^-T = A^-1
+    ///
+    /// * a: matrix that was factored
+    pub fn condition_estimate(&self, a: &NdArray) -> f32 {
+        let n = self.l.dims[0];
+        one_norm(a)
+            * estimate_one_norm(
+                n,
+                |x| self.solve_inplace_vec(x),
+                |x| self.solve_inplace_vec(x),
+            )
+    }
+    pub fn solve_with_report(&self, a: &NdArray, b: &[f32], max_refinements: usize) -> SolveReport {
+        refine(
+            a,
+            b,
+            |x| self.solve_inplace_vec(x),
+            |x| self.solve_inplace_vec(x),
+            max_refinements,
+        )
+    }
+}
+
+impl QrDecomposition {
+    /// condition_estimate
+    /// ||A||_1 ||A^-1||_1 of a square matrix from its householder qr
+    ///
+    /// * a: matrix that was factored
+    pub fn condition_estimate(&self, a: &NdArray) -> f32 {
+        one_norm(a)
+            * estimate_one_norm(
+                self.cols,
+                |x| self.solve_inplace_vec(x),
+                |x| self.solve_transpose_inplace_vec(x),
+            )
+    }
+    pub fn solve_with_report(&self, a: &NdArray, b: &[f32], max_refinements: usize) -> SolveReport {
+        refine(
+            a,
+            b,
+            |x| self.solve_inplace_vec(x),
+            |x| self.solve_transpose_inplace_vec(x),
+            max_refinements,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_condition {
+    use super::*;
+    use crate::algebra::ndmethods::{create_identity_matrix, matrix_mult};
+    use crate::random::generation::{generate_random_matrix, generate_seeded_matrix};
+
+    const SEED: u64 = 39;
+
+    fn exact_condition(a: &NdArray) -> f32 {
+        let n = a.dims[0];
+        let lu = LuPivotDecompose::new(a.clone(), &mut vec![0f32; n]);
+        let mut inverse = create_identity_matrix(n);
+        lu.solve_inplace(&mut inverse);
+        one_norm(a) * one_norm(&inverse)
+    }
+
+    #[test]
+    fn test_condition_estimates() {
+        let n = 8;
+        let a = generate_random_matrix(n, n);
+        let exact = exact_condition(&a);
+        let lu = LuPivotDecompose::new(a.clone(), &mut vec![0f32; n]);
+        let qr = QrDecomposition::new(a.clone());
+        for estimate in [lu.condition_estimate(&a), qr.condition_estimate(&a)] {
+            assert!(estimate <= exact * 1.01 && estimate >= exact / 3f32);
+        }
+        // spd through A'A + I
+        let mut spd = matrix_mult(&a.transpose(), &a);
+        (0..n).for_each(|i| spd.data[i * n + i] += 1f32);
+        let estimate = Cholesky::new(spd.clone()).condition_estimate(&spd);
+        let exact = exact_condition(&spd);
+        assert!(estimate <= exact * 1.01 && estimate >= exact / 3f32);
+    }
+    #[test]
+    fn test_refinement_report() {
+        // graded columns make the plain f32 solve lose digits, a fixed draw keeps kappa bounded
+        let n = 10;
+        let mut a = generate_seeded_matrix(n, n, SEED);
+        for i in 0..n {
+            for j in 0..n {
+                a.data[i * n + j] *= 2f32.powi(-(j as i32));
+            }
+        }
+        let truth: Vec<f32> = (0..n).map(|i| 1f32 + i as f32).collect();
+        let b: Vec<f32> = a
+            .data
+            .chunks(n)
+            .map(|row| {
+                row.iter()
+                    .zip(&truth)
+                    .map(|(&r, &t)| r as f64 * t as f64)
+                    .sum::<f64>() as f32
+            })
+            .collect();
+        let lu = LuPivotDecompose::new(a.clone(), &mut vec![0f32; n]);
+        let plain = lu.solve_with_report(&a, &b, 0);
+        let refined = lu.solve_with_report(&a, &b, 10);
+        assert_eq!(plain.refinements, 0);
+        assert!(refined.refinements > 0);
+        assert!(refined.componentwise_backward_error <= plain.componentwise_backward_error);
+        assert!(refined.normwise_backward_error < 1e-6);
+        let error = refined
+            .x
+            .iter()
+            .zip(&truth)
+            .fold(0f32, |m, (x, t)| m.max((x - t).abs()))
+            / inf_norm(&refined.x);
+        assert!(error <= refined.forward_error_bound.max(1e-6));
+    }
+}
@@ -0,0 +1,400 @@
+use crate::decomposition::schur::{SchurDecomp, block_eigenvalue, real_schur, schur_blocks};
+use crate::structure::ndarray::NdArray;
+use crate::structure::ndsignal::{Complex, NdSignal};
+
+const RADIX: f64 = 2.0;
+
+/// Eigen
+/// eigendecomposition of a general real matrix AX = XL and Y^H A = L Y^H
+///
+/// * values: eigenvalues in schur order, conjugate pairs are adjacent with the positive imaginary part first
+/// * right: unit right eigenvectors stored as columns ~ n x n
+/// * left: unit left eigenvectors stored as columns ~ n x n
+/// * schur: real schur form of the balanced matrix D^-1 A D with rotation Q, QD^-1ADQ' = T
+/// * scale: diagonal of the balancing matrix D, ones when balancing is off
+/// * iterations: francis double shift steps
+/// * converged: false if the iteration budget ran out before full deflation
+pub struct Eigen {
+    pub values: Vec<Complex>,
+    pub right: NdSignal,
+    pub left: NdSignal,
+    pub schur: SchurDecomp,
+    pub scale: Vec<f32>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+type C64 = (f64, f64);
+
+fn cmul(a: C64, b: C64) -> C64 {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+fn cdiv(a: C64, b: C64) -> C64 {
+    // smith's division
+    if b.0.abs() >= b.1.abs() {
+        let r = b.1 / b.0;
+        let d = b.0 + b.1 * r;
+        ((a.0 + a.1 * r) / d, (a.1 - a.0 * r) / d)
+    } else {
+        let r = b.0 / b.1;
+        let d = b.0 * r + b.1;
+        ((a.0 * r + a.1) / d, (a.1 * r - a.0) / d)
+    }
+}
+
+/// balance
+/// parlett reinsch scaling by powers of the radix, A <- D^-1 A D
+///
+/// * a: square matrix data, overwritten
+/// * n: number of rows
+///
+/// returns the diagonal of D
+fn balance(a: &mut [f64], n: usize) -> Vec<f64> {
+    let mut scale = vec![1f64; n];
+    let mut done = false;
+    while !done {
+        done = true;
+        for i in 0..n {
+            let (mut c, mut r) = (0f64, 0f64);
+            for j in (0..n).filter(|&j| j != i) {
+                c += a[j * n + i].abs();
+                r += a[i * n + j].abs();
+            }
+            if c == 0f64 || r == 0f64 {
+                continue;
+            }
+            let s = c + r;
+            let mut f = 1f64;
+            while c < r / RADIX {
+                f *= RADIX;
+                c *= RADIX * RADIX;
+            }
+            while c >= r * RADIX {
+                f /= RADIX;
+                c /= RADIX * RADIX;
+            }
+            if (c + r) / f < 0.95 * s {
+                done = false;
+                scale[i] *= f;
+                for j in 0..n {
+                    a[i * n + j] /= f;
+                    a[j * n + i] *= f;
+                }
+            }
+        }
+    }
+    scale
+}
+
+// solves the 1x1 or 2x2 system (M - lambda)x = rhs, perturbing singular pivots
+fn solve_block(m: [[f64; 2]; 2], size: usize, lambda: C64, rhs: [C64; 2], small: f64) -> [C64; 2] {
+    let shift = |x: f64| (x - lambda.0, -lambda.1);
+    let nonzero = |x: C64| {
+        if x.0 == 0f64 && x.1 == 0f64 {
+            (small, 0f64)
+        } else {
+            x
+        }
+    };
+    if size == 1 {
+        return [cdiv(rhs[0], nonzero(shift(m[0][0]))), (0f64, 0f64)];
+    }
+    let (a, d) = (shift(m[0][0]), shift(m[1][1]));
+    let (b, c) = ((m[0][1], 0f64), (m[1][0], 0f64));
+    let det = cmul(a, d);
+    let bc = cmul(b, c);
+    let det = nonzero((det.0 - bc.0, det.1 - bc.1));
+    let x0 = cmul(d, rhs[0]);
+    let y0 = cmul(b, rhs[1]);
+    let x1 = cmul(a, rhs[1]);
+    let y1 = cmul(c, rhs[0]);
+    [
+        cdiv((x0.0 - y0.0, x0.1 - y0.1), det),
+        cdiv((x1.0 - y1.0, x1.1 - y1.1), det),
+    ]
+}
+
+fn block_matrix(t: &[f64], n: usize, s: usize, transpose: bool) -> [[f64; 2]; 2] {
+    let at = |i: usize, j: usize| if i < n && j < n { t[i * n + j] } else { 0f64 };
+    if transpose {
+        [[at(s, s), at(s + 1, s)], [at(s, s + 1), at(s + 1, s + 1)]]
+    } else {
+        [[at(s, s), at(s, s + 1)], [at(s + 1, s), at(s + 1, s + 1)]]
+    }
+}
+
+/// right_vector
+/// back substitution for (T - lambda)x = 0 on the quasi triangular T
+///
+/// * t: real schur form
+/// * n: number of rows
+/// * blocks: diagonal blocks of T
+/// * b: index of the block owning lambda
+/// * lambda: eigenvalue with non-negative imaginary part
+fn right_vector(
+    t: &[f64],
+    n: usize,
+    blocks: &[(usize, usize)],
+    b: usize,
+    lambda: C64,
+    small: f64,
+) -> Vec<C64> {
+    let mut x = vec![(0f64, 0f64); n];
+    let (p, size) = blocks[b];
+    if size == 1 {
+        x[p] = (1f64, 0f64);
+    } else {
+        // (B - lambda)[b, lambda - a]' = 0
+        x[p] = (t[p * n + p + 1], 0f64);
+        x[p + 1] = (lambda.0 - t[p * n + p], lambda.1);
+    }
+    let last = p + size;
+    for &(s, size) in blocks[..b].iter().rev() {
+        let mut rhs = [(0f64, 0f64); 2];
+        for (r, value) in rhs.iter_mut().enumerate().take(size) {
+            for (j, xj) in x.iter().enumerate().take(last).skip(s + size) {
+                let tij = t[(s + r) * n + j];
+                value.0 -= tij * xj.0;
+                value.1 -= tij * xj.1;
+            }
+        }
+        let m = block_matrix(t, n, s, false);
+        let solution = solve_block(m, size, lambda, rhs, small);
+        x[s..s + size].copy_from_slice(&solution[..size]);
+    }
+    x
+}
+
+/// left_vector
+/// forward substitution for w'(T - lambda) = 0 on the quasi triangular T
+///
+/// * t: real schur form
+/// * n: number of rows
+/// * blocks: diagonal blocks of T
+/// * b: index of the block owning lambda
+/// * lambda: eigenvalue with non-negative imaginary part
+fn left_vector(
+    t: &[f64],
+    n: usize,
+    blocks: &[(usize, usize)],
+    b: usize,
+    lambda: C64,
+    small: f64,
+) -> Vec<C64> {
+    let mut w = vec![(0f64, 0f64); n];
+    let (p, size) = blocks[b];
+    if size == 1 {
+        w[p] = (1f64, 0f64);
+    } else {
+        // [c, lambda - a](B - lambda) = 0
+        w[p] = (t[(p + 1) * n + p], 0f64);
+        w[p + 1] = (lambda.0 - t[p * n + p], lambda.1);
+    }
+    for &(s, size) in &blocks[b + 1..] {
+        let mut rhs = [(0f64, 0f64); 2];
+        for (r, value) in rhs.iter_mut().enumerate().take(size) {
+            for (j, wj) in w.iter().enumerate().take(s).skip(p) {
+                let tji = t[j * n + s + r];
+                value.0 -= tji * wj.0;
+                value.1 -= tji * wj.1;
+            }
+        }
+        let m = block_matrix(t, n, s, true);
+        let solution = solve_block(m, size, lambda, rhs, small);
+        w[s..s + size].copy_from_slice(&solution[..size]);
+    }
+    w
+}
+
+// x <- diag(scale) R'x normalized to a unit vector
+fn transform(r: &[f32], scale: &[f64], x: &[C64], n: usize) -> Vec<C64> {
+    let mut y: Vec<C64> = (0..n)
+        .map(|i| {
+            let (re, im) = x.iter().enumerate().fold((0f64, 0f64), |acc, (j, xj)| {
+                let rji = r[j * n + i] as f64;
+                (acc.0 + rji * xj.0, acc.1 + rji * xj.1)
+            });
+            (re * scale[i], im * scale[i])
+        })
+        .collect();
+    let norm = y.iter().map(|v| v.0 * v.0 + v.1 * v.1).sum::<f64>().sqrt();
+    if norm > 0f64 {
+        for v in y.iter_mut() {
+            *v = (v.0 / norm, v.1 / norm);
+        }
+    }
+    y
+}
+
+impl Eigen {
+    pub fn compute(a: &NdArray) -> Self {
+        Self::compute_with(a, true)
+    }
+    /// compute_with
+    ///
+    /// * a: square matrix
+    /// * balance: scale rows and cols before the reduction to improve the accuracy of small eigenvalues
+    pub fn compute_with(a: &NdArray, balanced: bool) -> Self {
+        let n = a.dims[0];
+        debug_assert_eq!(n, a.dims[1], "eigen requires a square matrix");
+        let mut h: Vec<f64> = a.data.iter().map(|&x| x as f64).collect();
+        let scale = if balanced {
+            balance(&mut h, n)
+        } else {
+            vec![1f64; n]
+        };
+        let balanced = NdArray::new(vec![n, n], h.iter().map(|&x| x as f32).collect());
+        // QD^-1ADQ' = T
+        let (mut schur, iterations, converged) = real_schur(&balanced);
+        let blocks = schur_blocks(&mut schur.kernel.data, n);
+        let t: Vec<f64> = schur.kernel.data.iter().map(|&x| x as f64).collect();
+        let small = f64::EPSILON
+            * t.iter()
+                .map(|x| x.abs())
+                .sum::<f64>()
+                .max(f64::MIN_POSITIVE);
+        let inverse: Vec<f64> = scale.iter().map(|s| 1f64 / s).collect();
+        let rotation = &schur.rotation.data;
+        let mut values = Vec::with_capacity(n);
+        let mut right = vec![Complex::zero(); n * n];
+        let mut left = vec![Complex::zero(); n * n];
+        let mut col = 0;
+        for (b, &(p, size)) in blocks.iter().enumerate() {
+            let lambda = block_eigenvalue(&schur.kernel.data, n, p, size);
+            let lambda = (lambda.real() as f64, lambda.imag() as f64);
+            let x = transform(
+                rotation,
+                &scale,
+                &right_vector(&t, n, &blocks, b, lambda, small),
+                n,
+            );
+            // y = conj(D^-1 R'w) with w'T = lambda w'
+            let w = transform(
+                rotation,
+                &inverse,
+                &left_vector(&t, n, &blocks, b, lambda, small),
+                n,
+            );
+            for k in 0..size {
+                let sign = if k == 0 { 1f64 } else { -1f64 };
+                values.push(Complex::new(lambda.0 as f32, (sign * lambda.1) as f32));
+                for i in 0..n {
+                    right[i * n + col] = Complex::new(x[i].0 as f32, (sign * x[i].1) as f32);
+                    left[i * n + col] = Complex::new(w[i].0 as f32, (-sign * w[i].1) as f32);
+                }
+                col += 1;
+            }
+        }
+        Self {
+            values,
+            right: NdSignal::new(vec![n, n], right),
+            left: NdSignal::new(vec![n, n], left),
+            schur,
+            scale: scale.iter().map(|&s| s as f32).collect(),
+            iterations,
+            converged,
+        }
+    }
+}
+
+impl Eigen {
+    pub fn condition_numbers(&self) -> Vec<f32> {
+        // 1 / |y^H x| for unit left and right eigenvectors
+        let n = self.values.len();
+        (0..n)
+            .map(|k| {
+                let mut dot = Complex::zero();
+                for i in 0..n {
+                    dot += self.left.data[i * n + k].conj() * self.right.data[i * n + k];
+                }
+                let s = dot.norm();
+                if s > 0f32 { 1f32 / s } else { f32::INFINITY }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_general_eigen {
+    use super::*;
+    use crate::random::generation::generate_random_matrix;
+
+    // max_k ||Ax - lambda x|| and max_k ||y^H A - lambda y^H||
+    fn residuals(a: &NdArray, eig: &Eigen) -> (f32, f32) {
+        let n = a.dims[0];
+        let (mut right, mut left) = (0f32, 0f32);
+        for (k, &lambda) in eig.values.iter().enumerate() {
+            for i in 0..n {
+                let mut ax = Complex::zero();
+                let mut ya = Complex::zero();
+                for j in 0..n {
+                    let mut x = eig.right.data[j * n + k];
+                    x.scale(a.data[i * n + j]);
+                    ax += x;
+                    let mut y = eig.left.data[j * n + k].conj();
+                    y.scale(a.data[j * n + i]);
+                    ya += y;
+                }
+                let rx = ax - lambda * eig.right.data[i * n + k];
+                let ry = ya - lambda * eig.left.data[i * n + k].conj();
+                right = right.max(rx.norm());
+                left = left.max(ry.norm());
+            }
+        }
+        (right, left)
+    }
+    #[test]
+    fn test_general_rotation() {
+        // eigenvalues cos(t) +- i sin(t)
+        let (c, s) = (0.6f32, 0.8f32);
+        let a = NdArray::new(vec![2, 2], vec![c, -s, s, c]);
+        let eig = Eigen::compute(&a);
+        assert!(eig.converged);
+        assert!((eig.values[0].real() - c).abs() < 1e-5);
+        assert!((eig.values[0].imag() - s).abs() < 1e-5);
+        assert!((eig.values[1].imag() + s).abs() < 1e-5);
+        let (right, left) = residuals(&a, &eig);
+        assert!(right < 1e-4 && left < 1e-4);
+        // normal matrix is perfectly conditioned
+        assert!(
+            eig.condition_numbers()
+                .iter()
+                .all(|&c| (c - 1f32).abs() < 1e-3)
+        );
+    }
+    #[test]
+    fn test_general_random() {
+        for n in [1, 3, 8, 17, 32] {
+            let a = generate_random_matrix(n, n);
+            for balanced in [true, false] {
+                let eig = Eigen::compute_with(&a, balanced);
+                assert!(eig.converged);
+                assert_eq!(eig.values.len(), n);
+                // conjugate pairs are adjacent
+                let trace: f32 = (0..n).map(|i| a.data[i * n + i]).sum();
+                let sum: f32 = eig.values.iter().map(|v| v.real()).sum();
+                let imag: f32 = eig.values.iter().map(|v| v.imag()).sum();
+                assert!((trace - sum).abs() < 1e-2 && imag.abs() < 1e-2);
+                let (right, left) = residuals(&a, &eig);
+                assert!(right < 1e-2 && left < 1e-2);
+                assert!(eig.condition_numbers().iter().all(|&c| c >= 1f32 - 1e-3));
+            }
+        }
+    }
+    #[test]
+    fn test_general_balance() {
+        // badly scaled upper triangular matrix with eigenvalues 1, 2, 3
+        let a = NdArray::new(
+            vec![3, 3],
+            vec![1f32, 1e4, 1e-4, 0f32, 2f32, 1e6, 0f32, 0f32, 3f32],
+        );
+        let eig = Eigen::compute(&a);
+        let mut values: Vec<f32> = eig.values.iter().map(|v| v.real()).collect();
+        values.sort_by(|x, y| x.total_cmp(y));
+        assert!((values[0] - 1f32).abs() < 1e-4);
+        assert!((values[1] - 2f32).abs() < 1e-4);
+        assert!((values[2] - 3f32).abs() < 1e-4);
+        assert!(eig.scale.iter().all(|s| s.log2().fract() == 0f32));
+    }
+}
@@ -0,0 +1,541 @@
+use crate::algebra::ndmethods::symmetrize;
+use crate::decomposition::cholesky::Cholesky;
+use crate::decomposition::eigen::symmetric::{EigenRange, SymmetricEigen};
+use crate::decomposition::householder::{householder, reflect_cols, reflect_rows};
+use crate::structure::ndarray::NdArray;
+use crate::structure::ndsignal::Complex;
+
+const MAX_ITERS: usize = 30;
+const EXCEPTIONAL: usize = 10;
+
+/// GeneralizedEigen
+/// generalized schur decomposition of the pencil (A, B), Q'AZ = S and Q'BZ = T
+/// the eigenvalues solve det(beta A - alpha B) = 0
+///
+/// * alpha: numerators, conjugate pairs are adjacent with the positive imaginary part first
+/// * beta: non-negative denominators, zero for infinite eigenvalues
+/// * s: upper quasi triangular factor of A
+/// * t: upper triangular factor of B
+/// * q: left orthogonal factor
+/// * z: right orthogonal factor
+/// * iterations: QZ double shift steps
+/// * converged: false if the iteration budget ran out before full deflation
+pub struct GeneralizedEigen {
+    pub alpha: Vec<Complex>,
+    pub beta: Vec<f32>,
+    pub s: NdArray,
+    pub t: NdArray,
+    pub q: NdArray,
+    pub z: NdArray,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+// (c, s) with [c s; -s c][a; b] = [r; 0]
+fn givens(a: f64, b: f64) -> (f64, f64) {
+    if b == 0f64 {
+        return (1f64, 0f64);
+    }
+    let r = a.hypot(b);
+    (a / r, b / r)
+}
+
+// rows i, j of m <- G rows i, j on cols
+fn rotate_rows(
+    m: &mut [f64],
+    n: usize,
+    i: usize,
+    j: usize,
+    c: f64,
+    s: f64,
+    cols: std::ops::Range<usize>,
+) {
+    for k in cols {
+        let (x, y) = (m[i * n + k], m[j * n + k]);
+        m[i * n + k] = c * x + s * y;
+        m[j * n + k] = c * y - s * x;
+    }
+}
+
+// cols i, j of m <- cols i, j G' on rows
+fn rotate_cols(
+    m: &mut [f64],
+    n: usize,
+    i: usize,
+    j: usize,
+    c: f64,
+    s: f64,
+    rows: std::ops::Range<usize>,
+) {
+    for k in rows {
+        let (x, y) = (m[k * n + i], m[k * n + j]);
+        m[k * n + i] = c * x + s * y;
+        m[k * n + j] = c * y - s * x;
+    }
+}
+
+/// hessenberg_triangular
+/// reduces the pencil to H = Q'AZ upper hessenberg and T = Q'BZ upper triangular
+///
+/// * h: A, overwritten by H
+/// * t: B, overwritten by T
+/// * q: left rotation should be identity on coldstart
+/// * z: right rotation should be identity on coldstart
+/// * n: number of rows
+fn hessenberg_triangular(h: &mut [f64], t: &mut [f64], q: &mut [f64], z: &mut [f64], n: usize) {
+    // B = QR by householder
+    let mut x = vec![0f64; n];
+    let mut v = vec![0f64; n];
+    for k in 0..n.saturating_sub(1) {
+        let len = n - k;
+        for i in 0..len {
+            x[i] = t[(k + i) * n + k];
+        }
+        let tau = householder(&x[..len], &mut v);
+        if tau == 0f64 {
+            continue;
+        }
+        reflect_rows(t, n, &v[..len], tau, k, k..n);
+        reflect_rows(h, n, &v[..len], tau, k, 0..n);
+        reflect_cols(q, n, &v[..len], tau, k, 0..n);
+        for i in k + 1..n {
+            t[i * n + k] = 0f64;
+        }
+    }
+    // zero H below the subdiagonal, restoring T from the right
+    for j in 0..n.saturating_sub(2) {
+        for i in (j + 2..n).rev() {
+            let (c, s) = givens(h[(i - 1) * n + j], h[i * n + j]);
+            rotate_rows(h, n, i - 1, i, c, s, j..n);
+            rotate_rows(t, n, i - 1, i, c, s, i - 1..n);
+            rotate_cols(q, n, i - 1, i, c, s, 0..n);
+            h[i * n + j] = 0f64;
+            // T[i, i - 1] fill
+            let (c, s) = givens(t[i * n + i], t[i * n + i - 1]);
+            rotate_cols(t, n, i, i - 1, c, s, 0..i + 1);
+            rotate_cols(h, n, i, i - 1, c, s, 0..n);
+            rotate_cols(z, n, i, i - 1, c, s, 0..n);
+            t[i * n + i - 1] = 0f64;
+        }
+    }
+}
+
+// moves a zero T[j, j] to the bottom of the window and deflates an infinite eigenvalue
+fn chase_zero(
+    h: &mut [f64],
+    t: &mut [f64],
+    q: &mut [f64],
+    z: &mut [f64],
+    n: usize,
+    l: usize,
+    j: usize,
+    hi: usize,
+) {
+    for k in j..hi {
+        let (c, s) = givens(t[k * n + k + 1], t[(k + 1) * n + k + 1]);
+        rotate_rows(t, n, k, k + 1, c, s, k + 1..n);
+        rotate_rows(h, n, k, k + 1, c, s, k.saturating_sub(1).max(l)..n);
+        rotate_cols(q, n, k, k + 1, c, s, 0..n);
+        t[(k + 1) * n + k + 1] = 0f64;
+        if k > l {
+            // H[k + 1, k - 1] fill
+            let (c, s) = givens(h[(k + 1) * n + k], h[(k + 1) * n + k - 1]);
+            rotate_cols(h, n, k, k - 1, c, s, 0..(k + 2).min(n));
+            rotate_cols(t, n, k, k - 1, c, s, 0..k + 1);
+            rotate_cols(z, n, k, k - 1, c, s, 0..n);
+            h[(k + 1) * n + k - 1] = 0f64;
+        }
+    }
+    let (c, s) = givens(h[hi * n + hi], h[hi * n + hi - 1]);
+    rotate_cols(h, n, hi, hi - 1, c, s, 0..hi + 1);
+    rotate_cols(t, n, hi, hi - 1, c, s, 0..hi + 1);
+    rotate_cols(z, n, hi, hi - 1, c, s, 0..n);
+    h[hi * n + hi - 1] = 0f64;
+}
+
+// inverse of the upper triangular block T[p..p + m, p..p + m]
+fn triangular_inverse(t: &[f64], n: usize, p: usize, m: usize) -> [[f64; 3]; 3] {
+    let mut inv = [[0f64; 3]; 3];
+    for j in 0..m {
+        inv[j][j] = 1f64 / t[(p + j) * n + p + j];
+        for i in (0..j).rev() {
+            let s: f64 = (i + 1..=j)
+                .map(|k| t[(p + i) * n + p + k] * inv[k][j])
+                .sum();
+            inv[i][j] = -s / t[(p + i) * n + p + i];
+        }
+    }
+    inv
+}
+
+/// qz
+/// implicit double shift QZ on a hessenberg triangular pencil
+///
+/// * h: upper hessenberg, overwritten by the quasi triangular S
+/// * t: upper triangular, stays upper triangular
+/// * q: accumulated left rotation
+/// * z: accumulated right rotation
+/// * n: number of rows
+///
+/// returns the number of double shift steps and whether every block converged
+fn qz(h: &mut [f64], t: &mut [f64], q: &mut [f64], z: &mut [f64], n: usize) -> (usize, bool) {
+    let hnorm = h
+        .iter()
+        .map(|x| x.abs())
+        .sum::<f64>()
+        .max(f64::MIN_POSITIVE);
+    let tnorm = t
+        .iter()
+        .map(|x| x.abs())
+        .sum::<f64>()
+        .max(f64::MIN_POSITIVE);
+    let (mut iters, mut total) = (0, 0);
+    let mut v = [0f64; 3];
+    let mut hi = n.saturating_sub(1);
+    while hi > 0 {
+        let mut l = hi;
+        while l > 0 {
+            let s = h[(l - 1) * n + l - 1].abs() + h[l * n + l].abs();
+            let s = if s == 0f64 { hnorm } else { s };
+            if h[l * n + l - 1].abs() <= f64::EPSILON * s {
+                break;
+            }
+            l -= 1;
+        }
+        if l > 0 {
+            h[l * n + l - 1] = 0f64;
+        }
+        if l == hi {
+            hi -= 1;
+            iters = 0;
+            continue;
+        }
+        // infinite eigenvalues
+        if let Some(j) = (l..=hi).find(|&j| t[j * n + j].abs() <= f64::EPSILON * tnorm) {
+            t[j * n + j] = 0f64;
+            chase_zero(h, t, q, z, n, l, j, hi);
+            continue;
+        }
+        if l + 1 == hi {
+            hi = hi.saturating_sub(2);
+            iters = 0;
+            continue;
+        }
+        if total >= MAX_ITERS * n {
+            return (total, false);
+        }
+        iters += 1;
+        total += 1;
+        // shifts from the trailing 2x2 of M = HT^-1
+        let p = hi - 1;
+        let ti = triangular_inverse(t, n, p - 1, 3);
+        let m = |i: usize, j: usize| -> f64 {
+            (i.max(1) - 1..=j)
+                .map(|k| h[(p - 1 + i) * n + p - 1 + k] * ti[k][j])
+                .sum()
+        };
+        let (m11, m12, m21, m22) = (m(1, 1), m(1, 2), m(2, 1), m(2, 2));
+        let (s, d) = if iters % EXCEPTIONAL == 0 {
+            let w = m21.abs() + (h[p * n + p - 1] * ti[0][0]).abs();
+            (1.5 * w, w * w)
+        } else {
+            (m11 + m22, m11 * m22 - m12 * m21)
+        };
+        // first column of (M - s1)(M - s2)
+        let ti = triangular_inverse(t, n, l, 2);
+        let (h00, h01, h10, h11) = (
+            h[l * n + l],
+            h[l * n + l + 1],
+            h[(l + 1) * n + l],
+            h[(l + 1) * n + l + 1],
+        );
+        let m00 = h00 * ti[0][0];
+        let m01 = h00 * ti[0][1] + h01 * ti[1][1];
+        let m10 = h10 * ti[0][0];
+        let m11 = h10 * ti[0][1] + h11 * ti[1][1];
+        let m21 = h[(l + 2) * n + l + 1] * ti[1][1];
+        let mut x = [
+            m00 * m00 + m01 * m10 - s * m00 + d,
+            m10 * (m00 + m11 - s),
+            m10 * m21,
+        ];
+        for k in l..hi {
+            let size = if k + 2 <= hi { 3 } else { 2 };
+            let tau = householder(&x[..size], &mut v);
+            if tau != 0f64 {
+                let col = if k > l { k - 1 } else { l };
+                reflect_rows(h, n, &v[..size], tau, k, col..n);
+                reflect_rows(t, n, &v[..size], tau, k, k..n);
+                reflect_cols(q, n, &v[..size], tau, k, 0..n);
+                if k > l {
+                    for i in k + 1..k + size {
+                        h[i * n + k - 1] = 0f64;
+                    }
+                }
+            }
+            // restore T, the last row of the bulge first
+            let last = k + size - 1;
+            let row: Vec<f64> = (k..=last).rev().map(|c| t[last * n + c]).collect();
+            let tau = householder(&row, &mut v);
+            if tau != 0f64 {
+                v[..size].reverse();
+                let rows = 0..(k + size + 1).min(hi + 1);
+                reflect_cols(h, n, &v[..size], tau, k, rows);
+                reflect_cols(t, n, &v[..size], tau, k, 0..last + 1);
+                reflect_cols(z, n, &v[..size], tau, k, 0..n);
+                for c in k..last {
+                    t[last * n + c] = 0f64;
+                }
+            }
+            if size == 3 {
+                let (c, s) = givens(t[(k + 1) * n + k + 1], t[(k + 1) * n + k]);
+                rotate_cols(h, n, k + 1, k, c, s, 0..(k + 4).min(hi + 1));
+                rotate_cols(t, n, k + 1, k, c, s, 0..k + 2);
+                rotate_cols(z, n, k + 1, k, c, s, 0..n);
+                t[(k + 1) * n + k] = 0f64;
+            }
+            if k + 1 < hi {
+                x[0] = h[(k + 1) * n + k];
+                x[1] = h[(k + 2) * n + k];
+                x[2] = if k + 3 <= hi {
+                    h[(k + 3) * n + k]
+                } else {
+                    0f64
+                };
+            }
+        }
+    }
+    (total, true)
+}
+
+// generalized eigenvalues (alpha, beta) of the 2x2 diagonal block at i with beta >= 0,
+// det(beta S - alpha T) = qa alpha^2 - qb alpha beta + qc beta^2 and qa = 0 gives beta = 0
+fn pencil_2x2(h: &[f64], t: &[f64], n: usize, i: usize) -> [(Complex, f32); 2] {
+    let (s00, s01, s10, s11) = (
+        h[i * n + i],
+        h[i * n + i + 1],
+        h[(i + 1) * n + i],
+        h[(i + 1) * n + i + 1],
+    );
+    let (t00, t01, t11) = (t[i * n + i], t[i * n + i + 1], t[(i + 1) * n + i + 1]);
+    let qa = t00 * t11;
+    let half = 0.5 * (s00 * t11 + s11 * t00 - s10 * t01);
+    let qc = s00 * s11 - s10 * s01;
+    let disc = half * half - qa * qc;
+    let pair = |mut alpha: Complex, beta: f64| {
+        let sign = if beta < 0f64 { -1f32 } else { 1f32 };
+        alpha.scale(sign);
+        (alpha, sign * beta as f32)
+    };
+    if disc < 0f64 {
+        // qa qc > half^2 so qa != 0
+        let sign = qa.signum();
+        let (re, im) = ((sign * half) as f32, (-disc).sqrt() as f32);
+        let beta = qa.abs() as f32;
+        return [(Complex::new(re, im), beta), (Complex::new(re, -im), beta)];
+    }
+    // roots w / qa and qc / w without cancellation
+    let w = half + half.signum() * disc.sqrt();
+    [
+        pair(Complex::new(w as f32, 0f32), qa),
+        pair(Complex::new(qc as f32, 0f32), w),
+    ]
+}
+
+impl GeneralizedEigen {
+    pub fn compute(a: &NdArray, b: &NdArray) -> Self {
+        let n = a.dims[0];
+        debug_assert_eq!(
+            a.dims, b.dims,
+            "generalized eigen requires square matrices of equal size"
+        );
+        let mut h: Vec<f64> = a.data.iter().map(|&x| x as f64).collect();
+        let mut t: Vec<f64> = b.data.iter().map(|&x| x as f64).collect();
+        let mut q = vec![0f64; n * n];
+        let mut z = vec![0f64; n * n];
+        for i in 0..n {
+            q[i * n + i] = 1f64;
+            z[i * n + i] = 1f64;
+        }
+        hessenberg_triangular(&mut h, &mut t, &mut q, &mut z, n);
+        let (iterations, converged) = qz(&mut h, &mut t, &mut q, &mut z, n);
+        let mut alpha = Vec::with_capacity(n);
+        let mut beta = Vec::with_capacity(n);
+        let mut i = 0;
+        while i < n {
+            if i + 1 == n || h[(i + 1) * n + i] == 0f64 {
+                // keep beta non-negative
+                let sign = if t[i * n + i] < 0f64 { -1f64 } else { 1f64 };
+                alpha.push(Complex::new((sign * h[i * n + i]) as f32, 0f32));
+                beta.push((sign * t[i * n + i]) as f32);
+                i += 1;
+                continue;
+            }
+            for (alpha_i, beta_i) in pencil_2x2(&h, &t, n, i) {
+                alpha.push(alpha_i);
+                beta.push(beta_i);
+            }
+            i += 2;
+        }
+        let f32s =
+            |x: Vec<f64>| NdArray::new(vec![n, n], x.into_iter().map(|v| v as f32).collect());
+        Self {
+            alpha,
+            beta,
+            s: f32s(h),
+            t: f32s(t),
+            q: f32s(q),
+            z: f32s(z),
+            iterations,
+            converged,
+        }
+    }
+    pub fn values(&self) -> Vec<Complex> {
+        // alpha / beta, infinite eigenvalues map to inf
+        self.alpha
+            .iter()
+            .zip(&self.beta)
+            .map(|(alpha, &beta)| {
+                if beta == 0f32 {
+                    Complex::new(f32::INFINITY, 0f32)
+                } else {
+                    let mut lambda = *alpha;
+                    lambda.scale(1f32 / beta);
+                    lambda
+                }
+            })
+            .collect()
+    }
+}
+
+impl SymmetricEigen {
+    /// compute_definite
+    /// symmetric definite pencil Ax = lambda Bx through the cholesky reduction B = LL'
+    /// and the symmetric eigenproblem of C = L^-1 A L^-T, the eigenvectors are B orthonormal
+    ///
+    /// * a: symmetric matrix
+    /// * b: symmetric positive definite matrix
+    /// * range: which eigenpairs of the ascending spectrum are returned
+    pub fn compute_definite(a: &NdArray, b: &NdArray, range: EigenRange) -> Self {
+        let n = a.dims[0];
+        debug_assert_eq!(
+            a.dims, b.dims,
+            "definite pencil requires square matrices of equal size"
+        );
+        let cholesky = Cholesky::new(b.clone());
+        // A symmetric so the rows of A L^-T are L^-1 on the rows of A, C = L^-1 (A L^-T)
+        let mut c = a.clone();
+        for _ in 0..2 {
+            c.data
+                .chunks_mut(n)
+                .for_each(|row| cholesky.solve_lower_inplace_vec(row));
+            c = c.transpose();
+        }
+        symmetrize(&mut c);
+        let mut eig = Self::compute_range(&c, range);
+        // X = L^-T V on the rows of V'
+        let mut x = eig.vectors.transpose();
+        x.data
+            .chunks_mut(n)
+            .for_each(|col| cholesky.solve_upper_inplace_vec(col));
+        eig.vectors = x.transpose();
+        eig
+    }
+}
+
+#[cfg(test)]
+mod test_generalized_eigen {
+    use super::*;
+    use crate::algebra::ndmethods::{create_identity_matrix, matrix_mult};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::{generate_random_matrix, generate_random_symetric};
+
+    fn spd(n: usize) -> NdArray {
+        let x = generate_random_matrix(n, n);
+        let mut b = matrix_mult(&x, &x.transpose());
+        for i in 0..n {
+            b.data[i * n + i] += n as f32;
+        }
+        b
+    }
+    #[test]
+    fn test_qz_decomposition() {
+        for n in [1, 2, 5, 12, 20] {
+            let a = generate_random_matrix(n, n);
+            let b = generate_random_matrix(n, n);
+            let eig = GeneralizedEigen::compute(&a, &b);
+            assert!(eig.converged);
+            // Q'AZ = S, Q'BZ = T
+            let qt = eig.q.transpose();
+            let s = matrix_mult(&matrix_mult(&qt, &a), &eig.z);
+            let t = matrix_mult(&matrix_mult(&qt, &b), &eig.z);
+            assert!(approx_vector_eq(&s.data, &eig.s.data));
+            assert!(approx_vector_eq(&t.data, &eig.t.data));
+            let identity = create_identity_matrix(n);
+            assert!(approx_vector_eq(
+                &matrix_mult(&qt, &eig.q).data,
+                &identity.data
+            ));
+            for i in 0..n {
+                for j in 0..i {
+                    assert_eq!(eig.t.data[i * n + j], 0f32);
+                    if j + 1 < i {
+                        assert_eq!(eig.s.data[i * n + j], 0f32);
+                    }
+                }
+            }
+            assert!(eig.beta.iter().all(|&beta| beta >= 0f32));
+        }
+    }
+    #[test]
+    fn test_qz_infinite() {
+        // singular B gives one infinite eigenvalue
+        let n = 6;
+        let a = generate_random_matrix(n, n);
+        let mut b = generate_random_matrix(n, n);
+        for j in 0..n {
+            b.data[3 * n + j] = 0f32;
+        }
+        let eig = GeneralizedEigen::compute(&a, &b);
+        assert!(eig.converged);
+        let infinite = eig.beta.iter().filter(|&&beta| beta.abs() < 1e-4).count();
+        assert_eq!(infinite, 1);
+    }
+    #[test]
+    fn test_pencil_block_infinite() {
+        // det(S - lambda T) = -2 - lambda with T singular
+        let h = [1f64, 2f64, 3f64, 4f64];
+        let t = [1f64, 1f64, 0f64, 0f64];
+        let [(a0, b0), (a1, b1)] = pencil_2x2(&h, &t, 2, 0);
+        assert_eq!(b0, 0f32);
+        assert!(a0.real() != 0f32);
+        assert!(b1 > 0f32);
+        assert!((a1.real() / b1 + 2f32).abs() < 1e-6);
+        assert_eq!(a1.imag(), 0f32);
+    }
+    #[test]
+    fn test_qz_definite_agree() {
+        let n = 8;
+        let a = generate_random_symetric(n);
+        let b = spd(n);
+        let eig = SymmetricEigen::compute_definite(&a, &b, EigenRange::All);
+        // AX = BX diag(lambda)
+        let ax = matrix_mult(&a, &eig.vectors);
+        let mut bxl = matrix_mult(&b, &eig.vectors);
+        for i in 0..n {
+            for j in 0..n {
+                bxl.data[i * n + j] *= eig.values[j];
+            }
+        }
+        assert!(approx_vector_eq(&ax.data, &bxl.data));
+        // X'BX = I
+        let xbx = matrix_mult(&eig.vectors.transpose(), &matrix_mult(&b, &eig.vectors));
+        assert!(approx_vector_eq(&xbx.data, &create_identity_matrix(n).data));
+        // QZ finds the same real spectrum
+        let qz = GeneralizedEigen::compute(&a, &b);
+        let mut values: Vec<f32> = qz.values().iter().map(|v| v.real()).collect();
+        values.sort_by(|x, y| x.total_cmp(y));
+        assert!(qz.values().iter().all(|v| v.imag().abs() < 1e-3));
+        assert!(approx_vector_eq(&values, &eig.values));
+    }
+}
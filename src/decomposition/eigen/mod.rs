@@ -0,0 +1,4 @@
+pub mod general;
+pub mod generalized;
+pub mod symmetric;
+pub mod tridiagonal;
@@ -0,0 +1,234 @@
+use crate::algebra::ndmethods::{create_identity_vector, matrix_mult};
+use crate::algebra::vector::magnitude;
+use crate::decomposition::eigen::tridiagonal::{bisection, divide_and_conquer};
+use crate::decomposition::eigen::tridiagonal::{inverse_iteration, sturm_count};
+use crate::decomposition::francis::interface::francis_qr_sym;
+use crate::decomposition::francis::primitives::hessenberg;
+use crate::decomposition::jacobi::jacobi_eigen;
+use crate::structure::ndarray::NdArray;
+
+const TOLERANCE: f32 = 4f32 * f32::EPSILON;
+// sweeps per eigenvalue, half are refunded on each deflation
+const MAX_SWEEPS: usize = 30;
+
+/// EigenRange
+/// which eigenpairs of the ascending spectrum are returned
+///
+/// * All: every eigenpair
+/// * Index: eigenpairs lo..hi of the ascending spectrum
+/// * Interval: eigenpairs with values in (lower, upper]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EigenRange {
+    All,
+    Index(usize, usize),
+    Interval(f32, f32),
+}
+
+/// EigenMethod
+/// solver used for the tridiagonal stage
+///
+/// * Qr: implicit shifted QR with accumulated rotations
+/// * DivideAndConquer: cuppen divide and conquer, parallel over subproblems
+/// * Bisection: sturm bisection and inverse iteration on the requested range only
+/// * Jacobi: cyclic jacobi on the full matrix, high relative accuracy without the tridiagonal stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EigenMethod {
+    Qr,
+    DivideAndConquer,
+    Bisection,
+    Jacobi,
+}
+
+/// SymmetricEigen
+/// eigendecomposition of a symmetric matrix A = VDV'
+///
+/// * values: eigenvalues in ascending order
+/// * vectors: orthonormal eigenvectors stored as columns ~ n x k
+/// * iterations: QR sweeps, secular or bisection steps on the tridiagonal
+/// * converged: false if the sweep budget ran out before full deflation
+pub struct SymmetricEigen {
+    pub values: Vec<f32>,
+    pub vectors: NdArray,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+// householder reduction RAR' = T, returns the diagonal and the off diagonal of T
+fn tridiagonal(h: &mut [f32], r: &mut [f32], n: usize) -> (Vec<f32>, Vec<f32>) {
+    let mut p = vec![0f32; n];
+    let mut w = vec![0f32; n];
+    hessenberg(h, Some(r), &mut p, &mut w, n, n, n);
+    let d = (0..n).map(|i| h[i * n + i]).collect();
+    // average the two off diagonals to absorb symmetry drift
+    let e = (1..n)
+        .map(|i| 0.5f32 * (h[(i - 1) * n + i] + h[i * n + i - 1]))
+        .collect();
+    (d, e)
+}
+
+impl SymmetricEigen {
+    pub fn compute(a: &NdArray) -> Self {
+        Self::compute_range(a, EigenRange::All)
+    }
+    pub fn compute_range(a: &NdArray, range: EigenRange) -> Self {
+        Self::compute_with(a, range, EigenMethod::Qr)
+    }
+    pub fn compute_with(a: &NdArray, range: EigenRange, method: EigenMethod) -> Self {
+        let n = a.dims[0];
+        debug_assert_eq!(n, a.dims[1], "symmetric eigen requires a square matrix");
+        let mut h = a.data.clone();
+        let mut r = create_identity_vector(n, n);
+        let diagonal = |h: &[f32]| (0..n).map(|i| h[i * n + i]).collect::<Vec<f32>>();
+        match method {
+            EigenMethod::Qr => {
+                let mut p = vec![0f32; n];
+                let mut w = vec![0f32; n];
+                // deflate against eps * ||A|| alone as in tql2, rotations of the dense
+                // tridiagonal cannot resolve the off diagonals below it
+                let (iterations, converged) = francis_qr_sym(
+                    &mut h,
+                    Some(&mut r),
+                    &mut p,
+                    &mut w,
+                    n,
+                    n,
+                    n,
+                    MAX_SWEEPS,
+                    f32::INFINITY,
+                    TOLERANCE * magnitude(&a.data),
+                );
+                Self::select(&diagonal(&h), &r, n, range, iterations, converged)
+            }
+            EigenMethod::Jacobi => {
+                // jacobi runs on the full matrix without the tridiagonal stage
+                let (sweeps, converged) = jacobi_eigen(&mut h, &mut r, n);
+                Self::select(&diagonal(&h), &r, n, range, sweeps, converged)
+            }
+            EigenMethod::DivideAndConquer => {
+                let (d, e) = tridiagonal(&mut h, &mut r, n);
+                let eig = divide_and_conquer(&d, &e);
+                // eigenvectors of A are R'Z, stored as rows Z'R
+                let r = NdArray::new(vec![n, n], r);
+                let rows = matrix_mult(&eig.vectors.transpose(), &r);
+                let (iterations, converged) = (eig.iterations, eig.converged);
+                Self::select(&eig.values, &rows.data, n, range, iterations, converged)
+            }
+            EigenMethod::Bisection => {
+                let (d, e) = tridiagonal(&mut h, &mut r, n);
+                let (lo, hi) = match range {
+                    EigenRange::All => (0, n),
+                    EigenRange::Index(lo, hi) => (lo.min(n), hi.min(n)),
+                    EigenRange::Interval(lower, upper) => (
+                        sturm_count(&d, &e, lower as f64),
+                        sturm_count(&d, &e, upper as f64),
+                    ),
+                };
+                let (values, steps, converged) = bisection(&d, &e, lo, hi.max(lo));
+                let z = inverse_iteration(&d, &e, &values);
+                let r = NdArray::new(vec![n, n], r);
+                let rows = matrix_mult(&z.transpose(), &r);
+                Self::select(&values, &rows.data, n, EigenRange::All, steps, converged)
+            }
+        }
+    }
+    /// select
+    /// sorts the eigenvalues ascending and keeps the requested range
+    ///
+    /// * d: eigenvalues
+    /// * r: eigenvectors stored as rows ~ k x n
+    /// * n: length of the eigenvectors
+    pub(crate) fn select(
+        d: &[f32],
+        r: &[f32],
+        n: usize,
+        range: EigenRange,
+        iterations: usize,
+        converged: bool,
+    ) -> Self {
+        let count = d.len();
+        let mut order: Vec<usize> = (0..count).collect();
+        order.sort_by(|&i, &j| d[i].total_cmp(&d[j]));
+        let order: Vec<usize> = match range {
+            EigenRange::All => order,
+            EigenRange::Index(lo, hi) => order[lo.min(count)..hi.min(count)].to_vec(),
+            EigenRange::Interval(lower, upper) => order
+                .into_iter()
+                .filter(|&i| d[i] > lower && d[i] <= upper)
+                .collect(),
+        };
+        let k = order.len();
+        let values = order.iter().map(|&i| d[i]).collect();
+        let mut vectors = NdArray::new(vec![n, k], vec![0f32; n * k]);
+        for (j, &src) in order.iter().enumerate() {
+            for i in 0..n {
+                vectors.data[i * k + j] = r[src * n + i];
+            }
+        }
+        Self {
+            values,
+            vectors,
+            iterations,
+            converged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_symmetric_eigen {
+    use super::*;
+    use crate::algebra::ndmethods::{create_identity_matrix, matrix_mult};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_symetric;
+
+    fn check_pairs(a: &NdArray, eig: &SymmetricEigen) {
+        let (n, k) = (a.dims[0], eig.values.len());
+        let av = matrix_mult(a, &eig.vectors);
+        let mut vd = eig.vectors.clone();
+        for i in 0..n {
+            for j in 0..k {
+                vd.data[i * k + j] *= eig.values[j];
+            }
+        }
+        assert!(approx_vector_eq(&av.data, &vd.data));
+        let vtv = matrix_mult(&eig.vectors.transpose(), &eig.vectors);
+        assert!(approx_vector_eq(&vtv.data, &create_identity_matrix(k).data));
+    }
+    #[test]
+    fn test_symmetric_eigen_full() {
+        for n in [1, 2, 5, 16, 40] {
+            let a = generate_random_symetric(n);
+            let eig = SymmetricEigen::compute(&a);
+            assert!(eig.converged);
+            assert!(eig.values.windows(2).all(|w| w[0] <= w[1]));
+            check_pairs(&a, &eig);
+        }
+    }
+    #[test]
+    fn test_symmetric_eigen_range() {
+        let n = 12;
+        let a = generate_random_symetric(n);
+        let all = SymmetricEigen::compute(&a);
+        let index = SymmetricEigen::compute_range(&a, EigenRange::Index(3, 7));
+        assert!(approx_vector_eq(&index.values, &all.values[3..7]));
+        check_pairs(&a, &index);
+        let (lower, upper) = (all.values[1], all.values[5]);
+        let interval = SymmetricEigen::compute_range(&a, EigenRange::Interval(lower, upper));
+        assert_eq!(interval.values.len(), 4);
+        check_pairs(&a, &interval);
+    }
+    #[test]
+    fn test_symmetric_eigen_methods() {
+        let n = 64;
+        let a = generate_random_symetric(n);
+        let qr = SymmetricEigen::compute(&a);
+        let dc = SymmetricEigen::compute_with(&a, EigenRange::All, EigenMethod::DivideAndConquer);
+        assert!(qr.converged && dc.converged);
+        assert!(approx_vector_eq(&dc.values, &qr.values));
+        check_pairs(&a, &dc);
+        let range = EigenRange::Index(n - 5, n);
+        let bisect = SymmetricEigen::compute_with(&a, range, EigenMethod::Bisection);
+        assert!(bisect.converged);
+        assert!(approx_vector_eq(&bisect.values, &qr.values[n - 5..]));
+        check_pairs(&a, &bisect);
+    }
+}
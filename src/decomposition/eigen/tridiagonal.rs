@@ -0,0 +1,610 @@
+use crate::algebra::ndmethods::{create_identity_vector, matrix_mult};
+use crate::algebra::vector::magnitude;
+use crate::decomposition::francis::symmetric::decomp_sym;
+use crate::random::generation::generate_seeded_matrix;
+use crate::structure::ndarray::NdArray;
+use rayon::join;
+
+// subproblems at or below this size are solved by implicit QR
+const LEAF_SIZE: usize = 24;
+// sweeps per eigenvalue, half are refunded on each deflation
+const MAX_SWEEPS: usize = 30;
+const TOLERANCE: f32 = 4f32 * f32::EPSILON;
+const DEFLATION: f64 = 8.0 * f32::EPSILON as f64;
+const MAX_BISECTIONS: usize = 128;
+// rational interpolation steps per secular root, as MAXIT in dlaed4
+const MAX_SECULAR: usize = 30;
+const INVERSE_STEPS: usize = 3;
+// eigenvalues closer than this relative gap are reorthogonalized
+const CLUSTER: f64 = 1e-3;
+// inverse iteration starts from a fixed draw so the vectors are reproducible
+const START_SEED: u64 = 31;
+
+/// TridiagonalEigen
+/// eigenpairs of a symmetric tridiagonal matrix T = ZDZ'
+///
+/// * values: eigenvalues in ascending order
+/// * vectors: eigenvectors stored as columns ~ n x k
+/// * iterations: qr sweeps, secular or bisection steps spent
+/// * converged: false if a leaf ran out of sweeps or a secular root did not converge
+pub struct TridiagonalEigen {
+    pub values: Vec<f32>,
+    pub vectors: NdArray,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+// rotation (i, j, c, s) recorded during deflation of close poles
+type Rotation = (usize, usize, f64, f64);
+
+fn sort_pairs(values: Vec<f64>, vectors: &[f64], n: usize) -> (Vec<f32>, NdArray) {
+    let k = values.len();
+    let mut order: Vec<usize> = (0..k).collect();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]));
+    let mut z = NdArray::new(vec![n, k], vec![0f32; n * k]);
+    for (j, &src) in order.iter().enumerate() {
+        for i in 0..n {
+            z.data[i * k + j] = vectors[i * k + src] as f32;
+        }
+    }
+    (order.iter().map(|&i| values[i] as f32).collect(), z)
+}
+
+/// secular_root
+/// root j of the secular equation f(x) = 1 / rho + sum z[l]^2 / (d[l] - x) in the style
+/// of dlaed4, each step solves a rational model with the two poles around the root and
+/// bisection of the bracket is the safeguard
+///
+/// * d: poles in ascending order
+/// * z: weights of the poles
+/// * rho: positive scalar of the update
+/// * j: index of the root, the last one lies in (d[k - 1], d[k - 1] + rho ||z||^2]
+///
+/// returns the pole the root is measured from, the offset from it, the steps and whether
+/// the root converged
+fn secular_root(d: &[f64], z: &[f64], rho: f64, j: usize) -> (usize, f64, usize, bool) {
+    let k = d.len();
+    if k == 1 {
+        return (0, rho * z[0] * z[0], 0, true);
+    }
+    let last = j + 1 == k;
+    // poles j and j + 1 around the root, the last root sits right of k - 2 and k - 1
+    let left = if last { k - 2 } else { j };
+    // measure from the closer pole so gaps to it keep their relative accuracy
+    let (origin, mut lo, mut hi, mut tau) = if last {
+        let zsq: f64 = z.iter().map(|v| v * v).sum();
+        let hi = rho * zsq * (1f64 + DEFLATION);
+        (j, 0f64, hi, 0.5f64 * hi)
+    } else {
+        let half = 0.5f64 * (d[j + 1] - d[j]);
+        let mid = 1f64 / rho
+            + (0..k)
+                .map(|l| z[l] * z[l] / ((d[l] - d[j]) - half))
+                .sum::<f64>();
+        if mid >= 0f64 {
+            (j, 0f64, half, half)
+        } else {
+            (j + 1, -half, 0f64, -half)
+        }
+    };
+    for step in 1..=MAX_SECULAR {
+        // split f at the left pole, psi holds the poles up to it and phi the rest
+        let (mut psi, mut dpsi, mut phi, mut dphi) = (0f64, 0f64, 0f64, 0f64);
+        for l in 0..k {
+            let t = z[l] / ((d[l] - d[origin]) - tau);
+            if l <= left {
+                psi += z[l] * t;
+                dpsi += t * t;
+            } else {
+                phi += z[l] * t;
+                dphi += t * t;
+            }
+        }
+        let w = 1f64 / rho + psi + phi;
+        let bound = 8f64 * (psi.abs() + phi.abs()) + 2f64 / rho + 3f64 * tau.abs() * (dpsi + dphi);
+        if w.abs() <= f64::EPSILON * bound {
+            return (origin, tau, step, true);
+        }
+        if w < 0f64 {
+            lo = lo.max(tau);
+        } else {
+            hi = hi.min(tau);
+        }
+        // f ~ c + s / (d[left] - x) + S / (d[left + 1] - x) matching f and both partial slopes
+        let (dl, dr) = ((d[left] - d[origin]) - tau, (d[left + 1] - d[origin]) - tau);
+        let c = w - dl * dpsi - dr * dphi;
+        let a = (dl + dr) * w - dl * dr * (dpsi + dphi);
+        let b = dl * dr * w;
+        let root = (a * a - 4f64 * b * c).abs().sqrt();
+        let mut eta = if c == 0f64 {
+            b / a
+        } else if last {
+            if a >= 0f64 {
+                (a + root) / (2f64 * c)
+            } else {
+                2f64 * b / (a - root)
+            }
+        } else if a <= 0f64 {
+            (a - root) / (2f64 * c)
+        } else {
+            2f64 * b / (a + root)
+        };
+        // f is increasing, a step against the sign of w falls back to newton
+        if w * eta >= 0f64 || !eta.is_finite() {
+            eta = -w / (dpsi + dphi);
+        }
+        let mut next = tau + eta;
+        if next <= lo || next >= hi {
+            next = 0.5f64 * (lo + hi);
+            if next <= lo || next >= hi {
+                return (origin, next, step, true);
+            }
+        }
+        tau = next;
+    }
+    (origin, tau, MAX_SECULAR, false)
+}
+
+/// rank_one
+/// eigenpairs of D + rho zz' by deflation and the secular equation
+///
+/// * d: diagonal entries
+/// * z: rank one update vector
+/// * rho: scalar of the update
+///
+/// returns the eigenvalues, the eigenvectors as columns, the secular steps
+/// and whether every root converged
+fn rank_one(d: &[f64], z: &[f64], rho: f64) -> (Vec<f64>, Vec<f64>, usize, bool) {
+    let n = d.len();
+    if rho < 0f64 {
+        // D + rho zz' = -(-D - rho zz')
+        let neg: Vec<f64> = d.iter().map(|v| -v).collect();
+        let (values, u, steps, converged) = rank_one(&neg, z, -rho);
+        return (values.iter().map(|v| -v).collect(), u, steps, converged);
+    }
+    let znorm = z.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let mut perm: Vec<usize> = (0..n).collect();
+    perm.sort_by(|&i, &j| d[i].total_cmp(&d[j]));
+    let mut ds: Vec<f64> = perm.iter().map(|&i| d[i]).collect();
+    let mut zs: Vec<f64> = perm
+        .iter()
+        .map(|&i| z[i] / znorm.max(f64::MIN_POSITIVE))
+        .collect();
+    let rho = rho * znorm * znorm;
+    let scale = ds.iter().fold(rho, |acc, v| acc.max(v.abs()));
+    let tol = DEFLATION * scale;
+    // deflation of small z and of close poles
+    let mut active: Vec<usize> = Vec::with_capacity(n);
+    let mut rotations: Vec<Rotation> = Vec::new();
+    for i in 0..n {
+        if rho * zs[i].abs() <= tol {
+            continue;
+        }
+        if let Some(&p) = active.last()
+            && ds[i] - ds[p] <= tol
+        {
+            // zero z[p] with a rotation in the (p, i) plane
+            let r = zs[p].hypot(zs[i]);
+            let (c, s) = (zs[i] / r, zs[p] / r);
+            let (dp, di) = (ds[p], ds[i]);
+            ds[p] = c * c * dp + s * s * di;
+            ds[i] = s * s * dp + c * c * di;
+            zs[p] = 0f64;
+            zs[i] = r;
+            rotations.push((p, i, c, s));
+            active.pop();
+        }
+        active.push(i);
+    }
+    let k = active.len();
+    let dk: Vec<f64> = active.iter().map(|&i| ds[i]).collect();
+    let zk: Vec<f64> = active.iter().map(|&i| zs[i]).collect();
+    // roots t[j] measured from the pole d[origin[j]]
+    let mut steps = 0;
+    let mut converged = true;
+    let mut origins = vec![0usize; k];
+    let mut roots = vec![0f64; k];
+    for j in 0..k {
+        let (origin, tau, count, done) = secular_root(&dk, &zk, rho, j);
+        (origins[j], roots[j]) = (origin, tau);
+        steps += count;
+        converged &= done;
+    }
+    // lambda[j] - d[i] = (d[origin] - d[i]) + t[j]
+    let gap = |j: usize, i: usize| (dk[origins[j]] - dk[i]) + roots[j];
+    // lowner recomputation of z keeps the vectors orthogonal
+    let zhat: Vec<f64> = (0..k)
+        .map(|i| {
+            let mut prod = gap(i, i) / rho;
+            for j in 0..k {
+                if j != i {
+                    prod *= gap(j, i) / (dk[j] - dk[i]);
+                }
+            }
+            prod.abs().sqrt().copysign(zk[i])
+        })
+        .collect();
+    let mut values = ds.clone();
+    // sorted coordinates, column j is the eigenvector of values[j]
+    let mut u = vec![0f64; n * n];
+    for i in 0..n {
+        u[i * n + i] = 1f64;
+    }
+    for (j, &col) in active.iter().enumerate() {
+        values[col] = dk[origins[j]] + roots[j];
+        let mut norm = 0f64;
+        for (l, &row) in active.iter().enumerate() {
+            let v = zhat[l] / -gap(j, l);
+            u[row * n + col] = v;
+            norm += v * v;
+        }
+        let inv = 1f64 / norm.sqrt();
+        for &row in active.iter() {
+            u[row * n + col] *= inv;
+        }
+    }
+    // undo the deflation rotations
+    for &(p, i, c, s) in rotations.iter().rev() {
+        for col in 0..n {
+            let (up, ui) = (u[p * n + col], u[i * n + col]);
+            u[p * n + col] = c * up + s * ui;
+            u[i * n + col] = -s * up + c * ui;
+        }
+    }
+    // undo the sort on the rows
+    let mut unsorted = vec![0f64; n * n];
+    for (s, &i) in perm.iter().enumerate() {
+        unsorted[i * n..(i + 1) * n].copy_from_slice(&u[s * n..(s + 1) * n]);
+    }
+    (values, unsorted, steps, converged)
+}
+
+// returns eigenvalues, eigenvectors as columns ~ n x n, steps and convergence
+fn cuppen(d: &[f32], e: &[f32]) -> (Vec<f64>, Vec<f64>, usize, bool) {
+    let n = d.len();
+    if n <= LEAF_SIZE {
+        let mut h = vec![0f32; n * n];
+        for i in 0..n {
+            h[i * n + i] = d[i];
+        }
+        for (i, &v) in e.iter().enumerate() {
+            h[i * n + i + 1] = v;
+            h[(i + 1) * n + i] = v;
+        }
+        let mut r = create_identity_vector(n, n);
+        // deflate against eps * ||T|| alone as in tql2
+        let absolute = TOLERANCE * magnitude(&h);
+        let (sweeps, converged) = decomp_sym(
+            &mut h,
+            Some(&mut r),
+            n,
+            n,
+            n,
+            MAX_SWEEPS,
+            f32::INFINITY,
+            absolute,
+        );
+        // eigenvectors are the rows of r
+        let mut q = vec![0f64; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                q[j * n + i] = r[i * n + j] as f64;
+            }
+        }
+        let values = (0..n).map(|i| h[i * n + i] as f64).collect();
+        return (values, q, sweeps, converged);
+    }
+    // T = diag(T1, T2) + beta vv'
+    let m = n >> 1;
+    let beta = e[m - 1];
+    let mut d1 = d[..m].to_vec();
+    let mut d2 = d[m..].to_vec();
+    d1[m - 1] -= beta;
+    d2[0] -= beta;
+    let ((l1, q1, i1, c1), (l2, q2, i2, c2)) =
+        join(|| cuppen(&d1, &e[..m - 1]), || cuppen(&d2, &e[m..]));
+    let p = n - m;
+    // z = Q'v ~ last row of Q1 and first row of Q2
+    let mut z = q1[(m - 1) * m..m * m].to_vec();
+    z.extend_from_slice(&q2[..p]);
+    let mut dvals = l1;
+    dvals.extend_from_slice(&l2);
+    let (values, u, steps, converged) = rank_one(&dvals, &z, beta as f64);
+    // Q = diag(Q1, Q2) * U
+    let block = |q: Vec<f64>, rows: usize, offset: usize| {
+        let q = NdArray::new(vec![rows, rows], q.iter().map(|&v| v as f32).collect());
+        let data = u[offset * n..(offset + rows) * n].iter().map(|&v| v as f32);
+        matrix_mult(&q, &NdArray::new(vec![rows, n], data.collect()))
+    };
+    let (top, bottom) = join(|| block(q1, m, 0), || block(q2, p, m));
+    let q = top.data.iter().chain(bottom.data.iter()).map(|&v| v as f64);
+    (values, q.collect(), i1 + i2 + steps, c1 && c2 && converged)
+}
+
+/// divide_and_conquer
+/// cuppen divide and conquer for a symmetric tridiagonal matrix
+/// subproblems are solved in parallel and merged through rank one updates
+///
+/// * d: diagonal ~ n
+/// * e: off diagonal ~ n - 1
+pub fn divide_and_conquer(d: &[f32], e: &[f32]) -> TridiagonalEigen {
+    let n = d.len();
+    let (values, vectors, iterations, converged) = cuppen(d, e);
+    let (values, vectors) = sort_pairs(values, &vectors, n);
+    TridiagonalEigen {
+        values,
+        vectors,
+        iterations,
+        converged,
+    }
+}
+
+/// sturm_count
+/// number of eigenvalues of T strictly less than x
+pub fn sturm_count(d: &[f32], e: &[f32], x: f64) -> usize {
+    let pivmin = f64::MIN_POSITIVE.sqrt();
+    let mut count = 0;
+    let mut q = 1f64;
+    for i in 0..d.len() {
+        let e2 = if i > 0 {
+            (e[i - 1] as f64).powi(2)
+        } else {
+            0f64
+        };
+        q = (d[i] as f64 - x) - e2 / q;
+        if q.abs() < pivmin {
+            q = -pivmin;
+        }
+        if q < 0f64 {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn gershgorin(d: &[f32], e: &[f32]) -> (f64, f64) {
+    let n = d.len();
+    let (mut lower, mut upper) = (f64::MAX, f64::MIN);
+    for i in 0..n {
+        let left = if i > 0 { e[i - 1].abs() } else { 0f32 };
+        let right = if i + 1 < n { e[i].abs() } else { 0f32 };
+        let radius = (left + right) as f64;
+        lower = lower.min(d[i] as f64 - radius);
+        upper = upper.max(d[i] as f64 + radius);
+    }
+    (lower, upper)
+}
+
+/// bisection
+/// eigenvalues lo..hi of the ascending spectrum through sturm counts
+///
+/// * d: diagonal ~ n
+/// * e: off diagonal ~ n - 1
+/// * lo: first index of the ascending spectrum
+/// * hi: one past the last index
+///
+/// returns the eigenvalues, the number of bisection steps and whether every
+/// interval collapsed
+pub fn bisection(d: &[f32], e: &[f32], lo: usize, hi: usize) -> (Vec<f32>, usize, bool) {
+    let (lower, upper) = gershgorin(d, e);
+    let mut steps = 0;
+    let mut converged = true;
+    let values = (lo..hi)
+        .map(|index| {
+            let (mut left, mut right) = (lower, upper);
+            let mut collapsed = false;
+            for _ in 0..MAX_BISECTIONS {
+                let mid = 0.5f64 * (left + right);
+                if mid <= left || mid >= right {
+                    collapsed = true;
+                    break;
+                }
+                steps += 1;
+                if sturm_count(d, e, mid) > index {
+                    right = mid;
+                } else {
+                    left = mid;
+                }
+            }
+            converged &= collapsed;
+            (0.5f64 * (left + right)) as f32
+        })
+        .collect();
+    (values, steps, converged)
+}
+
+// gaussian elimination with partial pivoting of T - shift * I
+struct TridiagonalLu {
+    dl: Vec<f64>,
+    dd: Vec<f64>,
+    du: Vec<f64>,
+    du2: Vec<f64>,
+    swap: Vec<bool>,
+}
+
+impl TridiagonalLu {
+    fn new(d: &[f32], e: &[f32], shift: f64, pivmin: f64) -> Self {
+        let n = d.len();
+        let mut dl: Vec<f64> = e.iter().map(|&v| v as f64).collect();
+        let mut dd: Vec<f64> = d.iter().map(|&v| v as f64 - shift).collect();
+        let mut du = dl.clone();
+        let mut du2 = vec![0f64; n.saturating_sub(2)];
+        let mut swap = vec![false; n.saturating_sub(1)];
+        for i in 0..n.saturating_sub(1) {
+            if dd[i].abs() >= dl[i].abs() {
+                if dd[i] == 0f64 {
+                    dd[i] = pivmin;
+                }
+                let fact = dl[i] / dd[i];
+                dl[i] = fact;
+                dd[i + 1] -= fact * du[i];
+            } else {
+                let fact = dd[i] / dl[i];
+                dd[i] = dl[i];
+                dl[i] = fact;
+                let temp = du[i];
+                du[i] = dd[i + 1];
+                dd[i + 1] = temp - fact * dd[i + 1];
+                if i + 2 < n {
+                    du2[i] = du[i + 1];
+                    du[i + 1] *= -fact;
+                }
+                swap[i] = true;
+            }
+        }
+        for v in dd.iter_mut() {
+            if v.abs() < pivmin {
+                *v = pivmin.copysign(*v);
+            }
+        }
+        Self {
+            dl,
+            dd,
+            du,
+            du2,
+            swap,
+        }
+    }
+    fn solve(&self, b: &mut [f64]) {
+        let n = b.len();
+        for i in 0..n.saturating_sub(1) {
+            if self.swap[i] {
+                let temp = b[i];
+                b[i] = b[i + 1];
+                b[i + 1] = temp - self.dl[i] * b[i];
+            } else {
+                b[i + 1] -= self.dl[i] * b[i];
+            }
+        }
+        for i in (0..n).rev() {
+            let mut v = b[i];
+            if i + 1 < n {
+                v -= self.du[i] * b[i + 1];
+            }
+            if i + 2 < n {
+                v -= self.du2[i] * b[i + 2];
+            }
+            b[i] = v / self.dd[i];
+        }
+    }
+}
+
+/// inverse_iteration
+/// eigenvectors of T for the given eigenvalues, vectors of clustered
+/// eigenvalues are reorthogonalized against each other
+///
+/// * d: diagonal ~ n
+/// * e: off diagonal ~ n - 1
+/// * values: eigenvalues in ascending order
+///
+/// returns the eigenvectors as columns ~ n x k
+pub fn inverse_iteration(d: &[f32], e: &[f32], values: &[f32]) -> NdArray {
+    let (n, k) = (d.len(), values.len());
+    let (lower, upper) = gershgorin(d, e);
+    let tnorm = lower.abs().max(upper.abs()).max(f64::MIN_POSITIVE);
+    let pivmin = tnorm * f64::EPSILON;
+    let mut vectors: Vec<Vec<f64>> = Vec::with_capacity(k);
+    let mut cluster = 0;
+    for j in 0..k {
+        let lambda = values[j] as f64;
+        if j > 0 && lambda - values[j - 1] as f64 > CLUSTER * tnorm {
+            cluster = j;
+        }
+        // perturb the shift so repeated eigenvalues give distinct factorizations
+        let shift = lambda + (j - cluster) as f64 * 10f64 * pivmin;
+        let lu = TridiagonalLu::new(d, e, shift, pivmin);
+        let mut x: Vec<f64> = generate_seeded_matrix(1, n, START_SEED + j as u64)
+            .data
+            .iter()
+            .map(|&v| v as f64)
+            .collect();
+        for _ in 0..INVERSE_STEPS {
+            lu.solve(&mut x);
+            for prev in &vectors[cluster..j] {
+                let dot: f64 = x.iter().zip(prev.iter()).map(|(a, b)| a * b).sum();
+                x.iter_mut()
+                    .zip(prev.iter())
+                    .for_each(|(a, b)| *a -= dot * b);
+            }
+            let norm = x.iter().map(|v| v * v).sum::<f64>().sqrt();
+            x.iter_mut().for_each(|v| *v /= norm);
+        }
+        vectors.push(x);
+    }
+    let mut z = NdArray::new(vec![n, k], vec![0f32; n * k]);
+    for (j, x) in vectors.iter().enumerate() {
+        for i in 0..n {
+            z.data[i * k + j] = x[i] as f32;
+        }
+    }
+    z
+}
+
+#[cfg(test)]
+mod test_tridiagonal {
+    use super::*;
+    use crate::algebra::ndmethods::create_identity_matrix;
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_vector;
+
+    fn dense(d: &[f32], e: &[f32]) -> NdArray {
+        let n = d.len();
+        let mut t = NdArray::new(vec![n, n], vec![0f32; n * n]);
+        for i in 0..n {
+            t.data[i * n + i] = d[i];
+            if i + 1 < n {
+                t.data[i * n + i + 1] = e[i];
+                t.data[(i + 1) * n + i] = e[i];
+            }
+        }
+        t
+    }
+    fn check_pairs(t: &NdArray, values: &[f32], z: &NdArray) {
+        let (n, k) = (t.dims[0], values.len());
+        let tz = matrix_mult(t, z);
+        let mut zd = z.clone();
+        for i in 0..n {
+            for j in 0..k {
+                zd.data[i * k + j] *= values[j];
+            }
+        }
+        assert!(approx_vector_eq(&tz.data, &zd.data));
+        let ztz = matrix_mult(&z.transpose(), z);
+        assert!(approx_vector_eq(&ztz.data, &create_identity_matrix(k).data));
+    }
+    #[test]
+    fn test_divide_and_conquer() {
+        for n in [1, 7, 30, 100] {
+            let d = generate_random_vector(n);
+            let e = generate_random_vector(n.saturating_sub(1));
+            let eig = divide_and_conquer(&d, &e);
+            assert!(eig.converged);
+            assert!(eig.values.windows(2).all(|w| w[0] <= w[1]));
+            check_pairs(&dense(&d, &e), &eig.values, &eig.vectors);
+        }
+    }
+    #[test]
+    fn test_divide_and_conquer_deflation() {
+        // repeated diagonal and a decoupled block force both deflation paths
+        let n = 60;
+        let d = vec![1f32; n];
+        let mut e = vec![0.5f32; n - 1];
+        e[n / 2 - 1] = 0f32;
+        let eig = divide_and_conquer(&d, &e);
+        assert!(eig.converged);
+        check_pairs(&dense(&d, &e), &eig.values, &eig.vectors);
+    }
+    #[test]
+    fn test_bisection_inverse_iteration() {
+        let n = 50;
+        let d = generate_random_vector(n);
+        let e = generate_random_vector(n - 1);
+        let expected = divide_and_conquer(&d, &e);
+        let (values, _, converged) = bisection(&d, &e, 10, 15);
+        assert!(converged);
+        assert!(approx_vector_eq(&values, &expected.values[10..15]));
+        let z = inverse_iteration(&d, &e, &values);
+        check_pairs(&dense(&d, &e), &values, &z);
+        assert_eq!(z.data, inverse_iteration(&d, &e, &values).data);
+    }
+}
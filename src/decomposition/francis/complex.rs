@@ -11,8 +11,25 @@ use crate::decomposition::francis::primitives::{
     lapply_householder,
     rapply_householder,
 };
+/// decomp_cpx
+/// francis double shift on a lower hessenberg matrix, sweeps start below the last
+/// negligible supra diagonal and real eigen pairs are split out of 2x2 blocks
+///
+/// * h: hessenberg linearized matrix
+/// * r: rotations are accumulated into the rows of r when given
+/// * p: projection slice
+/// * w: workspace slice
+/// * range: number of rows in active window
+/// * size: static number of rows of h and cols of r
+/// * stride: stride of the data format
+/// * max_iters: number of iterations per eigen value recoups half on success
+/// * tolerance: absolute bound on the supra diagonal for deflation
+///
+/// returns the number of iterations and whether every eigen value deflated
+#[rustfmt::skip]
 pub fn decomp_cpx(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     p: &mut [f32],
     w: &mut [f32],
     mut range: usize,
@@ -20,7 +37,7 @@ pub fn decomp_cpx(
     stride: usize,
     max_iters: usize,
     tolerance: f32,
-) {
+) -> (usize, bool) {
     let s = range * stride;
     // error 1 supra-diagonal above the first real eigen
     // error 2 supra-diagonal above the second complex real eigen
@@ -29,10 +46,12 @@ pub fn decomp_cpx(
     let mut tl = s.saturating_sub(stride + 2);
     let mut bl = s.saturating_sub(2);
     let mut curriter = 0;
+    let mut iterations = 0;
     let mut stall = 0;
-    while range > 0 && curriter < max_iters {
+    while range > 1 && curriter < max_iters {
         curriter += 1;
         if h[e1].abs() < tolerance {
+            h[e1] = 0f32;
             deflate(
                 1,
                 stride,
@@ -44,20 +63,8 @@ pub fn decomp_cpx(
                 &mut curriter,
             );
             stall = 0;
-        } else if h[e2].abs() < tolerance {
-            // if e2 == 0 then we are hitting eigen which should be greater than tolerance
-            deflate(
-                2,
-                stride,
-                &mut range,
-                &mut e1,
-                &mut e2,
-                &mut tl,
-                &mut bl,
-                &mut curriter,
-            );
-            stall = 0;
-        } else if range == 2 && complex_eig_pair(h, tl, bl) {
+        } else if (range == 2 || h[e2].abs() < tolerance) && complex_eig_pair(h, tl, bl) {
+            if range > 2 { h[e2] = 0f32; }
             deflate(
                 2,
                 stride,
@@ -69,70 +76,119 @@ pub fn decomp_cpx(
                 &mut curriter,
             );
             stall = 0;
+        } else if range == 2 || h[e2].abs() < tolerance {
+            if range > 2 { h[e2] = 0f32; }
+            francis_iteration_cpx_2x2(h, r.as_deref_mut(), range - 2, range, size, stride);
+            iterations += 1;
         } else {
-            if range == 2 {
-                francis_iteration_cpx_2x2(h, size, stride, tl, bl);
-            } else if (stall + EXCEPTION_SHIFT_OFFSET).is_multiple_of(EXCEPTION_SHIFT_PERIOD) {
-                exception_shift(h, w, stride, tl, bl);
-                francis_iteration_cpx(h, p, w, size, range, stride);
+            // the sweep starts below the last negligible supra diagonal
+            let mut lo = range - 3;
+            while lo > 0 && h[(lo - 1) * stride + lo].abs() >= tolerance {
+                lo -= 1;
+            }
+            if lo > 0 { h[(lo - 1) * stride + lo] = 0f32; }
+            let corner = lo * (stride + 1);
+            if (stall + EXCEPTION_SHIFT_OFFSET).is_multiple_of(EXCEPTION_SHIFT_PERIOD) {
+                exception_shift(&mut h[corner..], w, stride, tl - corner, bl - corner);
             } else {
-                double_shift(h, w, stride, tl, bl);
-                francis_iteration_cpx(h, p, w, size, range, stride);
+                double_shift(&mut h[corner..], w, stride, tl - corner, bl - corner);
             }
+            francis_iteration_cpx(h, r.as_deref_mut(), p, w, lo, range, size, stride);
+            iterations += 1;
             stall += 1;
         }
     }
+    (iterations, range <= 1)
 }
 /// francis_iteration_cpx
+/// one double shift sweep with the first row of the shift polynomial in w
 ///
 /// * h: hessenberg linearized matrix
-/// * r: rotaiton linearized matrix
+/// * r: rotations are accumulated into the rows of r when given
 /// * p: projection slice
 /// * w: workspace slice
-/// * size: static number of rows for rotations
+/// * lo: first row of the active window
 /// * range: number of rows in active window
+/// * size: static number of rows of h and cols of r
 /// * stride: stride of the data format
 pub fn francis_iteration_cpx(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     p: &mut [f32],
     w: &mut [f32],
-    size: usize,
+    lo: usize,
     range: usize,
+    size: usize,
     stride: usize,
 ) {
-    let bound = range.min(3);
-    let p = &mut p[..bound];
-    let tau = params(&mut w[..bound], p);
+    let bound = (range - lo).min(3);
+    let proj = &mut p[..bound];
+    let tau = params(&mut w[..bound], proj);
     if tau != 0f32 {
-        rapply_householder(h, p, w, tau, size, bound, stride);
-        lapply_householder(h, p, w, tau, bound, range, stride);
+        let offset = lo * stride;
+        rapply_householder(
+            &mut h[offset + lo..],
+            proj,
+            w,
+            tau,
+            size - lo,
+            bound,
+            stride,
+        );
+        lapply_householder(&mut h[offset..], proj, w, tau, bound, range, stride);
+        if let Some(r) = r.as_deref_mut() {
+            lapply_householder(&mut r[offset..], proj, w, tau, bound, size, stride);
+        }
     }
-    let mut offset = 0;
-    for o in 1..range.saturating_sub(1) {
-        let bound = bound.min(stride - o);
-        let (slice, t) = h.split_at_mut(offset + stride);
-        let slice = &mut slice[offset + o..offset + o + bound];
+    for o in lo + 1..range.saturating_sub(1) {
+        let bound = (range - o).min(3);
+        let offset = o * stride;
+        let (slice, t) = h.split_at_mut(offset);
+        let slice = &mut slice[offset - stride + o..offset - stride + o + bound];
         let proj = &mut p[..bound];
         let tau = params(slice, proj);
-        offset += stride;
         if tau == 0f32 {
             continue;
         }
         rapply_householder(&mut t[o..], proj, w, tau, size - o, bound, stride);
         lapply_householder(&mut h[offset..], proj, w, tau, bound, range, stride);
+        if let Some(r) = r.as_deref_mut() {
+            lapply_householder(&mut r[offset..], proj, w, tau, bound, size, stride);
+        }
     }
 }
 /// francis_iteration_cpx_2x2
+/// single shift by an eigen value of the 2x2 block at lo
 ///
 /// * h: hessenberg linearized matrix
-/// * size: static number of rows for rotations
+/// * r: rotations are accumulated into the rows of r when given
+/// * lo: first row of the block
 /// * range: number of rows in active window
+/// * size: static number of rows of h and cols of r
 /// * stride: stride of the data format
-/// * tl : top-left of the eigen-pair
-/// * bl : bottom-left of the eigen-pair
-pub fn francis_iteration_cpx_2x2(h: &mut [f32], size: usize, stride: usize, tl: usize, bl: usize) {
+pub fn francis_iteration_cpx_2x2(
+    h: &mut [f32],
+    r: Option<&mut [f32]>,
+    lo: usize,
+    range: usize,
+    size: usize,
+    stride: usize,
+) {
+    let (tl, bl) = (lo * (stride + 1), (lo + 1) * (stride + 1) - 1);
     let eig = eigen(h[tl], h[tl + 1], h[bl], h[bl + 1]);
-    let (_, cosine, sine) = implicit_givens_rotation(h[0] - eig, h[1]);
-    apply_gt_right(h, 0, 1, stride, size, cosine, sine);
-    apply_g_left(h, 0, 1, stride, 2, cosine, sine);
+    let (_, cosine, sine) = implicit_givens_rotation(h[tl] - eig, h[tl + 1]);
+    let (s1, s2) = (lo, lo + 1);
+    apply_gt_right(
+        &mut h[lo * stride..],
+        s1,
+        s2,
+        stride,
+        size - lo,
+        cosine,
+        sine,
+    );
+    apply_g_left(h, s1, s2, stride, range, cosine, sine);
+    if let Some(r) = r {
+        apply_g_left(r, s1, s2, stride, size, cosine, sine);
+    }
 }
@@ -8,6 +8,7 @@ use crate::decomposition::francis::{complex, primitives, symmetric};
 /// francis_qr_sym
 ///
 /// * h: householder
+/// * r: rotations are accumulated into the rows of r when given
 /// * p: projection vector
 /// * w: workspace for a givens rotation
 /// * range: number of rows in active window
@@ -15,8 +16,11 @@ use crate::decomposition::francis::{complex, primitives, symmetric};
 /// * max_iters: number of iterations per eigen vector recoups half on success
 /// * tolerance: error tolerance which is used as a bound for non relative error
 /// * absolute: absolute bound on error minimum should be less than tolerance
+///
+/// returns the number of sweeps and whether every eigen value deflated
 pub fn francis_qr_sym(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     p: &mut [f32],
     w: &mut [f32],
     range: usize,
@@ -25,21 +29,25 @@ pub fn francis_qr_sym(
     max_iters: usize,
     tolerance: f32,
     absolute: f32,
-) {
-    primitives::hessenberg(h, p, w, size, range, stride);
-    symmetric::decomp_sym(h, range, size, stride, max_iters, tolerance, absolute);
+) -> (usize, bool) {
+    primitives::hessenberg(h, r.as_deref_mut(), p, w, size, range, stride);
+    symmetric::decomp_sym(h, r, range, size, stride, max_iters, tolerance, absolute)
 }
 /// francis_qr_complex
 ///
 /// * h: householder
+/// * r: rotations are accumulated into the rows of r when given
 /// * p: projection vector
 /// * w: workspace for a givens rotation
 /// * range: number of rows in active window
 /// * size: static number of rows for rotations
 /// * max_iters: number of iterations per eigen vector recoups half on success
 /// * tolerance: error tolerance which is used as a bound for non relative error
+///
+/// returns the number of iterations and whether every eigen value deflated
 pub fn francis_qr_cpx(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     p: &mut [f32],
     w: &mut [f32],
     range: usize,
@@ -47,10 +55,10 @@ pub fn francis_qr_cpx(
     stride: usize,
     max_iters: usize,
     tolerance: f32,
-) {
+) -> (usize, bool) {
     debug_assert!(size > 2);
-    primitives::hessenberg(h, p, w, size, range, stride);
-    complex::decomp_cpx(h, p, w, range, size, stride, max_iters, tolerance);
+    primitives::hessenberg(h, r.as_deref_mut(), p, w, size, range, stride);
+    complex::decomp_cpx(h, r, p, w, range, size, stride, max_iters, tolerance)
 }
 mod test_francis_interface {
     #![allow(dead_code)]
@@ -73,6 +81,7 @@ mod test_francis_interface {
 
         francis_qr_sym(
             &mut h,
+            None,
             &mut p,
             &mut w,
             c,
@@ -96,7 +105,9 @@ mod test_francis_interface {
 
         let original_trace = trace(&h, c, stride);
 
-        francis_qr_cpx(&mut h, &mut p, &mut w, c, c, stride, MAX_ITERS, TOLERANCE);
+        francis_qr_cpx(
+            &mut h, None, &mut p, &mut w, c, c, stride, MAX_ITERS, TOLERANCE,
+        );
 
         let final_trace = trace(&h, c, stride);
         let trace_ok = approx_scalar_eq(original_trace, final_trace);
@@ -140,6 +140,7 @@ pub fn rapply_householder(
 }
 /// hessenberg
 /// * h: matrix to create the hessenberg
+/// * r: reflections are accumulated into the rows of r when given
 /// * p: projection vector
 /// * w: workspace vector
 /// * rows: number of rows
@@ -148,6 +149,7 @@ pub fn rapply_householder(
 #[rustfmt::skip]
 pub fn hessenberg(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     p: &mut [f32],
     w: &mut [f32],
     rows: usize,
@@ -168,6 +170,9 @@ pub fn hessenberg(
         if tau == 0f32 { continue; }
         rapply_householder(&mut h[offset + k..], proj, w, tau, rows - k, split_range, stride);
         lapply_householder(&mut h[offset..], proj, w, tau, active_range, cols, stride);
+        if let Some(r) = r.as_deref_mut() {
+            lapply_householder(&mut r[offset..], proj, w, tau, active_range, cols, stride);
+        }
     }
 }
 pub fn deflate(
@@ -5,16 +5,31 @@ use crate::decomposition::francis::primitives::{
     eigen,
 };
 
+/// decomp_sym
+/// shifted QR on a symmetric tridiagonal matrix, sweeps start below the
+/// last negligible off diagonal so reducible windows do not stall
+///
+/// * h: tridiagonal linearized matrix
+/// * r: rotations are accumulated into the rows of r when given
+/// * range: number of rows in active window
+/// * size: static number of cols of r
+/// * stride: stride of the data format
+/// * max_iters: number of iterations per eigen value recoups half on success
+/// * tolerance: error tolerance relative to the neighbouring diagonal, infinite for an absolute test
+/// * absolute: absolute bound on error minimum should be less than tolerance
+///
+/// returns the number of sweeps and whether every eigen value deflated
 #[rustfmt::skip]
 pub fn decomp_sym(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     mut range: usize,
     size: usize,
     stride: usize,
     max_iters:usize,
     tolerance: f32,
     absolute: f32,
-) {
+) -> (usize, bool) {
     let s = range * stride;
     // error 1 supra-diagonal above the first real eigen
     // error 2 supra-diagonal above the second complex real eigen
@@ -23,10 +38,12 @@ pub fn decomp_sym(
     let mut tl = s.saturating_sub(stride + 2);
     let mut bl = s.saturating_sub(2);
     let mut curriter = 0;
+    let mut sweeps = 0;
+    let mut forced = false;
     while range > 1 && curriter < max_iters {
         curriter += 1;
         let scale = h[tl].abs() + h[bl+1].abs();
-        if h[e1].abs() < (scale * tolerance).min(absolute) {
+        if h[e1].abs() <= (scale * tolerance).min(absolute) {
             deflate(
                 1,
                 stride,
@@ -38,6 +55,7 @@ pub fn decomp_sym(
                 &mut curriter,
             );
         } else if h[e2].abs() < tolerance && curriter == max_iters {
+            forced = true;
             deflate(
                 2,
                 stride,
@@ -49,20 +67,34 @@ pub fn decomp_sym(
                 &mut curriter,
             );
         } else {
-            francis_iteration_sym(h, size, range, stride, tl, bl);
+            // the sweep starts below the last negligible off diagonal
+            let mut lo = range - 2;
+            while lo > 0 {
+                let (above, diag) = ((lo - 1) * (stride + 1), lo * (stride + 1));
+                let scale = h[above].abs() + h[diag].abs();
+                if h[above + 1].abs() <= (scale * tolerance).min(absolute) { break; }
+                lo -= 1;
+            }
+            let corner = lo * (stride + 1);
+            let r = r.as_deref_mut().map(|r| &mut r[lo * stride..]);
+            francis_iteration_sym(&mut h[corner..], r, size, range - lo, stride, tl - corner, bl - corner);
+            sweeps += 1;
         }
     }
+    (sweeps, range <= 1 && !forced)
 }
 /// francis_iteration_sym
 ///
 /// * h: hessenberg linearized matrix
-/// * size: static number of rows for rotations
+/// * r: rotations are accumulated into the rows of r when given
+/// * size: static number of cols of r
 /// * range: number of rows in active window
 /// * stride: stride of the data format
 /// * tl: top left of the window for the eigens
 /// * bl: bottom left of the window for the eigens
 pub fn francis_iteration_sym(
     h: &mut [f32],
+    mut r: Option<&mut [f32]>,
     size: usize,
     range: usize,
     stride: usize,
@@ -71,8 +103,11 @@ pub fn francis_iteration_sym(
 ) {
     let eig = eigen(h[tl], h[tl + 1], h[bl], h[bl + 1]);
     let (_, cosine, sine) = implicit_givens_rotation(h[0] - eig, h[1]);
-    apply_gt_right(h, 0, 1, stride, size, cosine, sine);
-    apply_g_left(h, 0, 1, stride, size, cosine, sine);
+    apply_gt_right(h, 0, 1, stride, range, cosine, sine);
+    apply_g_left(h, 0, 1, stride, range, cosine, sine);
+    if let Some(r) = r.as_deref_mut() {
+        apply_g_left(r, 0, 1, stride, size, cosine, sine);
+    }
     for o in 0..range.saturating_sub(2) {
         let row = o * stride;
         let s1 = o + 1;
@@ -80,5 +115,8 @@ pub fn francis_iteration_sym(
         let (_, cosine, sine) = implicit_givens_rotation(h[row + s1], h[row + s2]);
         apply_gt_right(&mut h[row..], s1, s2, stride, range - o, cosine, sine);
         apply_g_left(h, s1, s2, stride, range, cosine, sine);
+        if let Some(r) = r.as_deref_mut() {
+            apply_g_left(r, s1, s2, stride, size, cosine, sine);
+        }
     }
 }
@@ -1,266 +1,9 @@
 #![allow(unused)]
-use crate::decomposition::francis::constants::{
-    ABSOLUTE_CAP, EXCEPTION_SHIFT_OFFSET, EXCEPTION_SHIFT_PERIOD, MAX_ITERS, TOLERANCE,
-};
-use crate::decomposition::sgivens::{apply_g_left, apply_gt_right, implicit_givens_rotation};
-#[rustfmt::skip]
-use crate::decomposition::francis::primitives::{
-    params,
-    deflate,
-    eigen,
-    double_shift,
-    exception_shift,
-    complex_eig_pair,
-    lapply_householder,
-    rapply_householder,
-};
+use crate::decomposition::francis::complex::decomp_cpx;
+use crate::decomposition::francis::constants::{ABSOLUTE_CAP, MAX_ITERS, TOLERANCE};
+use crate::decomposition::francis::primitives::hessenberg;
+use crate::decomposition::francis::symmetric::decomp_sym;
 
-#[rustfmt::skip]
-fn full_decomp_sym(
-    h: &mut [f32],
-    r: &mut [f32],
-    mut range: usize,
-    size: usize,
-    stride: usize
-) -> bool {
-    let s = range * stride;
-    let mut e1 = s.saturating_sub(stride + 1);
-    let mut e2 = s.saturating_sub(stride + stride + 2);
-    let mut tl = s.saturating_sub(stride + 2);
-    let mut bl = s.saturating_sub(2);
-    let mut curriter = 0;
-    while range > 1 && curriter < MAX_ITERS {
-        let scale = h[tl].abs() + h[bl+1].abs();
-        curriter += 1;
-        if h[e1].abs() < (scale * TOLERANCE).min(ABSOLUTE_CAP) {
-            deflate(
-                1,
-                stride,
-                &mut range,
-                &mut e1,
-                &mut e2,
-                &mut tl,
-                &mut bl,
-                &mut curriter,
-            );
-        } else if h[e2].abs() < TOLERANCE && curriter == MAX_ITERS {
-            deflate(
-                2,
-                stride,
-                &mut range,
-                &mut e1,
-                &mut e2,
-                &mut tl,
-                &mut bl,
-                &mut curriter,
-            );
-        } else {
-            full_francis_iteration_sym(h, r, size, range, stride, tl, bl);
-        }
-    }
-    range <= 1
-}
-fn full_decomp_cpx(
-    h: &mut [f32],
-    p: &mut [f32],
-    r: &mut [f32],
-    w: &mut [f32],
-    mut range: usize,
-    size: usize,
-    stride: usize,
-) -> bool {
-    let s = range * stride;
-    let mut e1 = s.saturating_sub(stride + 1);
-    let mut e2 = s.saturating_sub(stride + stride + 2);
-    let mut tl = s.saturating_sub(stride + 2);
-    let mut bl = s.saturating_sub(2);
-    let mut curriter = 0;
-    let p = &mut [0f32; 3];
-    let mut stall = 0;
-    while range > 0 && curriter < MAX_ITERS {
-        curriter += 1;
-        if h[e1].abs() < TOLERANCE {
-            deflate(
-                1,
-                stride,
-                &mut range,
-                &mut e1,
-                &mut e2,
-                &mut tl,
-                &mut bl,
-                &mut curriter,
-            );
-            stall = 0;
-        } else if h[e2].abs() < TOLERANCE {
-            // if e2 == 0 then we are hitting eigen which should be greater than tolerance
-            deflate(
-                2,
-                stride,
-                &mut range,
-                &mut e1,
-                &mut e2,
-                &mut tl,
-                &mut bl,
-                &mut curriter,
-            );
-            stall = 0;
-        } else if range == 2 && complex_eig_pair(h, tl, bl) {
-            deflate(
-                2,
-                stride,
-                &mut range,
-                &mut e1,
-                &mut e2,
-                &mut tl,
-                &mut bl,
-                &mut curriter,
-            );
-            stall = 0;
-        } else {
-            if range == 2 {
-                full_francis_iteration_cpx_2x2(h, r, size, stride, tl, bl);
-            } else if (stall + EXCEPTION_SHIFT_OFFSET).is_multiple_of(EXCEPTION_SHIFT_PERIOD) {
-                exception_shift(h, w, stride, tl, bl);
-                full_francis_iteration_cpx(h, r, p, w, size, range, stride, tl, bl);
-            } else {
-                double_shift(h, w, stride, tl, bl);
-                full_francis_iteration_cpx(h, r, p, w, size, range, stride, tl, bl);
-            }
-            stall += 1;
-        }
-    }
-    range <= 1
-}
-/// francis_iteration_cpx
-///
-/// * h: hessenberg linearized matrix
-/// * r: rotaiton linearized matrix
-/// * p: projection slice
-/// * w: workspace slice
-/// * size: static number of rows for rotations
-/// * range: number of rows in active window
-/// * stride: stride of the data format
-fn full_francis_iteration_cpx(
-    h: &mut [f32],
-    r: &mut [f32],
-    p: &mut [f32],
-    w: &mut [f32],
-    size: usize,
-    range: usize,
-    stride: usize,
-    _tl: usize,
-    _bl: usize,
-) {
-    let bound = range.min(3);
-    let p = &mut p[..bound];
-    let tau = params(&mut w[..bound], p);
-    if tau != 0f32 {
-        rapply_householder(h, p, w, tau, size, bound, stride);
-        lapply_householder(h, p, w, tau, bound, range, stride);
-        // ----------------- tracking the rotation matrix
-        lapply_householder(r, p, w, tau, bound, size, stride);
-    }
-    let mut offset = 0;
-    for o in 1..range.saturating_sub(1) {
-        let bound = bound.min(stride - o);
-        let (slice, t) = h.split_at_mut(offset + stride);
-        let slice = &mut slice[offset + o..offset + o + bound];
-        let proj = &mut p[..bound];
-        let tau = params(slice, proj);
-        offset += stride;
-        if tau == 0f32 {
-            continue;
-        }
-        rapply_householder(&mut t[o..], proj, w, tau, size - o, bound, stride);
-        lapply_householder(&mut h[offset..], proj, w, tau, bound, range, stride);
-        // ----------------- tracking the rotation matrix
-        lapply_householder(&mut r[offset..], proj, w, tau, bound, size, stride);
-    }
-}
-fn full_francis_iteration_cpx_2x2(
-    h: &mut [f32],
-    r: &mut [f32],
-    size: usize,
-    stride: usize,
-    tl: usize,
-    bl: usize,
-) {
-    let eig = eigen(h[tl], h[tl + 1], h[bl], h[bl + 1]);
-    let (_, cosine, sine) = implicit_givens_rotation(h[0] - eig, h[1]);
-    apply_gt_right(h, 0, 1, stride, size, cosine, sine);
-    apply_g_left(h, 0, 1, stride, 2, cosine, sine);
-    apply_g_left(r, 0, 1, stride, size, cosine, sine);
-}
-/// full_francis_iteration_sym
-///
-/// * h: hessenberg linearized matrix
-/// * r: rotation accumulated linearized matrix
-/// * size: static number of rows for rotations
-/// * range: number of rows in active window
-/// * stride: stride of the data format
-/// * tl: top left of the window for the eigens
-/// * bl: bottom left of the window for the eigens
-fn full_francis_iteration_sym(
-    h: &mut [f32],
-    r: &mut [f32],
-    size: usize,
-    range: usize,
-    stride: usize,
-    tl: usize,
-    bl: usize,
-) {
-    let eig = eigen(h[tl], h[tl + 1], h[bl], h[bl + 1]);
-    let (_, cosine, sine) = implicit_givens_rotation(h[0] - eig, h[1]);
-    apply_gt_right(h, 0, 1, stride, size, cosine, sine);
-    apply_g_left(h, 0, 1, stride, size, cosine, sine);
-    apply_g_left(r, 0, 1, stride, size, cosine, sine);
-    for o in 0..range.saturating_sub(2) {
-        let row = o * stride;
-        let s1 = o + 1;
-        let s2 = o + 2;
-        let (_, cosine, sine) = implicit_givens_rotation(h[row + s1], h[row + s2]);
-        apply_gt_right(&mut h[row..], s1, s2, stride, range - o, cosine, sine);
-        apply_g_left(h, s1, s2, stride, range, cosine, sine);
-        apply_g_left(r, s1, s2, stride, size, cosine, sine);
-    }
-}
-/// full_hessenberg
-/// * h: matrix to create the hessenberg
-/// * r: rotation matrix should be identity on coldstart
-/// * p: projection vector
-/// * w: workspace vector
-/// * rows: number of rows
-/// * cols: number of cols
-/// * stride: stride of the data
-fn full_hessenberg(
-    h: &mut [f32],
-    r: &mut [f32],
-    p: &mut [f32],
-    w: &mut [f32],
-    rows: usize,
-    cols: usize,
-    stride: usize,
-) {
-    // stores tau
-    let mut offset = 0;
-    let mut active_range = rows;
-    let mut split_range = cols;
-    for o in 1..rows {
-        active_range -= 1;
-        split_range -= 1;
-        let (slice, t) = h.split_at_mut(offset + stride);
-        let slice = &mut slice[offset + o..offset + cols];
-        let proj = &mut p[..split_range];
-        let tau = params(slice, proj);
-        offset += stride;
-        if tau == 0f32 {
-            continue;
-        }
-        rapply_householder(&mut t[o..], proj, w, tau, rows - o, split_range, stride);
-        lapply_householder(&mut h[offset..], proj, w, tau, active_range, cols, stride);
-        lapply_householder(&mut r[offset..], proj, w, tau, active_range, cols, stride);
-    }
-}
 mod test_hessenberg_reconstructions {
     use super::*;
 
@@ -284,7 +27,7 @@ mod test_hessenberg_reconstructions {
                 dims: vec![rows, cols],
                 data: h.clone(),
             };
-            full_hessenberg(&mut h, &mut r, &mut p, &mut w, rows, cols, stride);
+            hessenberg(&mut h, Some(&mut r), &mut p, &mut w, rows, cols, stride);
             let kernel = NdArray {
                 dims: vec![rows, cols],
                 data: h.clone(),
@@ -331,7 +74,7 @@ mod test_hessenberg_reconstructions {
                 dims: vec![rows, cols],
                 data: h.clone(),
             };
-            full_hessenberg(&mut h, &mut r, &mut p, &mut w, rows, cols, stride);
+            hessenberg(&mut h, Some(&mut r), &mut p, &mut w, rows, cols, stride);
             let kernel = NdArray {
                 dims: vec![rows, cols],
                 data: h.clone(),
@@ -383,8 +126,17 @@ mod test_hessenberg_reconstructions {
             data: h.clone(),
         };
 
-        full_hessenberg(&mut h, &mut r, &mut p, &mut w, rows, cols, stride);
-        let converged = full_decomp_sym(&mut h, &mut r, c, c, c);
+        hessenberg(&mut h, Some(&mut r), &mut p, &mut w, rows, cols, stride);
+        let (_, converged) = decomp_sym(
+            &mut h,
+            Some(&mut r),
+            c,
+            c,
+            c,
+            MAX_ITERS,
+            TOLERANCE,
+            ABSOLUTE_CAP,
+        );
 
         let kernel = NdArray {
             dims: vec![rows, cols],
@@ -439,8 +191,18 @@ mod test_hessenberg_reconstructions {
             data: h.clone(),
         };
 
-        full_hessenberg(&mut h, &mut r, &mut p, &mut w, rows, cols, stride);
-        let converged = full_decomp_cpx(&mut h, &mut p, &mut r, &mut w, c, c, c);
+        hessenberg(&mut h, Some(&mut r), &mut p, &mut w, rows, cols, stride);
+        let (_, converged) = decomp_cpx(
+            &mut h,
+            Some(&mut r),
+            &mut p,
+            &mut w,
+            c,
+            c,
+            c,
+            MAX_ITERS,
+            TOLERANCE,
+        );
 
         let kernel = NdArray {
             dims: vec![rows, cols],
@@ -21,7 +21,7 @@ pub fn householder_params(mut u: Vec<f32>) -> HouseholderReflection {
     let mut max_element = 0f32;
     let mut magnitude_squared = 0f32;
     for &e in u.iter() {
-        if e.abs() > max_element {
+        if e.abs() > max_element.abs() {
             max_element = e;
         }
     }
@@ -68,3 +68,51 @@ pub fn householder_inplace(u: &mut [f32]) -> f32 {
         2f32 * sign * tmp * magnitude_squared.sqrt() + magnitude_squared + magnitude_squared;
     2f32 / magnitude_squared
 }
+// P = I - tau vv' with Px = beta e1 in double precision
+pub(crate) fn householder(x: &[f64], v: &mut [f64]) -> f64 {
+    let alpha = x.iter().map(|xi| xi * xi).sum::<f64>().sqrt();
+    if alpha == 0f64 {
+        return 0f64;
+    }
+    let beta = -x[0].signum() * alpha;
+    v[..x.len()].copy_from_slice(x);
+    v[0] = x[0] - beta;
+    2f64 / v[..x.len()].iter().map(|vi| vi * vi).sum::<f64>()
+}
+// rows r0..r0 + v.len() of m <- P rows on cols
+pub(crate) fn reflect_rows(
+    m: &mut [f64],
+    n: usize,
+    v: &[f64],
+    tau: f64,
+    r0: usize,
+    cols: std::ops::Range<usize>,
+) {
+    for j in cols {
+        let s = tau
+            * v.iter()
+                .enumerate()
+                .map(|(k, vk)| vk * m[(r0 + k) * n + j])
+                .sum::<f64>();
+        for (k, vk) in v.iter().enumerate() {
+            m[(r0 + k) * n + j] -= s * vk;
+        }
+    }
+}
+// cols c0..c0 + v.len() of m <- cols P on rows
+pub(crate) fn reflect_cols(
+    m: &mut [f64],
+    n: usize,
+    v: &[f64],
+    tau: f64,
+    c0: usize,
+    rows: std::ops::Range<usize>,
+) {
+    for i in rows {
+        let row = &mut m[i * n + c0..i * n + c0 + v.len()];
+        let s = tau * row.iter().zip(v).map(|(mk, vk)| mk * vk).sum::<f64>();
+        for (mk, vk) in row.iter_mut().zip(v) {
+            *mk -= s * vk;
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::decomposition::sgivens::implicit_givens_rotation;
+use crate::decomposition::svd::decomp::Svd;
+use crate::structure::ndarray::NdArray;
+use rayon::prelude::*;
+
+const MAX_SWEEPS: usize = 60;
+
+/// tournament
+/// round robin ordering of all pairs (p, q), every round holds disjoint pairs
+///
+/// * n: number of indices
+fn tournament(n: usize) -> Vec<Vec<(usize, usize)>> {
+    // circle method, index n is a bye when n is odd
+    let players = n + (n & 1);
+    let mut circle: Vec<usize> = (0..players).collect();
+    let mut rounds = Vec::with_capacity(players.saturating_sub(1));
+    for _ in 1..players {
+        let round = (0..players / 2)
+            .map(|k| {
+                let (p, q) = (circle[k], circle[players - 1 - k]);
+                (p.min(q), p.max(q))
+            })
+            .filter(|&(_, q)| q < n)
+            .collect();
+        rounds.push(round);
+        circle[1..].rotate_right(1);
+    }
+    rounds
+}
+
+/// rotation
+/// jacobi rotation (c, s) diagonalizing the symmetric 2x2 [app apq; apq aqq]
+fn rotation(app: f64, aqq: f64, apq: f64) -> (f32, f32) {
+    let zeta = (aqq - app) / (2f64 * apq);
+    let t = zeta.signum() / (zeta.abs() + (1f64 + zeta * zeta).sqrt());
+    // c = 1 / sqrt(1 + t^2), s = tc
+    let (_, c, s) = implicit_givens_rotation(1f32, t as f32);
+    (c, s)
+}
+
+// [x y] <- [cx - sy, sx + cy]
+fn rotate(x: &mut [f32], y: &mut [f32], c: f32, s: f32) {
+    for (xi, yi) in x.iter_mut().zip(y.iter_mut()) {
+        let (a, b) = (*xi, *yi);
+        *xi = c * a - s * b;
+        *yi = s * a + c * b;
+    }
+}
+
+fn dot(x: &[f32], y: &[f32]) -> f64 {
+    x.iter().zip(y).map(|(a, b)| *a as f64 * *b as f64).sum()
+}
+
+// disjoint mutable row pairs of a row major matrix for one round
+fn row_pairs<'a>(
+    data: &'a mut [f32],
+    stride: usize,
+    round: &[(usize, usize)],
+) -> Vec<(&'a mut [f32], &'a mut [f32])> {
+    let mut rows: Vec<Option<&mut [f32]>> = data.chunks_mut(stride).map(Some).collect();
+    round
+        .iter()
+        .map(|&(p, q)| (rows[p].take().unwrap(), rows[q].take().unwrap()))
+        .collect()
+}
+
+/// one_sided
+/// hestenes one sided jacobi, orthogonalizes the rows of W = A' with W <- J'W and V' <- J'V'
+///
+/// * w: rows are the columns of A ~ n x m
+/// * vt: rows are the right singular vectors ~ n x n, identity on coldstart
+/// * n: number of columns of A
+/// * m: number of rows of A
+///
+/// returns the number of sweeps and whether the rows converged to orthogonality
+fn one_sided(w: &mut [f32], vt: &mut [f32], n: usize, m: usize) -> (usize, bool) {
+    let tolerance = m as f64 * f32::EPSILON as f64;
+    let rounds = tournament(n);
+    for sweep in 0..MAX_SWEEPS {
+        let mut rotated = false;
+        for round in &rounds {
+            let angles: Vec<Option<(f32, f32)>> = row_pairs(w, m, round)
+                .into_par_iter()
+                .map(|(x, y)| {
+                    let (alpha, beta, gamma) = (dot(x, x), dot(y, y), dot(x, y));
+                    if gamma.abs() <= tolerance * (alpha * beta).sqrt() {
+                        return None;
+                    }
+                    let (c, s) = rotation(alpha, beta, gamma);
+                    rotate(x, y, c, s);
+                    Some((c, s))
+                })
+                .collect();
+            row_pairs(vt, n, round)
+                .into_par_iter()
+                .zip(angles.par_iter())
+                .for_each(|((x, y), angle)| {
+                    if let Some((c, s)) = *angle {
+                        rotate(x, y, c, s);
+                    }
+                });
+            rotated |= angles.iter().any(|a| a.is_some());
+        }
+        if !rotated {
+            return (sweep + 1, true);
+        }
+    }
+    (MAX_SWEEPS, false)
+}
+
+/// jacobi_eigen
+/// cyclic two sided jacobi on a symmetric matrix, A <- J'AJ one round of disjoint rotations at a time
+///
+/// * a: symmetric matrix data, overwritten by the diagonalized matrix
+/// * vt: eigenvectors stored as rows, identity on coldstart
+/// * n: number of rows
+///
+/// returns the number of sweeps and whether every off diagonal entry became negligible
+pub fn jacobi_eigen(a: &mut [f32], vt: &mut [f32], n: usize) -> (usize, bool) {
+    let rounds = tournament(n);
+    let mut buffer = vec![0f32; n * n];
+    for sweep in 0..MAX_SWEEPS {
+        let mut rotated = false;
+        for round in &rounds {
+            let angles: Vec<Option<(f32, f32)>> = round
+                .par_iter()
+                .map(|&(p, q)| {
+                    let (app, aqq, apq) = (
+                        a[p * n + p] as f64,
+                        a[q * n + q] as f64,
+                        a[p * n + q] as f64,
+                    );
+                    // relative criterion keeps small eigenvalues accurate
+                    if apq.abs() <= f32::EPSILON as f64 * (app * aqq).abs().sqrt() || apq == 0f64 {
+                        return None;
+                    }
+                    Some(rotation(app, aqq, apq))
+                })
+                .collect();
+            if angles.iter().all(|a| a.is_none()) {
+                continue;
+            }
+            rotated = true;
+            // J'A, then J'(J'A)' = J'AJ as A is symmetric
+            for _ in 0..2 {
+                row_pairs(a, n, round)
+                    .into_par_iter()
+                    .zip(angles.par_iter())
+                    .for_each(|((x, y), angle)| {
+                        if let Some((c, s)) = *angle {
+                            rotate(x, y, c, s);
+                        }
+                    });
+                buffer.par_chunks_mut(n).enumerate().for_each(|(i, row)| {
+                    for (j, v) in row.iter_mut().enumerate() {
+                        *v = a[j * n + i];
+                    }
+                });
+                a.copy_from_slice(&buffer);
+            }
+            for (&(p, q), angle) in round.iter().zip(&angles) {
+                if angle.is_some() {
+                    a[p * n + q] = 0f32;
+                    a[q * n + p] = 0f32;
+                }
+            }
+            row_pairs(vt, n, round)
+                .into_par_iter()
+                .zip(angles.par_iter())
+                .for_each(|((x, y), angle)| {
+                    if let Some((c, s)) = *angle {
+                        rotate(x, y, c, s);
+                    }
+                });
+        }
+        if !rotated {
+            return (sweep + 1, true);
+        }
+    }
+    (MAX_SWEEPS, false)
+}
+
+/// jacobi_svd
+/// thin svd by one sided jacobi, small singular values keep high relative accuracy
+///
+/// * a: matrix ~ rows x cols
+///
+/// returns the decomposition, the number of sweeps and whether the columns became orthogonal
+pub fn jacobi_svd(a: &NdArray) -> (Svd, usize, bool) {
+    let (rows, cols) = (a.dims[0], a.dims[1]);
+    if rows < cols {
+        // A' = VSU'
+        let (svd, sweeps, converged) = jacobi_svd(&a.transpose());
+        let svd = Svd {
+            rows,
+            cols,
+            s: svd.s,
+            u: svd.v,
+            v: svd.u,
+        };
+        return (svd, sweeps, converged);
+    }
+    let (m, n) = (rows, cols);
+    let mut w = a.transpose().data;
+    let mut vt = vec![0f32; n * n];
+    for i in 0..n {
+        vt[i * n + i] = 1f32;
+    }
+    let (sweeps, converged) = one_sided(&mut w, &mut vt, n, m);
+    // s_j = ||w_j||, u_j = w_j / s_j
+    let norms: Vec<f32> = w.chunks(m).map(|row| dot(row, row).sqrt() as f32).collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| norms[j].total_cmp(&norms[i]));
+    let mut u = vec![0f32; m * n];
+    let mut v = vec![0f32; n * n];
+    for (k, &src) in order.iter().enumerate() {
+        let inv = if norms[src] > 0f32 {
+            1f32 / norms[src]
+        } else {
+            0f32
+        };
+        for i in 0..m {
+            u[i * n + k] = w[src * m + i] * inv;
+        }
+        for i in 0..n {
+            v[i * n + k] = vt[src * n + i];
+        }
+    }
+    let svd = Svd {
+        rows,
+        cols,
+        s: order.iter().map(|&i| norms[i]).collect(),
+        u: Some(NdArray::new(vec![m, n], u)),
+        v: Some(NdArray::new(vec![n, n], v)),
+    };
+    (svd, sweeps, converged)
+}
+
+impl Svd {
+    /// jacobi
+    /// thin svd by one sided jacobi, see jacobi_svd
+    ///
+    /// * a: matrix ~ rows x cols
+    pub fn jacobi(a: &NdArray) -> Self {
+        jacobi_svd(a).0
+    }
+}
+
+#[cfg(test)]
+mod test_jacobi {
+    use super::*;
+    use crate::algebra::ndmethods::{create_identity_matrix, matrix_mult};
+    use crate::decomposition::eigen::symmetric::{EigenMethod, EigenRange, SymmetricEigen};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::{generate_random_matrix, generate_random_symetric};
+
+    #[test]
+    fn test_tournament() {
+        for n in [1, 2, 5, 8] {
+            let rounds = tournament(n);
+            let mut seen = vec![false; n * n];
+            for round in &rounds {
+                let mut used = vec![false; n];
+                for &(p, q) in round {
+                    assert!(p < q && !used[p] && !used[q]);
+                    used[p] = true;
+                    used[q] = true;
+                    seen[p * n + q] = true;
+                }
+            }
+            assert_eq!(seen.iter().filter(|&&s| s).count(), n * (n - 1) / 2);
+        }
+    }
+    #[test]
+    fn test_jacobi_svd_graded() {
+        // A = D1 B D2 with a wide grading, singular values span many orders of magnitude
+        let n = 6;
+        let mut a = generate_random_matrix(n + 2, n);
+        for i in 0..n + 2 {
+            for j in 0..n {
+                a.data[i * n + j] *= 10f32.powi(-(j as i32));
+            }
+        }
+        let (svd, sweeps, converged) = jacobi_svd(&a);
+        assert!(converged && sweeps <= MAX_SWEEPS);
+        let (u, v) = (svd.u.as_ref().unwrap(), svd.v.as_ref().unwrap());
+        assert!(svd.s.windows(2).all(|w| w[0] >= w[1]));
+        // AV = US column by column, relative to each singular value
+        let av = matrix_mult(&a, v);
+        for k in 0..n {
+            for i in 0..n + 2 {
+                let expected = u.data[i * n + k] * svd.s[k];
+                assert!((av.data[i * n + k] - expected).abs() <= 1e-4 * svd.s[k]);
+            }
+        }
+        let vtv = matrix_mult(&v.transpose(), v);
+        assert!(approx_vector_eq(&vtv.data, &create_identity_matrix(n).data));
+        // wide input goes through the transpose
+        let wide = Svd::jacobi(&a.transpose());
+        assert!(approx_vector_eq(&wide.s, &svd.s));
+    }
+    #[test]
+    fn test_jacobi_eigen() {
+        let n = 9;
+        let a = generate_random_symetric(n);
+        let jacobi = SymmetricEigen::compute_with(&a, EigenRange::All, EigenMethod::Jacobi);
+        let qr = SymmetricEigen::compute(&a);
+        assert!(jacobi.converged);
+        assert!(approx_vector_eq(&jacobi.values, &qr.values));
+        let av = matrix_mult(&a, &jacobi.vectors);
+        let mut vd = jacobi.vectors.clone();
+        for i in 0..n {
+            for j in 0..n {
+                vd.data[i * n + j] *= jacobi.values[j];
+            }
+        }
+        assert!(approx_vector_eq(&av.data, &vd.data));
+    }
+}
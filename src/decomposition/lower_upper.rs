@@ -354,6 +354,26 @@ impl LuPivotDecompose {
         self.forward_solve_inplace_vec(y);
         self.backward_solve_inplace_vec(y);
     }
+    // A'x = b;
+    // A' = U'L'P;
+    // U'w = b, L'v = w, x = P'v
+    pub fn solve_transpose_inplace_vec(&self, y: &mut [f32]) {
+        debug_assert_eq!(self.matrix.dims[1], y.len());
+        let n = self.n;
+        let m = &self.matrix.data;
+        for i in 0..n {
+            for k in 0..i {
+                y[i] -= m[k * n + i] * y[k];
+            }
+            y[i] /= m[i * n + i];
+        }
+        for i in (0..n).rev() {
+            for k in i + 1..n {
+                y[i] -= m[k * n + i] * y[k];
+            }
+        }
+        self.unpivot_inplace_vec(y);
+    }
     pub fn pivot_inplace(&self, y: &mut NdArray) {
         let t_cols = y.dims[1];
         for (s, &d) in self.pivots.iter().enumerate() {
@@ -0,0 +1,324 @@
+use crate::algebra::ndmethods::{combine, create_identity_matrix, matrix_mult};
+use crate::decomposition::condition::one_norm;
+use crate::decomposition::eigen::symmetric::SymmetricEigen;
+use crate::decomposition::lower_upper::LuPivotDecompose;
+use crate::decomposition::schur::{SchurDecomp, real_schur, schur_blocks, small_solve};
+use crate::structure::ndarray::NdArray;
+
+// (degree, theta) in single precision, ||A||_1 <= theta bounds the pade error by the unit roundoff
+const PADE_THETA: [(usize, f32); 3] = [(3, 0.425873), (5, 1.8801527), (7, 3.9257248)];
+const PADE_3: [f32; 4] = [120.0, 60.0, 12.0, 1.0];
+const PADE_5: [f32; 6] = [30240.0, 15120.0, 3360.0, 420.0, 30.0, 1.0];
+const PADE_7: [f32; 8] = [
+    17297280.0, 8648640.0, 1995840.0, 277200.0, 25200.0, 1512.0, 56.0, 1.0,
+];
+// ||T - I||_1 bound for the log pade approximant after repeated square roots
+const LOG_THRESHOLD: f32 = 0.25;
+const MAX_SQRTS: usize = 40;
+// gauss legendre on [0, 1]
+const GAUSS_NODES: [f32; 7] = [
+    0.025446044,
+    0.1292344,
+    0.29707742,
+    0.5,
+    0.7029226,
+    0.87076559,
+    0.97455396,
+];
+const GAUSS_WEIGHTS: [f32; 7] = [
+    0.06474248, 0.1398527, 0.19091503, 0.20897959, 0.19091503, 0.1398527, 0.06474248,
+];
+
+// R'TR
+fn unrotate(schur: &SchurDecomp, t: &NdArray) -> NdArray {
+    let r = &schur.rotation;
+    matrix_mult(&matrix_mult(&r.transpose(), t), r)
+}
+
+/// expm
+/// scaling and squaring with a diagonal pade approximant, exp(A) = r(A / 2^s)^(2^s)
+///
+/// * a: square matrix
+pub fn expm(a: &NdArray) -> NdArray {
+    let n = a.dims[0];
+    debug_assert_eq!(n, a.dims[1], "expm requires a square matrix");
+    let norm = one_norm(a);
+    let (degree, s) = match PADE_THETA.iter().find(|(_, theta)| norm <= *theta) {
+        Some(&(degree, _)) => (degree, 0),
+        None => (7, (norm / PADE_THETA[2].1).log2().ceil() as i32),
+    };
+    let b: &[f32] = match degree {
+        3 => &PADE_3,
+        5 => &PADE_5,
+        _ => &PADE_7,
+    };
+    let mut a = a.clone();
+    let scale = 2f32.powi(-s);
+    a.data.iter_mut().for_each(|v| *v *= scale);
+    // even powers I, A^2, A^4, ..
+    let a2 = matrix_mult(&a, &a);
+    let mut powers = vec![create_identity_matrix(n)];
+    while powers.len() <= degree / 2 {
+        let next = matrix_mult(&powers[powers.len() - 1], &a2);
+        powers.push(next);
+    }
+    let odd: Vec<(f32, &NdArray)> = powers
+        .iter()
+        .enumerate()
+        .map(|(k, p)| (b[2 * k + 1], p))
+        .collect();
+    let even: Vec<(f32, &NdArray)> = powers
+        .iter()
+        .enumerate()
+        .map(|(k, p)| (b[2 * k], p))
+        .collect();
+    let u = matrix_mult(&a, &combine(&odd));
+    let v = combine(&even);
+    // (V - U)X = V + U
+    let mut x = combine(&[(1f32, &v), (1f32, &u)]);
+    let lhs = combine(&[(1f32, &v), (-1f32, &u)]);
+    let mut workspace = vec![0f32; n];
+    LuPivotDecompose::new(lhs, &mut workspace).solve_inplace(&mut x);
+    for _ in 0..s {
+        x = matrix_mult(&x, &x);
+    }
+    x
+}
+
+/// powm
+/// integer matrix power by repeated squaring, negative powers invert A first
+///
+/// * a: square matrix
+/// * p: exponent
+pub fn powm(a: &NdArray, p: i32) -> NdArray {
+    let n = a.dims[0];
+    debug_assert_eq!(n, a.dims[1], "powm requires a square matrix");
+    let mut base = a.clone();
+    if p < 0 {
+        base = create_identity_matrix(n);
+        let mut workspace = vec![0f32; n];
+        LuPivotDecompose::new(a.clone(), &mut workspace).solve_inplace(&mut base);
+    }
+    let mut e = p.unsigned_abs();
+    let mut result = create_identity_matrix(n);
+    while e > 0 {
+        if e & 1 == 1 {
+            result = matrix_mult(&result, &base);
+        }
+        e >>= 1;
+        if e > 0 {
+            base = matrix_mult(&base, &base);
+        }
+    }
+    result
+}
+
+/// sqrt_quasi
+/// principal square root R^2 = T of a real quasi triangular matrix, block by block
+/// negative real eigenvalues have no real square root and produce NaN
+///
+/// * t: quasi triangular matrix data
+/// * n: number of rows
+/// * blocks: (start, size) of the diagonal blocks of t
+fn sqrt_quasi(t: &[f64], n: usize, blocks: &[(usize, usize)]) -> Vec<f64> {
+    let mut r = vec![0f64; n * n];
+    for &(p, size) in blocks {
+        if size == 1 {
+            r[p * n + p] = t[p * n + p].sqrt();
+            continue;
+        }
+        // theta + i mu = eigenvalue, alpha + i beta = its principal root
+        let (a, b, c, d) = (
+            t[p * n + p],
+            t[p * n + p + 1],
+            t[(p + 1) * n + p],
+            t[(p + 1) * n + p + 1],
+        );
+        let theta = 0.5 * (a + d);
+        let half = 0.5 * (a - d);
+        let mu = (-(half * half + b * c)).max(0f64).sqrt();
+        let alpha = (0.5 * (theta.hypot(mu) + theta)).sqrt();
+        // R = alpha I + (T - theta I) / 2 alpha
+        let inv = 0.5 / alpha;
+        r[p * n + p] = alpha + (a - theta) * inv;
+        r[p * n + p + 1] = b * inv;
+        r[(p + 1) * n + p] = c * inv;
+        r[(p + 1) * n + p + 1] = alpha + (d - theta) * inv;
+    }
+    for jb in 0..blocks.len() {
+        let (j, q) = blocks[jb];
+        for &(i, p) in blocks[..jb].iter().rev() {
+            // R_ii X + X R_jj = T_ij - sum R_ik R_kj
+            let m = p * q;
+            let mut rhs = [0f64; 4];
+            let mut k = [0f64; 16];
+            for a in 0..p {
+                for b in 0..q {
+                    let row = a * q + b;
+                    let mut v = t[(i + a) * n + j + b];
+                    for l in i + p..j {
+                        v -= r[(i + a) * n + l] * r[l * n + j + b];
+                    }
+                    rhs[row] = v;
+                    for c in 0..p {
+                        k[row * m + c * q + b] += r[(i + a) * n + i + c];
+                    }
+                    for d in 0..q {
+                        k[row * m + a * q + d] += r[(j + d) * n + j + b];
+                    }
+                }
+            }
+            small_solve(&mut k[..m * m], &mut rhs[..m], m);
+            for a in 0..p {
+                for b in 0..q {
+                    r[(i + a) * n + j + b] = rhs[a * q + b];
+                }
+            }
+        }
+    }
+    r
+}
+
+/// sqrtm
+/// principal square root through the real schur form A = R'TR, sqrt(A) = R' sqrt(T) R
+///
+/// * a: square matrix without eigenvalues on the closed negative real axis
+pub fn sqrtm(a: &NdArray) -> NdArray {
+    let n = a.dims[0];
+    let (mut schur, _, converged) = real_schur(a);
+    debug_assert!(converged, "schur form did not converge");
+    let blocks = schur_blocks(&mut schur.kernel.data, n);
+    let t: Vec<f64> = schur.kernel.data.iter().map(|&x| x as f64).collect();
+    let root = sqrt_quasi(&t, n, &blocks);
+    let root = NdArray::new(vec![n, n], root.into_iter().map(|x| x as f32).collect());
+    unrotate(&schur, &root)
+}
+
+/// logm
+/// inverse scaling and squaring, log(A) = 2^k log(T^(1/2^k)) with a gauss legendre
+/// quadrature of log(I + X) = int_0^1 X(I + tX)^-1 dt on the schur form
+///
+/// * a: square matrix without eigenvalues on the closed negative real axis
+pub fn logm(a: &NdArray) -> NdArray {
+    let n = a.dims[0];
+    let (mut schur, _, converged) = real_schur(a);
+    debug_assert!(converged, "schur form did not converge");
+    // square roots keep the block structure of T
+    let blocks = schur_blocks(&mut schur.kernel.data, n);
+    let identity = create_identity_matrix(n);
+    let mut t: Vec<f64> = schur.kernel.data.iter().map(|&x| x as f64).collect();
+    let mut k = 0;
+    let mut x = NdArray::new(vec![n, n], vec![0f32; n * n]);
+    loop {
+        for (xi, (&ti, &ii)) in x.data.iter_mut().zip(t.iter().zip(&identity.data)) {
+            *xi = ti as f32 - ii;
+        }
+        if k == MAX_SQRTS || one_norm(&x) <= LOG_THRESHOLD {
+            break;
+        }
+        t = sqrt_quasi(&t, n, &blocks);
+        k += 1;
+    }
+    let mut log = NdArray::new(vec![n, n], vec![0f32; n * n]);
+    let mut workspace = vec![0f32; n];
+    for (&node, &weight) in GAUSS_NODES.iter().zip(&GAUSS_WEIGHTS) {
+        // (I + tX)^-1 X as X commutes with (I + tX)
+        let lhs = combine(&[(1f32, &identity), (node, &x)]);
+        let mut y = x.clone();
+        LuPivotDecompose::new(lhs, &mut workspace).solve_inplace(&mut y);
+        for (l, v) in log.data.iter_mut().zip(&y.data) {
+            *l += weight * v;
+        }
+    }
+    let scale = 2f32.powi(k as i32);
+    log.data.iter_mut().for_each(|v| *v *= scale);
+    unrotate(&schur, &log)
+}
+
+/// funm_symmetric
+/// f(A) = V f(D) V' for a symmetric matrix A = VDV'
+///
+/// * a: symmetric matrix
+/// * f: scalar function applied to the eigenvalues
+pub fn funm_symmetric(a: &NdArray, f: impl Fn(f32) -> f32) -> NdArray {
+    let n = a.dims[0];
+    let eig = SymmetricEigen::compute(a);
+    let mut vf = eig.vectors.clone();
+    for i in 0..n {
+        for (j, &lambda) in eig.values.iter().enumerate() {
+            vf.data[i * n + j] *= f(lambda);
+        }
+    }
+    matrix_mult(&vf, &eig.vectors.transpose())
+}
+
+#[cfg(test)]
+mod test_matfun {
+    use super::*;
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::{generate_random_matrix, generate_random_symetric};
+
+    fn scaled(a: &NdArray, alpha: f32) -> NdArray {
+        let mut a = a.clone();
+        a.data.iter_mut().for_each(|v| *v *= alpha);
+        a
+    }
+    #[test]
+    fn test_expm() {
+        // exp of the rotation generator
+        let theta = 0.7f32;
+        let a = NdArray::new(vec![2, 2], vec![0f32, -theta, theta, 0f32]);
+        let (c, s) = (theta.cos(), theta.sin());
+        assert!(approx_vector_eq(&expm(&a).data, &[c, -s, s, c]));
+        // exp(A)exp(-A) = I across all pade degrees and with squaring
+        for alpha in [0.05f32, 0.3, 1.0, 3.0] {
+            let n = 6;
+            let a = scaled(&generate_random_matrix(n, n), alpha / n as f32);
+            let product = matrix_mult(&expm(&a), &expm(&scaled(&a, -1f32)));
+            assert!(approx_vector_eq(
+                &product.data,
+                &create_identity_matrix(n).data
+            ));
+        }
+        // agrees with the symmetric eigendecomposition
+        let a = scaled(&generate_random_symetric(5), 0.5);
+        assert!(approx_vector_eq(
+            &expm(&a).data,
+            &funm_symmetric(&a, f32::exp).data
+        ));
+    }
+    #[test]
+    fn test_sqrtm_logm() {
+        let n = 7;
+        // eigenvalues near 4, complex pairs included
+        let mut a = scaled(&generate_random_matrix(n, n), 0.5);
+        for i in 0..n {
+            a.data[i * n + i] += 4f32;
+        }
+        let root = sqrtm(&a);
+        assert!(approx_vector_eq(&matrix_mult(&root, &root).data, &a.data));
+        let log = logm(&a);
+        assert!(approx_vector_eq(&expm(&log).data, &a.data));
+        let small = scaled(&generate_random_matrix(n, n), 0.2);
+        assert!(approx_vector_eq(&logm(&expm(&small)).data, &small.data));
+    }
+    #[test]
+    fn test_powm() {
+        let n = 5;
+        let mut a = generate_random_matrix(n, n);
+        for i in 0..n {
+            a.data[i * n + i] += n as f32;
+        }
+        let cube = matrix_mult(&matrix_mult(&a, &a), &a);
+        assert!(approx_vector_eq(&powm(&a, 3).data, &cube.data));
+        assert!(approx_vector_eq(
+            &powm(&a, 0).data,
+            &create_identity_matrix(n).data
+        ));
+        let product = matrix_mult(&powm(&a, -2), &powm(&a, 2));
+        assert!(approx_vector_eq(
+            &product.data,
+            &create_identity_matrix(n).data
+        ));
+    }
+}
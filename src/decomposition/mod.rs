@@ -1,13 +1,20 @@
 pub mod cholesky;
+pub mod condition;
+pub mod eigen;
 pub mod francis;
 pub mod givens;
 pub mod golub_kahan;
 pub mod householder;
+pub mod jacobi;
 pub mod lower_upper;
 pub mod lq;
+pub mod matfun;
+pub mod polar;
 pub mod qr;
 pub mod qr_matrix;
+pub mod qr_update;
 pub mod schur;
 pub mod sgivens;
 pub mod svd;
+pub mod tsqr;
 pub mod wy;
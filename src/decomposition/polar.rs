@@ -0,0 +1,231 @@
+use crate::algebra::ndmethods::{create_identity_matrix, frobenius, matrix_mult, symmetrize};
+use crate::decomposition::svd::decomp::{Svd, SvdMode};
+use crate::structure::ndarray::NdArray;
+
+const TOLERANCE: f32 = 1e-6;
+const MAX_ITERS: usize = 100;
+
+/// PolarMethod
+/// how the orthogonal factor is computed
+///
+/// * Svd: U = WV' from the thin svd A = WSV'
+/// * NewtonSchulz: X <- X(3I - X'X) / 2 from X = A / ||A||_F, converges slowly for ill conditioned A
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PolarMethod {
+    Svd,
+    NewtonSchulz,
+}
+
+/// PolarDecomp
+/// polar decomposition A = UP of a matrix with rows >= cols
+///
+/// * u: orthonormal columns ~ rows x cols
+/// * p: symmetric positive semidefinite factor ~ cols x cols
+/// * iterations: newton schulz steps, zero for the svd method
+/// * converged: false if newton schulz ran out of iterations before X'X = I
+pub struct PolarDecomp {
+    pub u: NdArray,
+    pub p: NdArray,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Procrustes
+/// minimizer of ||sAR - B||_F over orthogonal R and scale s, points stored as rows
+///
+/// * rotation: orthogonal matrix ~ d x d, det = 1 unless reflections are allowed
+/// * scale: s, one without scaling
+/// * residual: ||sAR - B||_F
+pub struct Procrustes {
+    pub rotation: NdArray,
+    pub scale: f32,
+    pub residual: f32,
+}
+
+// sign of the determinant by gaussian elimination with partial pivoting
+fn determinant_sign(a: &NdArray) -> f32 {
+    let n = a.dims[0];
+    let mut m = a.data.clone();
+    let mut sign = 1f32;
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|&i, &j| m[i * n + k].abs().total_cmp(&m[j * n + k].abs()))
+            .unwrap_or(k);
+        if m[p * n + k] == 0f32 {
+            return 0f32;
+        }
+        if p != k {
+            for j in 0..n {
+                m.swap(k * n + j, p * n + j);
+            }
+            sign = -sign;
+        }
+        sign *= m[k * n + k].signum();
+        for i in k + 1..n {
+            let f = m[i * n + k] / m[k * n + k];
+            for j in k..n {
+                m[i * n + j] -= f * m[k * n + j];
+            }
+        }
+    }
+    sign
+}
+
+impl PolarDecomp {
+    pub fn compute(a: &NdArray) -> Self {
+        Self::compute_with(a, PolarMethod::Svd)
+    }
+    pub fn compute_with(a: &NdArray, method: PolarMethod) -> Self {
+        let (rows, cols) = (a.dims[0], a.dims[1]);
+        debug_assert!(rows >= cols, "polar decomposition requires rows >= cols");
+        let (u, iterations, converged) = match method {
+            PolarMethod::Svd => {
+                let svd = Svd::compute(a, SvdMode::Thin);
+                let (w, v) = (svd.u.unwrap(), svd.v.unwrap());
+                (matrix_mult(&w, &v.transpose()), 0, true)
+            }
+            PolarMethod::NewtonSchulz => {
+                let mut x = a.clone();
+                let norm = frobenius(a).max(f32::MIN_POSITIVE);
+                x.data.iter_mut().for_each(|v| *v /= norm);
+                let identity = create_identity_matrix(cols);
+                let (mut iterations, mut converged) = (0, false);
+                while iterations < MAX_ITERS {
+                    // E = I - X'X
+                    let mut e = matrix_mult(&x.transpose(), &x);
+                    for (ei, ii) in e.data.iter_mut().zip(&identity.data) {
+                        *ei = ii - *ei;
+                    }
+                    if frobenius(&e) <= TOLERANCE * cols as f32 {
+                        converged = true;
+                        break;
+                    }
+                    // X(3I - X'X) / 2 = X + XE / 2
+                    let correction = matrix_mult(&x, &e);
+                    for (xi, ci) in x.data.iter_mut().zip(&correction.data) {
+                        *xi += 0.5 * ci;
+                    }
+                    iterations += 1;
+                }
+                (x, iterations, converged)
+            }
+        };
+        // P = U'A
+        let mut p = matrix_mult(&u.transpose(), a);
+        symmetrize(&mut p);
+        Self {
+            u,
+            p,
+            iterations,
+            converged,
+        }
+    }
+}
+
+/// procrustes
+/// orthogonal procrustes / kabsch alignment of the point sets A and B ~ points x d,
+/// R = WV' from the svd A'B = WSV' with the last singular pair flipped to exclude reflections
+/// and s = tr(S) / ||A||_F^2, center the points beforehand for a rigid alignment
+///
+/// * a: source points
+/// * b: target points
+/// * allow_scaling: fit s, otherwise s = 1
+/// * allow_reflection: accept det(R) = -1
+pub fn procrustes(
+    a: &NdArray,
+    b: &NdArray,
+    allow_scaling: bool,
+    allow_reflection: bool,
+) -> Procrustes {
+    debug_assert_eq!(
+        a.dims, b.dims,
+        "procrustes requires point sets of equal shape"
+    );
+    let d = a.dims[1];
+    let svd = Svd::compute(&matrix_mult(&a.transpose(), b), SvdMode::Full);
+    let (mut w, v) = (svd.u.unwrap(), svd.v.unwrap());
+    let mut s = svd.s.clone();
+    if !allow_reflection && d > 0 && determinant_sign(&w) * determinant_sign(&v) < 0f32 {
+        for i in 0..d {
+            w.data[i * d + d - 1] = -w.data[i * d + d - 1];
+        }
+        s[d - 1] = -s[d - 1];
+    }
+    let rotation = matrix_mult(&w, &v.transpose());
+    let scale = if allow_scaling {
+        let norm = frobenius(a);
+        s.iter().sum::<f32>() / (norm * norm).max(f32::MIN_POSITIVE)
+    } else {
+        1f32
+    };
+    let mut fitted = matrix_mult(a, &rotation);
+    for (f, bi) in fitted.data.iter_mut().zip(&b.data) {
+        *f = scale * *f - bi;
+    }
+    Procrustes {
+        rotation,
+        scale,
+        residual: frobenius(&fitted),
+    }
+}
+
+#[cfg(test)]
+mod test_polar {
+    use super::*;
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_matrix;
+
+    #[test]
+    fn test_polar_methods() {
+        let (rows, cols) = (7, 4);
+        let a = generate_random_matrix(rows, cols);
+        let identity = create_identity_matrix(cols);
+        for method in [PolarMethod::Svd, PolarMethod::NewtonSchulz] {
+            let polar = PolarDecomp::compute_with(&a, method);
+            assert!(polar.converged);
+            assert!(approx_vector_eq(
+                &matrix_mult(&polar.u, &polar.p).data,
+                &a.data
+            ));
+            let utu = matrix_mult(&polar.u.transpose(), &polar.u);
+            assert!(approx_vector_eq(&utu.data, &identity.data));
+            // P is positive semidefinite
+            let svd = Svd::compute(&polar.p, SvdMode::ValuesOnly);
+            let trace: f32 = (0..cols).map(|i| polar.p.data[i * cols + i]).sum();
+            assert!((trace - svd.s.iter().sum::<f32>()).abs() < 1e-2);
+        }
+        // a zero column has no orthonormal polar factor for newton schulz to reach
+        let mut deficient = a.clone();
+        for i in 0..rows {
+            deficient.data[i * cols] = 0f32;
+        }
+        let polar = PolarDecomp::compute_with(&deficient, PolarMethod::NewtonSchulz);
+        assert!(!polar.converged);
+        assert_eq!(polar.iterations, MAX_ITERS);
+    }
+    #[test]
+    fn test_procrustes() {
+        let (points, d) = (20, 3);
+        let a = generate_random_matrix(points, d);
+        // proper rotation from the polar factor of a random matrix
+        let mut r = PolarDecomp::compute(&generate_random_matrix(d, d)).u;
+        if determinant_sign(&r) < 0f32 {
+            r.data.iter_mut().take(d).for_each(|v| *v = -*v);
+        }
+        let mut b = matrix_mult(&a, &r);
+        b.data.iter_mut().for_each(|v| *v *= 2.5);
+        let fit = procrustes(&a, &b, true, false);
+        assert!(approx_vector_eq(&fit.rotation.data, &r.data));
+        assert!((fit.scale - 2.5).abs() < 1e-3);
+        assert!(fit.residual < 1e-3);
+        // a mirrored target cannot be matched by a proper rotation
+        let mut mirrored = matrix_mult(&a, &r);
+        for i in 0..points {
+            mirrored.data[i * d] = -mirrored.data[i * d];
+        }
+        let proper = procrustes(&a, &mirrored, false, false);
+        let reflected = procrustes(&a, &mirrored, false, true);
+        assert!(determinant_sign(&proper.rotation) > 0f32);
+        assert!(reflected.residual < 1e-3 && proper.residual > 1e-1);
+    }
+}
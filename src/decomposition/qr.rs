@@ -2,6 +2,8 @@ use crate::algebra::ndmethods::create_identity_rectangle;
 use crate::decomposition::householder::{HouseholderReflection, householder_params};
 use crate::structure::ndarray::NdArray;
 const THRESHOLD: f32 = 1e-6;
+// R[i][i] below this fraction of max |R[k][k]| is treated as a rank deficient column
+pub(crate) const RANK_TOLERANCE: f32 = 1e-6;
 #[derive(Debug)]
 pub struct QrDecomposition {
     // Qn..Q1 * A = R;
@@ -21,7 +23,7 @@ impl QrDecomposition {
         let (rows, cols) = (x.dims[0], x.dims[1]);
         let card = rows.min(cols) - (rows <= cols) as usize;
         let mut projections = Vec::with_capacity(card);
-        let mut w = vec![0f32; rows];
+        let mut w = vec![0f32; cols];
         for o in 0..card {
             let column_vector = (o..rows)
                 .map(|r| x.data[r * cols + o])
@@ -29,9 +31,11 @@ impl QrDecomposition {
             let proj = householder_params(column_vector);
             // x'A
             if proj.beta.abs() < THRESHOLD {
+                // keep the slot so projections[p] stays the reflection of column p
+                projections.push(proj);
                 continue;
             }
-            for j in o..=card {
+            for j in o..cols {
                 for i in o..rows {
                     w[j] += proj.vector[i - o] * x.data[i * cols + j];
                 }
@@ -48,7 +52,7 @@ impl QrDecomposition {
         // QR(A) -> Q ~ M[m,n], R ~ M[n,n];
         x.data.truncate(cols * cols);
         x.dims[0] = rows.min(cols);
-        for i in 1..=card {
+        for i in 1..rows.min(cols) {
             // for j in 0..i.min(cols) {
             for j in 0..i {
                 x.data[i * cols + j] = 0f32
@@ -289,4 +293,78 @@ impl QrDecomposition {
         }
         data
     }
+    // Ax = b, A square;
+    // QRx = b;
+    // Rx = Q'b;
+    pub fn solve_inplace_vec(&self, y: &mut [f32]) {
+        debug_assert!(self.rows == self.cols && y.len() == self.rows);
+        let n = self.cols;
+        for (p, proj) in self.projections.iter().enumerate() {
+            if proj.beta.abs() < THRESHOLD {
+                continue;
+            }
+            let scalar: f32 = (p..n).map(|i| y[i] * proj.vector[i - p]).sum();
+            for i in p..n {
+                y[i] -= scalar * proj.beta * proj.vector[i - p];
+            }
+        }
+        let r = &self.triangle.data;
+        for i in (0..n).rev() {
+            for k in i + 1..n {
+                y[i] -= r[i * n + k] * y[k];
+            }
+            y[i] /= r[i * n + i];
+        }
+    }
+    // A'x = b, A square;
+    // R'Q'x = b;
+    // R'w = b, x = Qw
+    pub fn solve_transpose_inplace_vec(&self, y: &mut [f32]) {
+        debug_assert!(self.rows == self.cols && y.len() == self.rows);
+        let n = self.cols;
+        let r = &self.triangle.data;
+        for i in 0..n {
+            for k in 0..i {
+                y[i] -= r[k * n + i] * y[k];
+            }
+            y[i] /= r[i * n + i];
+        }
+        for (p, proj) in self.projections.iter().enumerate().rev() {
+            if proj.beta.abs() < THRESHOLD {
+                continue;
+            }
+            let scalar: f32 = (p..n).map(|i| y[i] * proj.vector[i - p]).sum();
+            for i in p..n {
+                y[i] -= scalar * proj.beta * proj.vector[i - p];
+            }
+        }
+    }
+}
+
+// min ||a x - b||, a ~ M[m,n], rows are padded with zeros when m < n, a is expected to have
+// full column rank and a vanishing R[i][i] zeroes x[i] rather than dividing by it
+pub(crate) fn solve_qr(mut a: NdArray, b: &[f32]) -> Vec<f32> {
+    let (m, n) = (a.dims[0], a.dims[1]);
+    let rows = m.max(n);
+    a.data.resize(rows * n, 0f32);
+    a.dims[0] = rows;
+    let mut rhs = b.to_vec();
+    rhs.resize(rows, 0f32);
+    let qr = QrDecomposition::new(a);
+    let mut y = NdArray::new(vec![rows, 1], rhs);
+    qr.left_apply_qt(&mut y);
+    let r = &qr.triangle.data;
+    let scale = (0..n).fold(0f32, |acc, i| acc.max(r[i * n + i].abs()));
+    let mut x = y.data;
+    for i in (0..n).rev() {
+        if r[i * n + i].abs() <= RANK_TOLERANCE * scale {
+            x[i] = 0f32;
+            continue;
+        }
+        for k in i + 1..n {
+            x[i] -= r[i * n + k] * x[k];
+        }
+        x[i] /= r[i * n + i];
+    }
+    x
 }
@@ -0,0 +1,343 @@
+use crate::algebra::ndmethods::create_identity_matrix;
+use crate::decomposition::sgivens::{apply_g_left, apply_gt_right, implicit_givens_rotation};
+use crate::structure::ndarray::NdArray;
+
+const EPSILON: f32 = 1e-12;
+
+/// QrUpdate
+/// Givens based QR which keeps an explicit Q so that the factorization
+/// can be updated in O(n^2) instead of being rebuilt
+///
+/// * rows: rows in the current matrix A
+/// * cols: cols in the current matrix A
+/// * q: orthogonal factor ~ rows x rows
+/// * r: upper trapezoid factor ~ rows x cols
+pub struct QrUpdate {
+    pub rows: usize,
+    pub cols: usize,
+    pub q: NdArray,
+    pub r: NdArray,
+}
+
+impl QrUpdate {
+    pub fn new(r: NdArray) -> Self {
+        let (rows, cols) = (r.dims[0], r.dims[1]);
+        let q = create_identity_matrix(rows);
+        let mut qr = Self { rows, cols, q, r };
+        // zero each column bottom up, rows (i - 1, i) for every i > j
+        for j in 0..cols.min(rows.saturating_sub(1)) {
+            for i in (j + 1..rows).rev() {
+                qr.rotate(i - 1, i, j);
+            }
+        }
+        qr
+    }
+    /// rotate
+    /// zeros r[j, col] against r[i, col] and accumulates G' into q
+    ///
+    /// * i: pivot row
+    /// * j: row to zero
+    /// * col: column of the element being zeroed, columns before are assumed zero
+    fn rotate(&mut self, i: usize, j: usize, col: usize) {
+        let (m, n) = (self.rows, self.cols);
+        let a = self.r.data[i * n + col];
+        let b = self.r.data[j * n + col];
+        if b.abs() < EPSILON {
+            self.r.data[j * n + col] = 0f32;
+            return;
+        }
+        let (_, c, s) = implicit_givens_rotation(a, b);
+        // G * R ; Q * G'
+        apply_g_left(&mut self.r.data[col..], i, j, n, n - col, c, s);
+        apply_gt_right(&mut self.q.data, i, j, m, m, c, s);
+        self.r.data[j * n + col] = 0f32;
+    }
+    fn hessenberg_to_triangle(&mut self, start: usize) {
+        // zeros the sub diagonal r[j + 1, j] for j >= start
+        for j in start..self.cols.min(self.rows.saturating_sub(1)) {
+            self.rotate(j, j + 1, j);
+        }
+    }
+    pub fn left_apply_qt_vec(&self, x: &[f32]) -> Vec<f32> {
+        // Q'x
+        debug_assert_eq!(x.len(), self.rows);
+        let m = self.rows;
+        let mut out = vec![0f32; m];
+        for i in 0..m {
+            let scalar = x[i];
+            let q_row = &self.q.data[i * m..(i + 1) * m];
+            for j in 0..m {
+                out[j] += scalar * q_row[j];
+            }
+        }
+        out
+    }
+}
+
+impl QrUpdate {
+    /// rank_one_update
+    /// A + uv' = Q(R + wv') with w = Q'u
+    ///
+    /// * u: column vector ~ rows
+    /// * v: row vector ~ cols
+    pub fn rank_one_update(&mut self, u: &[f32], v: &[f32]) {
+        debug_assert_eq!(u.len(), self.rows);
+        debug_assert_eq!(v.len(), self.cols);
+        let (m, n) = (self.rows, self.cols);
+        let mut w = self.left_apply_qt_vec(u);
+        // collapse w into w[0] e1, R turns upper hessenberg
+        for j in (1..m).rev() {
+            if w[j].abs() < EPSILON {
+                continue;
+            }
+            let (r, c, s) = implicit_givens_rotation(w[j - 1], w[j]);
+            w[j - 1] = r;
+            w[j] = 0f32;
+            let col = (j - 1).min(n);
+            apply_g_left(&mut self.r.data[col..], j - 1, j, n, n - col, c, s);
+            apply_gt_right(&mut self.q.data, j - 1, j, m, m, c, s);
+        }
+        for j in 0..n {
+            self.r.data[j] += w[0] * v[j];
+        }
+        self.hessenberg_to_triangle(0);
+    }
+    /// insert_row
+    /// inserts `row` so that it becomes row k of A
+    ///
+    /// * k: index of the new row in A
+    /// * row: data of the new row ~ cols
+    pub fn insert_row(&mut self, k: usize, row: &[f32]) {
+        debug_assert!(k <= self.rows);
+        debug_assert_eq!(row.len(), self.cols);
+        let (m, n) = (self.rows, self.cols);
+        let nm = m + 1;
+        // R* = [row; R], Q* = P[1, 0; 0, Q] with row k of Q* equal to e1
+        let mut r = Vec::with_capacity(nm * n);
+        r.extend_from_slice(row);
+        r.extend_from_slice(&self.r.data);
+        let mut q = vec![0f32; nm * nm];
+        for i in 0..nm {
+            if i == k {
+                q[i * nm] = 1f32;
+                continue;
+            }
+            let src = if i < k { i } else { i - 1 };
+            q[i * nm + 1..(i + 1) * nm].copy_from_slice(&self.q.data[src * m..(src + 1) * m]);
+        }
+        self.rows = nm;
+        self.r = NdArray::new(vec![nm, n], r);
+        self.q = NdArray::new(vec![nm, nm], q);
+        self.hessenberg_to_triangle(0);
+    }
+    /// delete_row
+    /// removes row k of A
+    ///
+    /// * k: index of the row to remove
+    pub fn delete_row(&mut self, k: usize) {
+        debug_assert!(k < self.rows);
+        debug_assert!(self.rows > 1);
+        let (m, n) = (self.rows, self.cols);
+        // q^k ~ row k of Q, rotate into alpha e1 while R turns upper hessenberg
+        let mut qk = self.q.data[k * m..(k + 1) * m].to_vec();
+        for j in (1..m).rev() {
+            let (r, c, s) = implicit_givens_rotation(qk[j - 1], qk[j]);
+            qk[j - 1] = r;
+            qk[j] = 0f32;
+            let col = (j - 1).min(n);
+            apply_g_left(&mut self.r.data[col..], j - 1, j, n, n - col, c, s);
+            apply_gt_right(&mut self.q.data, j - 1, j, m, m, c, s);
+        }
+        // Q ~ [alpha, 0; 0, Q*] up to the row permutation, R* drops the first row
+        let nm = m - 1;
+        let mut q = Vec::with_capacity(nm * nm);
+        for i in 0..m {
+            if i == k {
+                continue;
+            }
+            q.extend_from_slice(&self.q.data[i * m + 1..(i + 1) * m]);
+        }
+        let r = self.r.data[n..].to_vec();
+        self.rows = nm;
+        self.r = NdArray::new(vec![nm, n], r);
+        self.q = NdArray::new(vec![nm, nm], q);
+    }
+    /// insert_col
+    /// inserts `col` so that it becomes column k of A
+    ///
+    /// * k: index of the new column in A
+    /// * col: data of the new column ~ rows
+    pub fn insert_col(&mut self, k: usize, col: &[f32]) {
+        debug_assert!(k <= self.cols);
+        debug_assert_eq!(col.len(), self.rows);
+        let (m, n) = (self.rows, self.cols);
+        let nn = n + 1;
+        let w = self.left_apply_qt_vec(col);
+        let mut r = Vec::with_capacity(m * nn);
+        for i in 0..m {
+            let row = &self.r.data[i * n..(i + 1) * n];
+            r.extend_from_slice(&row[..k]);
+            r.push(w[i]);
+            r.extend_from_slice(&row[k..]);
+        }
+        self.cols = nn;
+        self.r = NdArray::new(vec![m, nn], r);
+        // columns right of k are shifted so fill lands on the diagonal
+        for i in (k + 1..m).rev() {
+            self.rotate(i - 1, i, k);
+        }
+    }
+    /// delete_col
+    /// removes column k of A
+    ///
+    /// * k: index of the column to remove
+    pub fn delete_col(&mut self, k: usize) {
+        debug_assert!(k < self.cols);
+        let (m, n) = (self.rows, self.cols);
+        let nn = n - 1;
+        let mut r = Vec::with_capacity(m * nn);
+        for i in 0..m {
+            let row = &self.r.data[i * n..(i + 1) * n];
+            r.extend_from_slice(&row[..k]);
+            r.extend_from_slice(&row[k + 1..]);
+        }
+        self.cols = nn;
+        self.r = NdArray::new(vec![m, nn], r);
+        self.hessenberg_to_triangle(k);
+    }
+}
+
+impl QrUpdate {
+    /// solve
+    /// least squares min ||Ax - b|| through Rx = (Q'b)[..cols]
+    /// requires rows >= cols and a full column rank A
+    ///
+    /// * b: target ~ rows
+    pub fn solve(&self, b: &[f32]) -> Vec<f32> {
+        debug_assert!(self.rows >= self.cols);
+        let n = self.cols;
+        let mut x = self.left_apply_qt_vec(b);
+        x.truncate(n);
+        for i in (0..n).rev() {
+            for k in i + 1..n {
+                x[i] -= self.r.data[i * n + k] * x[k];
+            }
+            x[i] /= self.r.data[i * n + i];
+        }
+        x
+    }
+    /// residual
+    /// norm of the least squares residual ||(Q'b)[cols..]||
+    ///
+    /// * b: target ~ rows
+    pub fn residual(&self, b: &[f32]) -> f32 {
+        let w = self.left_apply_qt_vec(b);
+        w[self.cols.min(self.rows)..]
+            .iter()
+            .map(|v| v * v)
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+#[cfg(test)]
+mod test_qr_update {
+    use super::*;
+    use crate::algebra::ndmethods::{basic_mult, create_identity_matrix};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::{generate_random_matrix, generate_random_vector};
+
+    fn check_factors(qr: &QrUpdate, expected: &NdArray) {
+        let (m, n) = (qr.rows, qr.cols);
+        assert_eq!(expected.dims, vec![m, n]);
+        let result = basic_mult(&qr.q, &qr.r);
+        assert!(approx_vector_eq(&result.data, &expected.data));
+        let ortho = basic_mult(&qr.q.transpose(), &qr.q);
+        assert!(approx_vector_eq(
+            &ortho.data,
+            &create_identity_matrix(m).data
+        ));
+        for i in 0..m {
+            for j in 0..i.min(n) {
+                assert!(qr.r.data[i * n + j].abs() < 1e-4, "r not triangular");
+            }
+        }
+    }
+    #[test]
+    fn test_factorization() {
+        for (m, n) in [(1, 1), (4, 4), (8, 3), (3, 8)] {
+            let a = generate_random_matrix(m, n);
+            check_factors(&QrUpdate::new(a.clone()), &a);
+        }
+    }
+    #[test]
+    fn test_rank_one_update() {
+        for (m, n) in [(4, 4), (8, 3), (3, 8)] {
+            let mut a = generate_random_matrix(m, n);
+            let mut qr = QrUpdate::new(a.clone());
+            let u = generate_random_vector(m);
+            let v = generate_random_vector(n);
+            qr.rank_one_update(&u, &v);
+            for i in 0..m {
+                for j in 0..n {
+                    a.data[i * n + j] += u[i] * v[j];
+                }
+            }
+            check_factors(&qr, &a);
+        }
+    }
+    #[test]
+    fn test_rows() {
+        for (m, n) in [(4, 4), (8, 3), (3, 8)] {
+            let a = generate_random_matrix(m, n);
+            let row = generate_random_vector(n);
+            for k in [0, m / 2, m] {
+                let mut qr = QrUpdate::new(a.clone());
+                qr.insert_row(k, &row);
+                let mut data = a.data[..k * n].to_vec();
+                data.extend_from_slice(&row);
+                data.extend_from_slice(&a.data[k * n..]);
+                check_factors(&qr, &NdArray::new(vec![m + 1, n], data));
+                qr.delete_row(k);
+                check_factors(&qr, &a);
+            }
+        }
+    }
+    #[test]
+    fn test_cols() {
+        for (m, n) in [(4, 4), (8, 3), (3, 8)] {
+            let a = generate_random_matrix(m, n);
+            let col = generate_random_vector(m);
+            for k in [0, n / 2, n] {
+                let mut qr = QrUpdate::new(a.clone());
+                qr.insert_col(k, &col);
+                let mut expected = Vec::with_capacity(m * (n + 1));
+                for i in 0..m {
+                    expected.extend_from_slice(&a.data[i * n..i * n + k]);
+                    expected.push(col[i]);
+                    expected.extend_from_slice(&a.data[i * n + k..(i + 1) * n]);
+                }
+                check_factors(&qr, &NdArray::new(vec![m, n + 1], expected));
+                qr.delete_col(k);
+                check_factors(&qr, &a);
+            }
+        }
+    }
+    #[test]
+    fn test_least_squares() {
+        let (m, n) = (12, 4);
+        let a = generate_random_matrix(m, n);
+        let x = generate_random_vector(n);
+        let b = basic_mult(&a, &NdArray::new(vec![n, 1], x.clone())).data;
+        let mut qr = QrUpdate::new(a);
+        assert!(approx_vector_eq(&qr.solve(&b), &x));
+        assert!(qr.residual(&b) < 1e-3);
+        // streaming observation consistent with x
+        let row = generate_random_vector(n);
+        let target = row.iter().zip(x.iter()).map(|(r, x)| r * x).sum::<f32>();
+        let mut b = b;
+        b.push(target);
+        qr.insert_row(m, &row);
+        assert!(approx_vector_eq(&qr.solve(&b), &x));
+    }
+}
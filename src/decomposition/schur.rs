@@ -1,105 +1,371 @@
-use crate::algebra::ndmethods::create_identity_matrix;
-use crate::decomposition::lq::AutumnDecomp;
+use crate::algebra::ndmethods::create_identity_vector;
+use crate::algebra::vector::magnitude;
+use crate::decomposition::francis::complex::decomp_cpx;
+use crate::decomposition::francis::primitives::hessenberg;
+use crate::decomposition::householder::{householder, reflect_cols, reflect_rows};
 use crate::structure::ndarray::NdArray;
+use crate::structure::ndsignal::Complex;
 
 // schur takes in a matrix (A) -> outputs like a like bidiagonal like um triangle thing
 // it's strictly a triangle
 
-const CONVERGENCE_CONDITION: f32 = 1e-6;
-const LIMIT_ITERATION: usize = 2156;
+// francis steps per row shared by every window as in lapack, close eigenvalue pairs
+// can hold a single window for far more than 30 steps
+const MAX_ITERS: usize = 30;
+// supra diagonals below TOLERANCE * ||A|| deflate
+const TOLERANCE: f32 = 4f32 * f32::EPSILON;
 pub struct SchurDecomp {
     pub rotation: NdArray, // The current rotation
     pub kernel: NdArray,   // The upper quasi-triangular matrix (Schur form)
 }
 
-fn real_schur_iteration(
-    kernel: NdArray,
-    mut nkernel: NdArray,
-    rotation: &mut NdArray,
-    workspace: &mut [f32],
-) -> (NdArray, NdArray) {
-    let lq = AutumnDecomp::new(kernel);
-    // println!("--------------------------------------------------");
-    // println!("lq tau {:?}\nlq h {:?}", lq.t, lq.h);
+/// real_schur
+/// real schur form RAR' = T by householder reduction and francis double shift QR,
+/// real eigen pairs are split so every 2x2 block of T holds a conjugate pair
+///
+/// * a: square matrix
+///
+/// returns the decomposition, the number of francis steps and whether every block converged
+pub fn real_schur(a: &NdArray) -> (SchurDecomp, usize, bool) {
+    let n = a.dims[0];
+    debug_assert_eq!(n, a.dims[1], "schur requires a square matrix");
+    // the francis kernels reduce rows, A' = R'LR with L lower quasi triangular gives RAR' = L'
+    let mut h = a.transpose().data;
+    let mut r = create_identity_vector(n, n);
+    let mut p = vec![0f32; n.max(3)];
+    let mut w = vec![0f32; n.max(3)];
+    let tolerance = (TOLERANCE * magnitude(&a.data)).max(f32::MIN_POSITIVE);
+    hessenberg(&mut h, Some(&mut r), &mut p, &mut w, n, n, n);
+    let (iterations, converged) = decomp_cpx(
+        &mut h,
+        Some(&mut r),
+        &mut p,
+        &mut w,
+        n,
+        n,
+        n,
+        MAX_ITERS * n.max(10),
+        tolerance,
+    );
+    let schur = SchurDecomp {
+        rotation: NdArray::new(vec![n, n], r),
+        kernel: NdArray::new(vec![n, n], h).transpose(),
+    };
+    (schur, iterations, converged)
+}
+
+impl SchurDecomp {
+    /// compute
+    /// real schur form RAR' = T, see real_schur
+    ///
+    /// * a: square matrix
+    pub fn compute(a: &NdArray) -> Self {
+        real_schur(a).0
+    }
+}
+
+// (start, size) of the diagonal blocks, negligible subdiagonals are flushed to zero
+pub(crate) fn schur_blocks(t: &mut [f32], n: usize) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::with_capacity(n);
+    let mut i = 0;
+    while i < n {
+        let mut size = 1;
+        if i + 1 < n {
+            let scale = t[i * n + i].abs() + t[(i + 1) * n + i + 1].abs();
+            if t[(i + 1) * n + i].abs() <= f32::EPSILON * scale {
+                t[(i + 1) * n + i] = 0f32;
+            } else {
+                size = 2;
+            }
+        }
+        blocks.push((i, size));
+        i += size;
+    }
+    blocks
+}
+
+// eigenvalue of a diagonal block, the one with positive imaginary part for 2x2 blocks
+pub(crate) fn block_eigenvalue(t: &[f32], n: usize, p: usize, size: usize) -> Complex {
+    if size == 1 {
+        return Complex::new(t[p * n + p], 0f32);
+    }
+    let (a, b, c, d) = (
+        t[p * n + p],
+        t[p * n + p + 1],
+        t[(p + 1) * n + p],
+        t[(p + 1) * n + p + 1],
+    );
+    let half = 0.5 * (a - d);
+    let disc = half * half + b * c;
+    if disc >= 0f32 {
+        // real pair left in a 2x2 block, report the larger
+        return Complex::new(0.5 * (a + d) + disc.sqrt(), 0f32);
+    }
+    Complex::new(0.5 * (a + d), (-disc).sqrt())
+}
+
+// solves the dense system kx = b with partial pivoting, m <= 4
+pub(crate) fn small_solve(k: &mut [f64], b: &mut [f64], m: usize) {
+    let small = f64::EPSILON
+        * k.iter()
+            .map(|x| x.abs())
+            .fold(0f64, f64::max)
+            .max(f64::MIN_POSITIVE);
+    for c in 0..m {
+        let pivot = (c..m)
+            .max_by(|&i, &j| k[i * m + c].abs().total_cmp(&k[j * m + c].abs()))
+            .unwrap_or(c);
+        if pivot != c {
+            for j in 0..m {
+                k.swap(c * m + j, pivot * m + j);
+            }
+            b.swap(c, pivot);
+        }
+        if k[c * m + c].abs() < small {
+            k[c * m + c] = small;
+        }
+        for i in c + 1..m {
+            let f = k[i * m + c] / k[c * m + c];
+            for j in c..m {
+                k[i * m + j] -= f * k[c * m + j];
+            }
+            b[i] -= f * b[c];
+        }
+    }
+    for c in (0..m).rev() {
+        let s: f64 = (c + 1..m).map(|j| k[c * m + j] * b[j]).sum();
+        b[c] = (b[c] - s) / k[c * m + c];
+    }
+}
 
-    lq.mat_ql_apply(&mut nkernel, workspace);
-    lq.mat_left_apply_q(rotation, workspace);
-    // println!("kernel {nkernel:?}");
-    (nkernel, lq.h)
+/// swap_blocks
+/// orthogonal Q with Q'[A11 A12; 0 A22]Q = [A22' *; 0 A11'] for adjacent diagonal blocks
+///
+/// * t: upper quasi triangular matrix data
+/// * n: number of rows
+/// * j: first row of A11
+/// * p: size of A11
+/// * q: size of A22
+///
+/// returns Q ~ (p + q) x (p + q)
+fn swap_blocks(t: &[f32], n: usize, j: usize, p: usize, q: usize) -> Vec<f64> {
+    let m = p + q;
+    let at = |r: usize, c: usize| t[(j + r) * n + j + c] as f64;
+    // A11X - XA22 = A12 as a kronecker system in the row major X ~ p x q
+    let mut k = vec![0f64; p * q * p * q];
+    let mut x = vec![0f64; p * q];
+    for a in 0..p {
+        for b in 0..q {
+            let row = a * q + b;
+            x[row] = at(a, p + b);
+            for c in 0..p {
+                k[row * p * q + c * q + b] += at(a, c);
+            }
+            for d in 0..q {
+                k[row * p * q + a * q + d] -= at(p + d, p + b);
+            }
+        }
+    }
+    small_solve(&mut k, &mut x, p * q);
+    // the columns of W = [-X; I] span the invariant subspace of A22
+    let mut w = vec![0f64; m * q];
+    for a in 0..p {
+        for b in 0..q {
+            w[a * q + b] = -x[a * q + b];
+        }
+    }
+    for b in 0..q {
+        w[(p + b) * q + b] = 1f64;
+    }
+    // householder QR of W with Q accumulated explicitly
+    let mut qm = vec![0f64; m * m];
+    for i in 0..m {
+        qm[i * m + i] = 1f64;
+    }
+    let mut x = vec![0f64; m];
+    let mut v = vec![0f64; m];
+    for c in 0..q {
+        for i in c..m {
+            x[i] = w[i * q + c];
+        }
+        let tau = householder(&x[c..], &mut v);
+        if tau == 0f64 {
+            continue;
+        }
+        reflect_rows(&mut w, q, &v[..m - c], tau, c, 0..q);
+        // Q <- QH
+        reflect_cols(&mut qm, m, &v[..m - c], tau, c, 0..m);
+    }
+    qm
 }
-fn real_schur_threshold(kernel: &NdArray) -> f32 {
-    let rows = kernel.dims[0];
-    let cols = kernel.dims[1];
-    let mut off_diagonal = 0f32;
 
-    for i in 0..rows {
-        for j in i + 1..cols {
-            off_diagonal += kernel.data[i * rows + j].abs();
+impl SchurDecomp {
+    /// reorder
+    /// moves the selected eigenvalues to the leading diagonal blocks by swapping adjacent blocks,
+    /// conjugate pairs are selected by the eigenvalue with positive imaginary part
+    ///
+    /// * select: predicate on the eigenvalues of the diagonal blocks
+    ///
+    /// returns an orthonormal basis of the selected invariant subspace ~ n x k
+    pub fn reorder(&mut self, select: impl Fn(Complex) -> bool) -> NdArray {
+        let n = self.kernel.dims[0];
+        let mut blocks: Vec<usize> = schur_blocks(&mut self.kernel.data, n)
+            .into_iter()
+            .map(|(_, size)| size)
+            .collect();
+        let (mut head, mut k) = (0, 0);
+        let mut start = 0;
+        for b in 0..blocks.len() {
+            let size = blocks[b];
+            let lambda = block_eigenvalue(&self.kernel.data, n, start, size);
+            start += size;
+            if !select(lambda) {
+                continue;
+            }
+            // bubble block b up to position head
+            let mut pos = start - size;
+            for c in (head..b).rev() {
+                let prev = blocks[c];
+                let j = pos - prev;
+                let qm = swap_blocks(&self.kernel.data, n, j, prev, size);
+                self.apply_swap(&qm, j, prev, size);
+                blocks.swap(c, c + 1);
+                pos = j;
+            }
+            head += 1;
+            k += size;
+        }
+        schur_blocks(&mut self.kernel.data, n);
+        // A = R'TR so the leading k rows of R span the subspace
+        let mut basis = vec![0f32; n * k];
+        for i in 0..n {
+            for j in 0..k {
+                basis[i * k + j] = self.rotation.data[j * n + i];
+            }
+        }
+        NdArray::new(vec![n, k], basis)
+    }
+    fn apply_swap(&mut self, qm: &[f64], j: usize, p: usize, q: usize) {
+        // T <- Q'TQ and R <- Q'R on rows and cols j..j + m
+        let (n, m) = (self.kernel.dims[0], p + q);
+        let mut buffer = vec![0f64; m];
+        for target in [&mut self.kernel.data, &mut self.rotation.data] {
+            for c in 0..n {
+                for (r, value) in buffer.iter_mut().enumerate() {
+                    *value = (0..m)
+                        .map(|i| qm[i * m + r] * target[(j + i) * n + c] as f64)
+                        .sum();
+                }
+                for (r, value) in buffer.iter().enumerate() {
+                    target[(j + r) * n + c] = *value as f32;
+                }
+            }
+        }
+        let t = &mut self.kernel.data;
+        for row in 0..n {
+            for (c, value) in buffer.iter_mut().enumerate() {
+                *value = (0..m)
+                    .map(|i| t[row * n + j + i] as f64 * qm[i * m + c])
+                    .sum();
+            }
+            for (c, value) in buffer.iter().enumerate() {
+                t[row * n + j + c] = *value as f32;
+            }
+        }
+        // the swapped blocks are q x q over p x p
+        for row in j + q..n {
+            let end = if row < j + m { j + q } else { j + m };
+            for c in j..end {
+                t[row * n + c] = 0f32;
+            }
         }
     }
-    off_diagonal
 }
-pub fn real_schur(mut kernel: NdArray, mut nkernel: NdArray, workspace: &mut [f32]) -> SchurDecomp {
-    let rows = kernel.dims[0];
-    let mut rotation = create_identity_matrix(rows);
-    workspace.fill(0f32);
-    for _ in 0..LIMIT_ITERATION {
-        let threshold = real_schur_threshold(&kernel);
-        if CONVERGENCE_CONDITION > threshold {
-            break;
+
+#[cfg(test)]
+mod test_schur_reorder {
+    use super::*;
+    use crate::algebra::ndmethods::{create_identity_matrix, matrix_mult};
+    use crate::decomposition::eigen::general::Eigen;
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_matrix;
+
+    #[test]
+    fn test_reorder_subspace() {
+        for n in [2, 5, 9, 16] {
+            let a = generate_random_matrix(n, n);
+            let mut schur = Eigen::compute_with(&a, false).schur;
+            let basis = schur.reorder(|lambda| lambda.real() < 0f32);
+            let k = basis.dims[1];
+            // RAR' = T still holds and R stays orthogonal
+            let r = &schur.rotation;
+            let t = matrix_mult(&matrix_mult(r, &a), &r.transpose());
+            assert!(approx_vector_eq(&t.data, &schur.kernel.data));
+            let rrt = matrix_mult(r, &r.transpose());
+            assert!(approx_vector_eq(&rrt.data, &create_identity_matrix(n).data));
+            // leading blocks hold exactly the selected eigenvalues
+            let blocks = schur_blocks(&mut schur.kernel.data, n);
+            let mut rows = 0;
+            for &(p, size) in &blocks {
+                let lambda = block_eigenvalue(&schur.kernel.data, n, p, size);
+                assert_eq!(lambda.real() < 0f32, rows < k);
+                rows += size;
+            }
+            if k == 0 {
+                continue;
+            }
+            // AV = V(V'AV)
+            let av = matrix_mult(&a, &basis);
+            let projected = matrix_mult(&basis, &matrix_mult(&basis.transpose(), &av));
+            assert!(approx_vector_eq(&av.data, &projected.data));
         }
-        (kernel, nkernel) = real_schur_iteration(kernel, nkernel, &mut rotation, workspace);
     }
-    SchurDecomp { kernel, rotation }
 }
 
-// #[cfg(test)]
-// mod test_lq {
-//     const TOLERANCE: f32 = 1e-3;
-//     use super::*;
-//     use crate::algebra::ndmethods::basic_mult;
-//     use crate::algebra::ndmethods::create_identity_matrix;
-//     use crate::equality::approximate::approx_vector_tol_eq;
-//     use crate::random::generation::generate_random_matrix;
-//     #[test]
-//     fn test_reconstruction() {
-//         for n in 1..12 {
-//             check_reconstruct(n);
-//         }
-//     }
-//     fn check_reconstruct(n: usize) {
-//         let x = generate_random_matrix(n, n);
-//         let kernel = x.clone();
-//         let nkernel = create_identity_matrix(n);
-//         let mut workspace = vec![0f32; n];
-//         let schur = real_schur(kernel, nkernel, &mut workspace);
-//         let q = schur.rotation;
-//         let q_star = q.transpose();
-//         let expect = basic_mult(&q, &x);
-//         let expect = basic_mult(&expect, &q_star);
-//         let result = schur.kernel;
-//         println!("expect {expect:?}");
-//         println!("result {result:?}");
-//         assert!(approx_vector_tol_eq(&result.data, &expect.data, TOLERANCE));
-//     }
-//     #[test]
-//     fn test_orthogonalality() {
-//         for n in 1..12 {
-//             check_orthogonal(n);
-//         }
-//     }
-//     fn check_orthogonal(n: usize) {
-//         let x = generate_random_matrix(n, n);
-//         let kernel = x.clone();
-//         let nkernel = create_identity_matrix(n);
-//         let mut workspace = vec![0f32; n];
-//         let schur = real_schur(kernel, nkernel, &mut workspace);
-//         let q = schur.rotation;
-//         let q_star = q.transpose();
-//         let expect = create_identity_matrix(n);
-//         let result = basic_mult(&q, &q_star);
-//         println!("expect {expect:?}");
-//         println!("result {result:?}");
-//         assert!(approx_vector_tol_eq(&result.data, &expect.data, TOLERANCE));
-//     }
-// }
+#[cfg(test)]
+mod test_real_schur {
+    use super::*;
+    use crate::algebra::ndmethods::{create_identity_matrix, matrix_mult};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_matrix;
+
+    #[test]
+    fn test_real_schur_reconstruct() {
+        for n in [1, 2, 3, 8, 17, 40] {
+            let a = generate_random_matrix(n, n);
+            let (mut schur, _, converged) = real_schur(&a);
+            assert!(converged);
+            // RAR' = T with R orthogonal
+            let r = &schur.rotation;
+            let t = matrix_mult(&matrix_mult(r, &a), &r.transpose());
+            assert!(approx_vector_eq(&t.data, &schur.kernel.data));
+            let rrt = matrix_mult(r, &r.transpose());
+            assert!(approx_vector_eq(&rrt.data, &create_identity_matrix(n).data));
+            // quasi triangular and every 2x2 block holds a conjugate pair
+            let t = &mut schur.kernel.data;
+            for i in 0..n {
+                for j in 0..i.saturating_sub(1) {
+                    assert_eq!(t[i * n + j], 0f32);
+                }
+            }
+            for (p, size) in schur_blocks(t, n) {
+                if size == 2 {
+                    assert!(block_eigenvalue(t, n, p, size).imag() > 0f32);
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_real_schur_triangular() {
+        // already upper triangular input deflates without a francis step
+        let a = NdArray::new(
+            vec![3, 3],
+            vec![1f32, 2f32, 3f32, 0f32, 4f32, 5f32, 0f32, 0f32, 6f32],
+        );
+        let (schur, iterations, converged) = real_schur(&a);
+        assert!(converged);
+        assert_eq!(iterations, 0);
+        let diagonal: Vec<f32> = (0..3).map(|i| schur.kernel.data[i * 3 + i]).collect();
+        assert!(approx_vector_eq(&diagonal, &[1f32, 4f32, 6f32]));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::algebra::ndmethods::create_identity_vector;
+use crate::decomposition::svd::constants::MAX_ITERS;
+use crate::decomposition::svd::interface::{full_svd_decomposition, svd_decomposition};
+use crate::structure::ndarray::NdArray;
+
+const TOLERANCE: f32 = f32::EPSILON;
+const ABSOLUTE: f32 = 1e-4;
+
+/// SvdMode
+/// which singular vectors are kept
+///
+/// * Full: U ~ rows x rows, V ~ cols x cols
+/// * Thin: U ~ rows x card, V ~ cols x card
+/// * ValuesOnly: singular values without U and V
+/// * Truncated: the k largest singular triplets
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SvdMode {
+    Full,
+    Thin,
+    ValuesOnly,
+    Truncated(usize),
+}
+
+/// Svd
+/// singular value decomposition A = USV'
+///
+/// * rows: rows in A
+/// * cols: cols in A
+/// * s: non-negative singular values in descending order
+/// * u: left singular vectors stored as columns, None for ValuesOnly
+/// * v: right singular vectors stored as columns, None for ValuesOnly
+pub struct Svd {
+    pub rows: usize,
+    pub cols: usize,
+    pub s: Vec<f32>,
+    pub u: Option<NdArray>,
+    pub v: Option<NdArray>,
+}
+
+// reorders the leading card columns by order and truncates to width
+fn select_cols(x: &[f32], n: usize, order: &[usize], width: usize) -> NdArray {
+    let mut data = vec![0f32; n * width];
+    for j in 0..width {
+        let src = if j < order.len() { order[j] } else { j };
+        for i in 0..n {
+            data[i * width + j] = x[i * n + src];
+        }
+    }
+    NdArray::new(vec![n, width], data)
+}
+
+impl Svd {
+    pub fn compute(a: &NdArray, mode: SvdMode) -> Self {
+        let (rows, cols) = (a.dims[0], a.dims[1]);
+        let card = rows.min(cols);
+        let max_iters = card.max(1) * MAX_ITERS;
+        let mut b = a.data.clone();
+        let mut p = vec![0f32; rows.max(cols)];
+        let mut w = vec![0f32; rows.max(cols)];
+        if mode == SvdMode::ValuesOnly {
+            svd_decomposition(
+                &mut b, &mut p, &mut w, rows, cols, card, cols, max_iters, TOLERANCE, ABSOLUTE,
+            );
+            let mut s: Vec<f32> = (0..card).map(|i| b[i * cols + i].abs()).collect();
+            s.sort_by(|x, y| y.total_cmp(x));
+            return Self {
+                rows,
+                cols,
+                s,
+                u: None,
+                v: None,
+            };
+        }
+        let mut u = create_identity_vector(rows, rows);
+        let mut v = create_identity_vector(cols, cols);
+        #[rustfmt::skip]
+        full_svd_decomposition(
+            &mut b, &mut u, &mut v, &mut p, &mut w, rows, cols, card, cols, max_iters, TOLERANCE, ABSOLUTE,
+        );
+        // sign flip u[k] where s[k] < 0
+        let mut s: Vec<f32> = (0..card).map(|i| b[i * cols + i]).collect();
+        for (k, sk) in s.iter_mut().enumerate() {
+            if *sk < 0f32 {
+                *sk = -*sk;
+                for i in 0..rows {
+                    u[i * rows + k] = -u[i * rows + k];
+                }
+            }
+        }
+        let mut order: Vec<usize> = (0..card).collect();
+        order.sort_by(|&i, &j| s[j].total_cmp(&s[i]));
+        let (ucols, vcols, k) = match mode {
+            SvdMode::Full => (rows, cols, card),
+            SvdMode::Truncated(k) => (k.min(card), k.min(card), k.min(card)),
+            _ => (card, card, card),
+        };
+        let s = order[..k].iter().map(|&i| s[i]).collect();
+        Self {
+            rows,
+            cols,
+            s,
+            u: Some(select_cols(&u, rows, &order, ucols)),
+            v: Some(select_cols(&v, cols, &order, vcols)),
+        }
+    }
+}
+
+impl Svd {
+    pub fn rank(&self, tol: f32) -> usize {
+        // number of singular values above tol
+        self.s.iter().filter(|&&s| s > tol).count()
+    }
+    pub fn condition_number(&self) -> f32 {
+        // s[0] / s[k]
+        match (self.s.first(), self.s.last()) {
+            (Some(&max), Some(&min)) if min > 0f32 => max / min,
+            (Some(_), Some(_)) => f32::INFINITY,
+            _ => 0f32,
+        }
+    }
+    pub fn nuclear_norm(&self) -> f32 {
+        self.s.iter().sum()
+    }
+    fn cutoff(&self) -> f32 {
+        // singular values below this are treated as zero
+        let max = self.s.first().copied().unwrap_or(0f32);
+        max * self.rows.max(self.cols) as f32 * f32::EPSILON
+    }
+    pub fn pinv(&self) -> NdArray {
+        // A+ = V S+ U' ~ cols x rows
+        let u = self.u.as_ref().expect("pinv requires singular vectors");
+        let v = self.v.as_ref().expect("pinv requires singular vectors");
+        let (uw, vw) = (u.dims[1], v.dims[1]);
+        let cutoff = self.cutoff();
+        let mut pinv = NdArray::new(
+            vec![self.cols, self.rows],
+            vec![0f32; self.cols * self.rows],
+        );
+        for (k, &s) in self.s.iter().enumerate() {
+            if s <= cutoff {
+                continue;
+            }
+            let inv = 1f32 / s;
+            for i in 0..self.cols {
+                let scalar = v.data[i * vw + k] * inv;
+                let row = &mut pinv.data[i * self.rows..(i + 1) * self.rows];
+                for j in 0..self.rows {
+                    row[j] += scalar * u.data[j * uw + k];
+                }
+            }
+        }
+        pinv
+    }
+    pub fn solve(&self, b: &[f32]) -> Vec<f32> {
+        // least norm least squares x = V S+ U'b
+        debug_assert_eq!(b.len(), self.rows);
+        let u = self.u.as_ref().expect("solve requires singular vectors");
+        let v = self.v.as_ref().expect("solve requires singular vectors");
+        let (uw, vw) = (u.dims[1], v.dims[1]);
+        let cutoff = self.cutoff();
+        let mut x = vec![0f32; self.cols];
+        for (k, &s) in self.s.iter().enumerate() {
+            if s <= cutoff {
+                continue;
+            }
+            let mut dot = 0f32;
+            for i in 0..self.rows {
+                dot += u.data[i * uw + k] * b[i];
+            }
+            dot /= s;
+            for i in 0..self.cols {
+                x[i] += v.data[i * vw + k] * dot;
+            }
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod test_svd_decomp {
+    use super::*;
+    use crate::algebra::ndmethods::matrix_mult;
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::{
+        generate_random_matrix, generate_random_vector, generate_seeded_matrix,
+    };
+
+    fn reconstruct(svd: &Svd) -> NdArray {
+        let (u, v) = (svd.u.as_ref().unwrap(), svd.v.as_ref().unwrap());
+        let mut us = u.clone();
+        us.resize_cols(svd.s.len());
+        let k = svd.s.len();
+        for i in 0..svd.rows {
+            for j in 0..k {
+                us.data[i * k + j] *= svd.s[j];
+            }
+        }
+        let mut v = v.clone();
+        v.resize_cols(k);
+        matrix_mult(&us, &v.transpose())
+    }
+    // fixed draw with a dominant diagonal so the smallest singular value stays away from zero
+    fn well_conditioned(rows: usize, cols: usize, seed: u64) -> NdArray {
+        let mut a = generate_seeded_matrix(rows, cols, seed);
+        for i in 0..rows.min(cols) {
+            a.data[i * cols + i] += 4f32;
+        }
+        a
+    }
+    #[test]
+    fn test_svd_modes() {
+        for (seed, (rows, cols)) in [(6, 6), (8, 4), (4, 7), (1, 3)].into_iter().enumerate() {
+            let a = well_conditioned(rows, cols, seed as u64);
+            let card = rows.min(cols);
+            for mode in [SvdMode::Full, SvdMode::Thin] {
+                let svd = Svd::compute(&a, mode);
+                assert!(svd.s.windows(2).all(|w| w[0] >= w[1]));
+                assert!(svd.s.iter().all(|&s| s >= 0f32));
+                assert!(approx_vector_eq(&reconstruct(&svd).data, &a.data));
+            }
+            let full = Svd::compute(&a, SvdMode::Full);
+            assert_eq!(full.u.as_ref().unwrap().dims, vec![rows, rows]);
+            let values = Svd::compute(&a, SvdMode::ValuesOnly);
+            assert!(approx_vector_eq(&values.s, &full.s));
+            let truncated = Svd::compute(&a, SvdMode::Truncated(1));
+            assert_eq!(truncated.v.as_ref().unwrap().dims, vec![cols, 1]);
+            assert!(approx_vector_eq(&truncated.s, &full.s[..1]));
+            assert!((full.nuclear_norm() - full.s.iter().sum::<f32>()).abs() < 1e-4);
+            assert!(full.s[card - 1] > 1e-1 * full.s[0]);
+            assert_eq!(full.rank(1e-3), card);
+        }
+    }
+    #[test]
+    fn test_svd_pinv_solve() {
+        let (rows, cols) = (9, 4);
+        let a = generate_random_matrix(rows, cols);
+        let svd = Svd::compute(&a, SvdMode::Thin);
+        let pinv = svd.pinv();
+        // A A+ A = A
+        let result = matrix_mult(&matrix_mult(&a, &pinv), &a);
+        assert!(approx_vector_eq(&result.data, &a.data));
+        // x = A+ b solves the normal equations A'Ax = A'b
+        let b = generate_random_vector(rows);
+        let x = svd.solve(&b);
+        let bm = NdArray::new(vec![rows, 1], b.clone());
+        let expected = matrix_mult(&pinv, &bm);
+        assert!(approx_vector_eq(&x, &expected.data));
+        let xm = NdArray::new(vec![cols, 1], x);
+        let lhs = matrix_mult(&a.transpose(), &matrix_mult(&a, &xm));
+        let rhs = matrix_mult(&a.transpose(), &bm);
+        assert!(approx_vector_eq(&lhs.data, &rhs.data));
+    }
+}
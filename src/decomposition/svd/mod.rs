@@ -1,6 +1,7 @@
 pub mod bidiagonalization;
 pub mod bulge_chasing;
 pub mod constants;
+pub mod decomp;
 pub mod interface;
 pub mod primitives;
 pub mod verify;
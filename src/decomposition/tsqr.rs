@@ -0,0 +1,327 @@
+use crate::decomposition::francis::primitives::{lapply_householder, params};
+use crate::structure::ndarray::NdArray;
+use rayon::prelude::*;
+
+/// HouseholderBlock
+/// compact householder QR of a single row block
+///
+/// * rows: number of rows in the block
+/// * cols: number of cols in the block
+/// * h: reflector tails stored below the diagonal, implicit 1 on the diagonal
+/// * tau: scalar of each reflection (I - tau * uu')
+struct HouseholderBlock {
+    rows: usize,
+    cols: usize,
+    h: Vec<f32>,
+    tau: Vec<f32>,
+}
+
+/// TsqrDecomp
+/// tall skinny QR, rows are split into blocks which are factored in parallel
+/// and the resulting R factors are reduced pairwise in a binary tree
+///
+/// * rows: rows in the input matrix A
+/// * cols: cols in the input matrix A
+/// * bounds: starting row of every leaf block, terminated by rows
+/// * leaves: factorizations of the row blocks of A
+/// * tree: level l holds the factorizations of the stacked pairs of level l - 1
+/// * r: upper triangular factor ~ cols x cols
+pub struct TsqrDecomp {
+    pub rows: usize,
+    pub cols: usize,
+    bounds: Vec<usize>,
+    leaves: Vec<HouseholderBlock>,
+    tree: Vec<Vec<Option<HouseholderBlock>>>,
+    pub r: NdArray,
+}
+
+impl HouseholderBlock {
+    /// factor
+    /// factors the block in place and returns the block with its R
+    ///
+    /// * b: row major block data, overwritten by the reflectors
+    /// * rows: number of rows in the block
+    /// * cols: number of cols in the block
+    fn factor(b: &mut [f32], rows: usize, cols: usize) -> (Self, Vec<f32>) {
+        debug_assert!(rows >= cols, "tsqr blocks should be tall");
+        let card = rows.min(cols);
+        let mut tau = vec![0f32; card];
+        let mut u = vec![0f32; rows];
+        let mut w = vec![0f32; rows.max(cols)];
+        for k in 0..card {
+            let ract = rows - k;
+            let offset = k * cols + k;
+            for i in 0..ract {
+                w[i] = b[offset + i * cols];
+            }
+            let proj = &mut u[..ract];
+            tau[k] = params(&mut w[..ract], proj);
+            b[offset] = w[0];
+            if tau[k] != 0f32 && k + 1 < cols {
+                lapply_householder(
+                    &mut b[offset + 1..],
+                    proj,
+                    &mut w,
+                    tau[k],
+                    ract,
+                    cols - k - 1,
+                    cols,
+                );
+            }
+            for i in 1..ract {
+                b[offset + i * cols] = if tau[k] != 0f32 { proj[i] } else { 0f32 };
+            }
+        }
+        let mut r = vec![0f32; cols * cols];
+        for i in 0..card {
+            r[i * cols + i..(i + 1) * cols].copy_from_slice(&b[i * cols + i..(i + 1) * cols]);
+        }
+        let block = Self {
+            rows,
+            cols,
+            h: b.to_vec(),
+            tau,
+        };
+        (block, r)
+    }
+    fn reflector(&self, k: usize, u: &mut [f32]) {
+        u[0] = 1f32;
+        for i in k + 1..self.rows {
+            u[i - k] = self.h[i * self.cols + k];
+        }
+    }
+    fn left_apply_qt(&self, t: &mut [f32], tcols: usize) {
+        // Q'X = H[k]..H[0]X
+        let mut u = vec![0f32; self.rows];
+        let mut w = vec![0f32; tcols];
+        for k in 0..self.tau.len() {
+            if self.tau[k] == 0f32 {
+                continue;
+            }
+            let ract = self.rows - k;
+            self.reflector(k, &mut u);
+            lapply_householder(
+                &mut t[k * tcols..],
+                &mut u[..ract],
+                &mut w,
+                self.tau[k],
+                ract,
+                tcols,
+                tcols,
+            );
+        }
+    }
+    fn left_apply_q(&self, t: &mut [f32], tcols: usize) {
+        // QX = H[0]..H[k]X
+        let mut u = vec![0f32; self.rows];
+        let mut w = vec![0f32; tcols];
+        for k in (0..self.tau.len()).rev() {
+            if self.tau[k] == 0f32 {
+                continue;
+            }
+            let ract = self.rows - k;
+            self.reflector(k, &mut u);
+            lapply_householder(
+                &mut t[k * tcols..],
+                &mut u[..ract],
+                &mut w,
+                self.tau[k],
+                ract,
+                tcols,
+                tcols,
+            );
+        }
+    }
+}
+
+fn split_rows<'a>(mut data: &'a mut [f32], bounds: &[usize], stride: usize) -> Vec<&'a mut [f32]> {
+    let mut blocks = Vec::with_capacity(bounds.len());
+    for window in bounds.windows(2) {
+        let (head, tail) = data.split_at_mut((window[1] - window[0]) * stride);
+        blocks.push(head);
+        data = tail;
+    }
+    blocks
+}
+
+fn stack(top: &[f32], bottom: &[f32]) -> Vec<f32> {
+    let mut data = Vec::with_capacity(top.len() + bottom.len());
+    data.extend_from_slice(top);
+    data.extend_from_slice(bottom);
+    data
+}
+
+impl TsqrDecomp {
+    pub fn new(x: NdArray) -> Self {
+        let (rows, cols) = (x.dims[0], x.dims[1]);
+        let block = (rows / rayon::current_num_threads()).max(cols << 2);
+        Self::new_block(x, block)
+    }
+    /// new_block
+    ///
+    /// * x: tall matrix rows >= cols
+    /// * block: target number of rows per leaf, raised to at least cols
+    pub fn new_block(mut x: NdArray, block: usize) -> Self {
+        let (rows, cols) = (x.dims[0], x.dims[1]);
+        debug_assert!(rows >= cols, "tsqr requires rows >= cols");
+        let block = block.max(cols).max(1);
+        let count = (rows / block).max(1);
+        let size = rows / count;
+        let mut bounds: Vec<usize> = (0..count).map(|i| i * size).collect();
+        bounds.push(rows);
+        let (leaves, mut level): (Vec<HouseholderBlock>, Vec<Vec<f32>>) =
+            split_rows(&mut x.data, &bounds, cols)
+                .into_par_iter()
+                .map(|b| {
+                    let brows = b.len() / cols.max(1);
+                    HouseholderBlock::factor(b, brows, cols)
+                })
+                .unzip();
+        let mut tree = Vec::new();
+        while level.len() > 1 {
+            let (nodes, next): (Vec<Option<HouseholderBlock>>, Vec<Vec<f32>>) = level
+                .par_chunks(2)
+                .map(|pair| {
+                    if pair.len() == 1 {
+                        return (None, pair[0].clone());
+                    }
+                    let mut data = stack(&pair[0], &pair[1]);
+                    let (node, r) = HouseholderBlock::factor(&mut data, cols << 1, cols);
+                    (Some(node), r)
+                })
+                .unzip();
+            tree.push(nodes);
+            level = next;
+        }
+        let r = NdArray::new(vec![cols, cols], level.pop().unwrap_or_default());
+        Self {
+            rows,
+            cols,
+            bounds,
+            leaves,
+            tree,
+            r,
+        }
+    }
+}
+
+impl TsqrDecomp {
+    pub fn left_apply_qt(&self, target: &mut NdArray) {
+        // f(X) :: Q'X truncated to the thin cols x tcols block
+        debug_assert_eq!(target.dims[0], self.rows);
+        let (n, tcols) = (self.cols, target.dims[1]);
+        let card = n * tcols;
+        let mut level: Vec<Vec<f32>> = split_rows(&mut target.data, &self.bounds, tcols)
+            .into_par_iter()
+            .zip(self.leaves.par_iter())
+            .map(|(t, leaf)| {
+                leaf.left_apply_qt(t, tcols);
+                t[..card].to_vec()
+            })
+            .collect();
+        for nodes in &self.tree {
+            level = level
+                .par_chunks(2)
+                .zip(nodes.par_iter())
+                .map(|(pair, node)| match node {
+                    None => pair[0].clone(),
+                    Some(node) => {
+                        let mut data = stack(&pair[0], &pair[1]);
+                        node.left_apply_qt(&mut data, tcols);
+                        data.truncate(card);
+                        data
+                    }
+                })
+                .collect();
+        }
+        target.data = level.pop().unwrap_or_default();
+        target.dims[0] = n;
+    }
+    pub fn left_apply_q(&self, target: &mut NdArray) {
+        // f(X) :: QX with X ~ cols x tcols and Q the thin rows x cols factor
+        debug_assert_eq!(target.dims[0], self.cols);
+        let (n, tcols) = (self.cols, target.dims[1]);
+        let card = n * tcols;
+        let mut level = vec![target.data.clone()];
+        for nodes in self.tree.iter().rev() {
+            level = level
+                .par_iter()
+                .zip(nodes.par_iter())
+                .flat_map_iter(|(z, node)| match node {
+                    None => vec![z.clone()],
+                    Some(node) => {
+                        let mut data = z.clone();
+                        data.resize(card << 1, 0f32);
+                        node.left_apply_q(&mut data, tcols);
+                        let bottom = data.split_off(card);
+                        vec![data, bottom]
+                    }
+                })
+                .collect();
+        }
+        let mut output = vec![0f32; self.rows * tcols];
+        split_rows(&mut output, &self.bounds, tcols)
+            .into_par_iter()
+            .zip(self.leaves.par_iter())
+            .zip(level.par_iter())
+            .for_each(|((t, leaf), z)| {
+                t[..card].copy_from_slice(z);
+                leaf.left_apply_q(t, tcols);
+            });
+        target.data = output;
+        target.dims[0] = self.rows;
+    }
+    pub fn projection_matrix(&self) -> NdArray {
+        // thin Q ~ rows x cols
+        let n = self.cols;
+        let mut q = NdArray::new(vec![n, n], vec![0f32; n * n]);
+        for i in 0..n {
+            q.data[i * n + i] = 1f32;
+        }
+        self.left_apply_q(&mut q);
+        q
+    }
+}
+
+#[cfg(test)]
+mod test_tsqr {
+    use super::*;
+    use crate::algebra::ndmethods::{basic_mult, create_identity_matrix};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_matrix;
+
+    fn check_tsqr(rows: usize, cols: usize, block: usize) {
+        let a = generate_random_matrix(rows, cols);
+        let tsqr = TsqrDecomp::new_block(a.clone(), block);
+        // A'A = R'R
+        let expected = basic_mult(&a.transpose(), &a);
+        let result = basic_mult(&tsqr.r.transpose(), &tsqr.r);
+        assert!(approx_vector_eq(&result.data, &expected.data));
+        // QR = A
+        let mut result = tsqr.r.clone();
+        tsqr.left_apply_q(&mut result);
+        assert!(approx_vector_eq(&result.data, &a.data));
+        // Q'A = R
+        let mut result = a.clone();
+        tsqr.left_apply_qt(&mut result);
+        assert!(approx_vector_eq(&result.data, &tsqr.r.data));
+        // Q'Q = I
+        let q = tsqr.projection_matrix();
+        let result = basic_mult(&q.transpose(), &q);
+        assert!(approx_vector_eq(
+            &result.data,
+            &create_identity_matrix(cols).data
+        ));
+    }
+    #[test]
+    fn test_tsqr_single_block() {
+        check_tsqr(16, 4, 64);
+        check_tsqr(5, 5, 5);
+    }
+    #[test]
+    fn test_tsqr_tree() {
+        for (rows, cols, block) in [(64, 4, 8), (100, 3, 7), (257, 8, 16), (40, 5, 1)] {
+            check_tsqr(rows, cols, block);
+        }
+    }
+}
@@ -1,5 +1,10 @@
+use crate::algebra::bmethods::interface::{stride_kernel, stride_tkernel};
+use crate::algebra::bmethods::interface::{tensor_lt_kernel, tensor_rlt_kernel};
+use crate::algebra::bmethods::interface::{tensor_rut_kernel, tensor_tkernel};
 use crate::structure::ndarray::NdArray;
 
+const BLOCK_SIZE: usize = 32;
+
 /// LqBlockDecomp
 ///
 /// takes in a basis and mutates the original matrix
@@ -40,8 +45,8 @@ fn params(v: &mut [f32]) -> f32 {
         *val *= inv_max_element;
         magnitude_squared += *val * *val;
     }
-    // let g = v[0].signum() * magnitude_squared.sqrt();
-    let g = -v[0].signum() * magnitude_squared.sqrt();
+    // same sign as v[0] so v[0] + g never cancels
+    let g = v[0].signum() * magnitude_squared.sqrt();
     let scale = v[0] + g;
     let inv_scale = 1f32 / scale;
     for val in v[1..].iter_mut() {
@@ -78,9 +83,7 @@ fn triangle_iteration(
     // diagonal element stores the L[ii] element not householder
     let mut hoffset = 0;
     // h'Y :: Y
-    let koffset = k * h_dim;
     let h_k_tail = r;
-    // let h_k_tail = &h[koffset + k + 1..koffset + h_dim];
     for l in 0..k {
         // initial element of householder vector is 1
         let mut dot = h[hoffset + k];
@@ -92,7 +95,7 @@ fn triangle_iteration(
         hoffset += h_dim;
     }
     let mut toffset = 0;
-    let (t_upper, t_target) = t.split_at_mut(koffset);
+    let (t_upper, t_target) = t.split_at_mut(k * t_dim);
 
     // h'T :: T ~ bottom-left triangular
     for l in 0..k {
@@ -104,7 +107,7 @@ fn triangle_iteration(
         }
         toffset += t_dim;
     }
-    t[koffset + k] = tau;
+    t[k * t_dim + k] = tau;
 }
 impl LqBlockDecomp {
     pub fn new(mut l_yt: NdArray, mut t_mat: NdArray, workspace: &mut [f32]) -> Self {
@@ -149,3 +152,310 @@ impl LqBlockDecomp {
         Self { h: l_yt, t: t_mat }
     }
 }
+
+/// WyLqDecomp
+/// blocked LQ, panels of rows are factored by LqBlockDecomp and
+/// the trailing rows are updated with matrix multiplications
+///
+/// * h: ~ [ L \ Y' ] same layout as AutumnDecomp
+/// * y: Y' of every panel with an explicit unit diagonal ~ nb x (cols - k)
+/// * t: lower triangular T of every panel ~ nb x nb
+/// * block: number of reflectors per panel
+///
+/// Matrix decomp has form
+/// A = LQ;
+/// Q = Q[p]..Q[0];
+/// Q[b] = I - Y[b]T[b]Y[b]';
+pub struct WyLqDecomp {
+    pub h: NdArray,
+    pub y: Vec<NdArray>,
+    pub t: Vec<NdArray>,
+    pub block: usize,
+}
+
+/// WyQrDecomp
+/// blocked QR through the LQ of the transpose
+///
+/// A' = LQ => A = Q'L' = QR
+pub struct WyQrDecomp {
+    pub lq: WyLqDecomp,
+}
+
+// (I - YTY')X or (I - YT'Y')X
+//
+// * x: contiguous rows k.. of the target ~ (cols - k) x tcols
+fn panel_left_apply(y: &NdArray, t: &NdArray, x: &mut [f32], tcols: usize, transpose: bool) {
+    let (nb, width) = (y.dims[0], y.dims[1]);
+    // Z = Y'X
+    let mut z = NdArray::new(vec![nb, tcols], vec![0f32; nb * tcols]);
+    stride_kernel(
+        &y.data,
+        x,
+        &mut z.data,
+        nb,
+        width,
+        tcols,
+        width,
+        tcols,
+        tcols,
+    );
+    // Z = -TZ
+    let mut tz = vec![0f32; nb * tcols];
+    if transpose {
+        tensor_tkernel(t, &z, &mut tz);
+    } else {
+        tensor_lt_kernel(t, &z, &mut tz);
+    }
+    tz.iter_mut().for_each(|v| *v = -*v);
+    // X += YZ
+    stride_tkernel(&y.data, &tz, x, width, nb, tcols, width, tcols, tcols);
+}
+
+// X(I - YTY') or X(I - YT'Y')
+//
+// * x: contiguous cols k.. of the target ~ trows x (cols - k)
+fn panel_right_apply(y: &NdArray, t: &NdArray, x: &mut [f32], trows: usize, transpose: bool) {
+    let (nb, width) = (y.dims[0], y.dims[1]);
+    // W = XY
+    let yt = y.transpose();
+    let mut w = NdArray::new(vec![trows, nb], vec![0f32; trows * nb]);
+    stride_kernel(x, &yt.data, &mut w.data, trows, width, nb, width, nb, nb);
+    // W = -WT
+    let mut wt = vec![0f32; trows * nb];
+    if transpose {
+        tensor_rut_kernel(&w, &t.transpose(), &mut wt);
+    } else {
+        tensor_rlt_kernel(&w, t, &mut wt);
+    }
+    wt.iter_mut().for_each(|v| *v = -*v);
+    // X += WY'
+    stride_kernel(&wt, &y.data, x, trows, nb, width, nb, width, width);
+}
+
+// copies the trailing cols of a row major matrix into a contiguous block
+fn gather_cols(x: &[f32], rows: usize, stride: usize, k: usize) -> Vec<f32> {
+    let width = stride - k;
+    let mut block = vec![0f32; rows * width];
+    for i in 0..rows {
+        block[i * width..(i + 1) * width].copy_from_slice(&x[i * stride + k..(i + 1) * stride]);
+    }
+    block
+}
+
+fn scatter_cols(block: &[f32], x: &mut [f32], rows: usize, stride: usize, k: usize) {
+    let width = stride - k;
+    for i in 0..rows {
+        x[i * stride + k..(i + 1) * stride].copy_from_slice(&block[i * width..(i + 1) * width]);
+    }
+}
+
+impl WyLqDecomp {
+    pub fn new(x: NdArray) -> Self {
+        Self::new_block(x, BLOCK_SIZE)
+    }
+    /// new_block
+    ///
+    /// * h: matrix with rows <= cols, overwritten by [ L \ Y' ]
+    /// * block: number of reflectors per panel
+    pub fn new_block(mut h: NdArray, block: usize) -> Self {
+        let (rows, cols) = (h.dims[0], h.dims[1]);
+        debug_assert!(rows <= cols);
+        let block = block.max(1);
+        let mut workspace = vec![0f32; block];
+        let (mut y, mut t) = (Vec::new(), Vec::new());
+        for k in (0..rows).step_by(block) {
+            let nb = block.min(rows - k);
+            let width = cols - k;
+            // panel ~ A[k..k + nb, k..cols]
+            let rows_k = &h.data[k * cols..(k + nb) * cols];
+            let panel = NdArray::new(vec![nb, width], gather_cols(rows_k, nb, cols, k));
+            let tri = NdArray::new(vec![nb, nb], vec![0f32; nb * nb]);
+            let wy = LqBlockDecomp::new(panel, tri, &mut workspace);
+            scatter_cols(&wy.h.data, &mut h.data[k * cols..], nb, cols, k);
+            let mut yp = wy.h;
+            for i in 0..nb {
+                let row = &mut yp.data[i * width..(i + 1) * width];
+                row[..i].fill(0f32);
+                row[i] = 1f32;
+            }
+            // A[k + nb.., k..] = A[k + nb.., k..] * Q[b]'
+            let trows = rows - k - nb;
+            if trows > 0 {
+                let trail = &mut h.data[(k + nb) * cols..];
+                let mut x = gather_cols(trail, trows, cols, k);
+                panel_right_apply(&yp, &wy.t, &mut x, trows, true);
+                scatter_cols(&x, trail, trows, cols, k);
+            }
+            y.push(yp);
+            t.push(wy.t);
+        }
+        Self { h, y, t, block }
+    }
+}
+
+impl WyLqDecomp {
+    pub fn left_apply_q(&self, target: &mut NdArray) {
+        // Q * A = Q[p]..Q[0] * A
+        let (cols, tcols) = (self.h.dims[1], target.dims[1]);
+        debug_assert_eq!(target.dims[0], cols);
+        for (b, (y, t)) in self.y.iter().zip(self.t.iter()).enumerate() {
+            let k = b * self.block;
+            panel_left_apply(y, t, &mut target.data[k * tcols..], tcols, false);
+        }
+    }
+    pub fn left_apply_qt(&self, target: &mut NdArray) {
+        // Q' * A = Q[0]'..Q[p]' * A
+        let (cols, tcols) = (self.h.dims[1], target.dims[1]);
+        debug_assert_eq!(target.dims[0], cols);
+        for (b, (y, t)) in self.y.iter().zip(self.t.iter()).enumerate().rev() {
+            let k = b * self.block;
+            panel_left_apply(y, t, &mut target.data[k * tcols..], tcols, true);
+        }
+    }
+    pub fn right_apply_q(&self, target: &mut NdArray) {
+        // A * Q = A * Q[p]..Q[0]
+        for b in (0..self.y.len()).rev() {
+            self.right_apply_panel(target, b, false);
+        }
+    }
+    pub fn right_apply_qt(&self, target: &mut NdArray) {
+        // A * Q' = A * Q[0]'..Q[p]'
+        for b in 0..self.y.len() {
+            self.right_apply_panel(target, b, true);
+        }
+    }
+    fn right_apply_panel(&self, target: &mut NdArray, b: usize, transpose: bool) {
+        let (trows, tcols) = (target.dims[0], target.dims[1]);
+        debug_assert_eq!(tcols, self.h.dims[1]);
+        let k = b * self.block;
+        let mut x = gather_cols(&target.data, trows, tcols, k);
+        panel_right_apply(&self.y[b], &self.t[b], &mut x, trows, transpose);
+        scatter_cols(&x, &mut target.data, trows, tcols, k);
+    }
+    pub fn ql_apply(&self, target: &mut NdArray) {
+        // L * Q with L padded to cols x cols
+        let (rows, cols) = (self.h.dims[0], self.h.dims[1]);
+        debug_assert_eq!(target.dims, vec![cols, cols]);
+        target.data.fill(0f32);
+        for i in 0..rows {
+            let offset = i * cols;
+            target.data[offset..=offset + i].copy_from_slice(&self.h.data[offset..=offset + i]);
+        }
+        self.left_apply_q(target);
+    }
+}
+
+impl WyQrDecomp {
+    pub fn new(x: NdArray) -> Self {
+        Self::new_block(x, BLOCK_SIZE)
+    }
+    /// new_block
+    ///
+    /// * x: matrix with rows >= cols
+    /// * block: number of reflectors per panel
+    pub fn new_block(x: NdArray, block: usize) -> Self {
+        debug_assert!(x.dims[0] >= x.dims[1]);
+        Self {
+            lq: WyLqDecomp::new_block(x.transpose(), block),
+        }
+    }
+    pub fn triangle(&self) -> NdArray {
+        // R = L' ~ cols x cols
+        let (n, m) = (self.lq.h.dims[0], self.lq.h.dims[1]);
+        let mut r = NdArray::new(vec![n, n], vec![0f32; n * n]);
+        for i in 0..n {
+            for j in i..n {
+                r.data[i * n + j] = self.lq.h.data[j * m + i];
+            }
+        }
+        r
+    }
+    pub fn left_apply_q(&self, target: &mut NdArray) {
+        self.lq.left_apply_qt(target);
+    }
+    pub fn left_apply_qt(&self, target: &mut NdArray) {
+        self.lq.left_apply_q(target);
+    }
+    pub fn right_apply_q(&self, target: &mut NdArray) {
+        self.lq.right_apply_qt(target);
+    }
+    pub fn right_apply_qt(&self, target: &mut NdArray) {
+        self.lq.right_apply_q(target);
+    }
+}
+
+#[cfg(test)]
+mod test_wy {
+    use super::*;
+    use crate::algebra::ndmethods::{basic_mult, create_identity_matrix};
+    use crate::equality::approximate::approx_vector_eq;
+    use crate::random::generation::generate_random_matrix;
+
+    fn lower(h: &NdArray) -> NdArray {
+        let (rows, cols) = (h.dims[0], h.dims[1]);
+        let mut l = NdArray::new(vec![rows, cols], vec![0f32; rows * cols]);
+        for i in 0..rows {
+            l.data[i * cols..=i * cols + i].copy_from_slice(&h.data[i * cols..=i * cols + i]);
+        }
+        l
+    }
+    fn check_lq(rows: usize, cols: usize, block: usize) {
+        let a = generate_random_matrix(rows, cols);
+        let lq = WyLqDecomp::new_block(a.clone(), block);
+        let l = lower(&lq.h);
+        // A * Q' = [L 0]
+        let mut result = a.clone();
+        lq.right_apply_qt(&mut result);
+        assert!(approx_vector_eq(&result.data, &l.data));
+        // [L 0] * Q = A
+        lq.right_apply_q(&mut result);
+        assert!(approx_vector_eq(&result.data, &a.data));
+        // Q'Q = I
+        let mut q = create_identity_matrix(cols);
+        lq.left_apply_q(&mut q);
+        let expected = basic_mult(&q, &q.transpose());
+        assert!(approx_vector_eq(
+            &expected.data,
+            &create_identity_matrix(cols).data
+        ));
+        lq.left_apply_qt(&mut q);
+        assert!(approx_vector_eq(
+            &q.data,
+            &create_identity_matrix(cols).data
+        ));
+    }
+    #[test]
+    fn test_wy_lq() {
+        for (rows, cols, block) in [(1, 1, 4), (5, 5, 2), (7, 12, 3), (33, 40, 8), (16, 16, 32)] {
+            check_lq(rows, cols, block);
+        }
+    }
+    #[test]
+    fn test_wy_ql_apply() {
+        let n = 24;
+        let a = generate_random_matrix(n, n);
+        let lq = WyLqDecomp::new_block(a.clone(), 5);
+        let mut result = NdArray::new(vec![n, n], vec![0f32; n * n]);
+        lq.ql_apply(&mut result);
+        let mut expected = lower(&lq.h);
+        lq.left_apply_q(&mut expected);
+        assert!(approx_vector_eq(&result.data, &expected.data));
+    }
+    #[test]
+    fn test_wy_qr() {
+        let (rows, cols) = (30, 12);
+        let a = generate_random_matrix(rows, cols);
+        let qr = WyQrDecomp::new_block(a.clone(), 4);
+        let r = qr.triangle();
+        // Q * [R; 0] = A
+        let mut result = r.clone();
+        result.resize_rows(rows);
+        qr.left_apply_q(&mut result);
+        assert!(approx_vector_eq(&result.data, &a.data));
+        // Q' * A = [R; 0]
+        let mut result = a.clone();
+        qr.left_apply_qt(&mut result);
+        result.resize_rows(cols);
+        assert!(approx_vector_eq(&result.data, &r.data));
+    }
+}
@@ -1,4 +1,5 @@
 // NOTE: could consider switching to a mask look up table
+use std::ops::Range;
 
 /// kernel_mult_scalar
 /// a * b -> c
@@ -47,19 +48,35 @@ pub unsafe fn kernel_mult_simd(
         }
     }
 }
+/// kernel_tmult_simd
+/// t += x' * y
+///
+/// * x : p rows of m entries at stride s_x
+/// * y : p rows of n entries at stride s_y
+///
+/// # Safety
+///
+/// xptr and yptr must be valid for reads of p rows of m and n entries at strides s_x and s_y,
+/// tptr must be valid for writes of m rows of n entries at stride s_t without overlapping them
 #[inline(always)]
-pub fn kernel_tmult_simd(
-    _xptr: *const f32,
-    _yptr: *const f32,
-    _tptr: *mut f32,
-    _m: usize,
-    _p: usize,
-    _n: usize,
-    _s_x: usize,
-    _s_y: usize,
-    _s_t: usize,
+pub unsafe fn kernel_tmult_simd(
+    mut xptr: *const f32,
+    mut yptr: *const f32,
+    tptr: *mut f32,
+    m: usize,
+    p: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
 ) {
-    panic!("not yet implemented");
+    unsafe {
+        for _ in 0..p {
+            masked_accum(xptr, 1, yptr, tptr, 0..m, 0..n, s_t);
+            xptr = xptr.add(s_x);
+            yptr = yptr.add(s_y);
+        }
+    }
 }
 #[inline(always)]
 pub unsafe fn kernel_mult_scalar(
@@ -99,51 +116,136 @@ pub unsafe fn kernel_mult_scalar(
         }
     }
 }
-pub unsafe fn kernel_lt_mult_simd(
+// t[i][cols] += x[i] * y[cols] for i in rows, x[i] lives at xptr + i * x_step
+#[inline(always)]
+unsafe fn masked_accum(
+    xptr: *const f32,
+    x_step: usize,
+    yptr: *const f32,
+    tptr: *mut f32,
+    rows: Range<usize>,
+    cols: Range<usize>,
+    s_t: usize,
+) {
+    unsafe {
+        for i in rows {
+            let scalar = *xptr.add(i * x_step);
+            let trow = tptr.add(i * s_t);
+            for j in cols.clone() {
+                *trow.add(j) += scalar * *yptr.add(j);
+            }
+        }
+    }
+}
+// scalar lmult_lt and lmult_tlt, x_step and x_next select x or x'
+unsafe fn lmult_lt(
     mut xptr: *const f32,
     mut yptr: *const f32,
-    mut tptr: *mut f32,
-    _: usize,
-    _: usize,
+    tptr: *mut f32,
+    pre: usize,
+    pro: usize,
+    pos: usize,
+    m: usize,
+    n: usize,
+    x_step: usize,
+    x_next: usize,
+    s_y: usize,
+    s_t: usize,
+) {
+    unsafe {
+        for k in 0..pro + pos {
+            // rows above the diagonal drop out one per contraction in the triangle
+            let lo = if k < pro { pre } else { pre + k - pro + 1 };
+            masked_accum(xptr, x_step, yptr, tptr, lo..m, 0..n, s_t);
+            xptr = xptr.add(x_next);
+            yptr = yptr.add(s_y);
+        }
+    }
+}
+// scalar lmult_ut and lmult_tut, x_step and x_next select x or x'
+unsafe fn lmult_ut(
+    mut xptr: *const f32,
+    mut yptr: *const f32,
+    tptr: *mut f32,
+    pre: usize,
+    pro: usize,
+    pos: usize,
+    n: usize,
+    x_step: usize,
+    x_next: usize,
+    s_y: usize,
+    s_t: usize,
+) {
+    unsafe {
+        for k in 0..pos + pro {
+            // rows below the diagonal join one per contraction in the triangle
+            let hi = pre + (k + 1).min(pos);
+            masked_accum(xptr, x_step, yptr, tptr, 0..hi, 0..n, s_t);
+            xptr = xptr.add(x_next);
+            yptr = yptr.add(s_y);
+        }
+    }
+}
+// scalar rmult_lt
+unsafe fn rmult_lt(
+    mut xptr: *const f32,
+    mut yptr: *const f32,
+    tptr: *mut f32,
+    pre: usize,
+    pro: usize,
+    pos: usize,
     m: usize,
-    p: usize,
     n: usize,
     s_x: usize,
     s_y: usize,
     s_t: usize,
 ) {
     unsafe {
-        let mut acc = [0f32; 8];
-        let yorig = yptr;
-        for i in 0..m {
-            yptr = yorig;
-            {
-                let scalar = *xptr;
-                for j in 0..n {
-                    acc[j] = scalar * *yptr.add(j);
-                }
-            }
-            for k in 1..p {
-                let scalar = *xptr.add(k);
-                yptr = yptr.add(s_y);
-                for j in 0..=i {
-                    acc[j] += scalar * *yptr.add(j);
-                }
-            }
-            for j in 0..=i {
-                *tptr.add(j) += acc[j];
-            }
-            tptr = tptr.add(s_t);
-            xptr = xptr.add(s_x);
+        for k in 0..pos + pro {
+            let hi = if k < pos { (pre + k + 1).min(n) } else { n };
+            masked_accum(xptr, s_x, yptr, tptr, 0..m, 0..hi, s_t);
+            xptr = xptr.add(1);
+            yptr = yptr.add(s_y);
         }
     }
 }
-pub unsafe fn kernel_ut_mult_simd(
+// scalar rmult_ut
+unsafe fn rmult_ut(
     mut xptr: *const f32,
     mut yptr: *const f32,
-    mut tptr: *mut f32,
-    _: usize,
-    _: usize,
+    tptr: *mut f32,
+    pre: usize,
+    pro: usize,
+    pos: usize,
+    m: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
+) {
+    unsafe {
+        for k in 0..pro + pos {
+            let lo = if k < pro { 0 } else { pre + k - pro + 1 };
+            masked_accum(xptr, s_x, yptr, tptr, 0..m, lo..n, s_t);
+            xptr = xptr.add(1);
+            yptr = yptr.add(s_y);
+        }
+    }
+}
+/// kernel_lt_mult_simd
+/// t += l * y for the lower trapezoid l of x, the diagonal offset is d_add - d_sub
+///
+/// # Safety
+///
+/// xptr must be valid for reads of m rows of p entries at stride s_x, yptr for reads of p rows
+/// of n entries at stride s_y and tptr for writes of m rows of n entries at stride s_t without
+/// overlapping them
+pub unsafe fn kernel_lt_mult_simd(
+    xptr: *const f32,
+    yptr: *const f32,
+    tptr: *mut f32,
+    d_add: usize,
+    d_sub: usize,
     m: usize,
     p: usize,
     n: usize,
@@ -151,89 +253,195 @@ pub unsafe fn kernel_ut_mult_simd(
     s_y: usize,
     s_t: usize,
 ) {
+    let d_pos = d_add.saturating_sub(d_sub);
+    let d_neg = d_sub.saturating_sub(d_add);
+    let pre = d_neg;
+    let pro = d_pos;
+    debug_assert!(m >= d_neg, "m {m:}, d_neg: {d_neg:}");
+    let pos = (p - p.min(d_pos)).min(m - d_neg);
     unsafe {
-        let mut acc = [0f32; 8];
-        let yorig = yptr;
-        for i in 0..m {
-            yptr = yorig;
-            {
-                let scalar = *xptr;
-                for j in 0..n {
-                    acc[j] = scalar * *yptr.add(j);
-                }
-            }
-            for k in 1..p {
-                let scalar = *xptr.add(k);
-                yptr = yptr.add(s_y);
-                for j in 0..=i {
-                    acc[j] += scalar * *yptr.add(j);
-                }
-            }
-            for j in 0..=i {
-                *tptr.add(j) += acc[j];
-            }
-            tptr = tptr.add(s_t);
-            xptr = xptr.add(s_x);
+        if pos != 0 {
+            lmult_lt(xptr, yptr, tptr, pre, pro, pos, m, n, s_x, 1, s_y, s_t);
+        } else {
+            kernel_mult_simd(xptr, yptr, tptr, m, p, n, s_x, s_y, s_t);
+        }
+    }
+}
+/// kernel_tlt_mult_simd
+/// t += l' * y for the lower trapezoid l of x, the diagonal offset is d_add - d_sub
+///
+/// # Safety
+///
+/// xptr and yptr must be valid for reads of p rows of m and n entries at strides s_x and s_y,
+/// tptr must be valid for writes of m rows of n entries at stride s_t without overlapping them
+pub unsafe fn kernel_tlt_mult_simd(
+    xptr: *const f32,
+    yptr: *const f32,
+    tptr: *mut f32,
+    d_add: usize,
+    d_sub: usize,
+    m: usize,
+    p: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
+) {
+    let d_pos = d_add.saturating_sub(d_sub);
+    let d_neg = d_sub.saturating_sub(d_add);
+    let pre = d_neg;
+    let pro = d_pos.min(p);
+    let pos = (p - p.min(d_pos)).min(m - d_neg);
+    unsafe {
+        if pos != 0 {
+            lmult_lt(xptr, yptr, tptr, pre, pro, pos, m, n, 1, s_x, s_y, s_t);
+        } else {
+            kernel_tmult_simd(xptr, yptr, tptr, m, p, n, s_x, s_y, s_t);
         }
     }
 }
-pub fn kernel_tlt_mult_simd(
-    _xptr: *const f32,
-    _yptr: *const f32,
-    _tptr: *mut f32,
-    _: usize,
-    _: usize,
-    _m: usize,
-    _p: usize,
-    _n: usize,
-    _s_x: usize,
-    _s_y: usize,
-    _s_t: usize,
+/// kernel_ut_mult_simd
+/// t += u * y for the upper trapezoid u of x, the diagonal offset is d_add - d_sub
+///
+/// # Safety
+///
+/// xptr must be valid for reads of m rows of p entries at stride s_x, yptr for reads of p rows
+/// of n entries at stride s_y and tptr for writes of m rows of n entries at stride s_t without
+/// overlapping them
+pub unsafe fn kernel_ut_mult_simd(
+    mut xptr: *const f32,
+    mut yptr: *const f32,
+    tptr: *mut f32,
+    d_add: usize,
+    d_sub: usize,
+    m: usize,
+    mut p: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
 ) {
-    panic!("not yet implemented");
+    let d_pos = d_add.saturating_sub(d_sub);
+    let d_neg = d_sub.saturating_sub(d_add);
+    let pre = m.min(d_neg);
+    let pos = (p - p.min(d_pos)).min(m - pre);
+    debug_assert!(d_pos + pos <= p, "d_pos: {d_pos}, pos:{pos}, p:{p}");
+    let pro = p - d_pos - pos;
+    unsafe {
+        p -= d_pos;
+        xptr = xptr.add(d_pos);
+        yptr = yptr.add(d_pos * s_y);
+        if pos != 0 {
+            lmult_ut(xptr, yptr, tptr, pre, pro, pos, n, s_x, 1, s_y, s_t);
+        } else {
+            kernel_mult_simd(xptr, yptr, tptr, m, p, n, s_x, s_y, s_t);
+        }
+    }
 }
-pub fn kernel_rlt_mult_simd(
-    _xptr: *const f32,
-    _yptr: *const f32,
-    _tptr: *mut f32,
-    _: usize,
-    _: usize,
-    _m: usize,
-    _p: usize,
-    _n: usize,
-    _s_x: usize,
-    _s_y: usize,
-    _s_t: usize,
+/// kernel_tut_mult_simd
+/// t += u' * y for the upper trapezoid u of x, the diagonal offset is d_add - d_sub
+///
+/// # Safety
+///
+/// xptr and yptr must be valid for reads of p rows of m and n entries at strides s_x and s_y,
+/// tptr must be valid for writes of m rows of n entries at stride s_t without overlapping them
+pub unsafe fn kernel_tut_mult_simd(
+    mut xptr: *const f32,
+    mut yptr: *const f32,
+    tptr: *mut f32,
+    d_add: usize,
+    d_sub: usize,
+    m: usize,
+    mut p: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
 ) {
-    panic!("not yet implemented");
+    let d_pos = d_add.saturating_sub(d_sub);
+    let d_neg = d_sub.saturating_sub(d_add);
+    let pre = m.min(d_neg);
+    let pos = (p - p.min(d_pos)).min(m - pre);
+    let pro = p.saturating_sub(d_pos + pos);
+    unsafe {
+        p -= d_pos;
+        xptr = xptr.add(d_pos * s_x);
+        yptr = yptr.add(d_pos * s_y);
+        if pos != 0 {
+            lmult_ut(xptr, yptr, tptr, pre, pro, pos, n, 1, s_x, s_y, s_t);
+        } else {
+            kernel_tmult_simd(xptr, yptr, tptr, m, p, n, s_x, s_y, s_t);
+        }
+    }
 }
-pub fn kernel_rut_mult_simd(
-    _xptr: *const f32,
-    _yptr: *const f32,
-    _tptr: *mut f32,
-    _: usize,
-    _: usize,
-    _m: usize,
-    _p: usize,
-    _n: usize,
-    _s_x: usize,
-    _s_y: usize,
-    _s_t: usize,
+/// kernel_rlt_mult_simd
+/// t += x * l for the lower trapezoid l of y, the diagonal offset is d_add - d_sub
+///
+/// # Safety
+///
+/// xptr must be valid for reads of m rows of p entries at stride s_x, yptr for reads of p rows
+/// of n entries at stride s_y and tptr for writes of m rows of n entries at stride s_t without
+/// overlapping them
+pub unsafe fn kernel_rlt_mult_simd(
+    mut xptr: *const f32,
+    mut yptr: *const f32,
+    tptr: *mut f32,
+    d_add: usize,
+    d_sub: usize,
+    m: usize,
+    mut p: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
 ) {
-    panic!("not yet implemented");
+    let d_pos = d_add.saturating_sub(d_sub);
+    let d_neg = d_sub.saturating_sub(d_add);
+    let pre = d_pos;
+    let pos = (n.saturating_sub(pre)).min(p);
+    let pro = p.saturating_sub(pos + d_neg);
+    unsafe {
+        xptr = xptr.add(d_neg);
+        yptr = yptr.add(d_neg * s_y);
+        p = p.saturating_sub(d_neg);
+        if pos != 0 {
+            rmult_lt(xptr, yptr, tptr, pre, pro, pos, m, n, s_x, s_y, s_t);
+        } else {
+            kernel_mult_simd(xptr, yptr, tptr, m, p, n, s_x, s_y, s_t);
+        }
+    }
 }
-pub fn kernel_tut_mult_simd(
-    _xptr: *const f32,
-    _yptr: *const f32,
-    _tptr: *mut f32,
-    _: usize,
-    _: usize,
-    _m: usize,
-    _p: usize,
-    _n: usize,
-    _s_x: usize,
-    _s_y: usize,
-    _s_t: usize,
+/// kernel_rut_mult_simd
+/// t += x * u for the upper trapezoid u of y, the diagonal offset is d_add - d_sub
+///
+/// # Safety
+///
+/// xptr must be valid for reads of m rows of p entries at stride s_x, yptr for reads of p rows
+/// of n entries at stride s_y and tptr for writes of m rows of n entries at stride s_t without
+/// overlapping them
+pub unsafe fn kernel_rut_mult_simd(
+    xptr: *const f32,
+    yptr: *const f32,
+    tptr: *mut f32,
+    d_add: usize,
+    d_sub: usize,
+    m: usize,
+    p: usize,
+    n: usize,
+    s_x: usize,
+    s_y: usize,
+    s_t: usize,
 ) {
-    panic!("not yet implemented");
+    let d_pos = d_add.saturating_sub(d_sub);
+    let d_neg = d_sub.saturating_sub(d_add);
+    let pre = d_neg;
+    let pro = d_pos.min(p);
+    let pos = (n.saturating_sub(pre)).min(p - pro);
+    unsafe {
+        if pos != 0 {
+            rmult_ut(xptr, yptr, tptr, pre, pro, pos, m, n, s_x, s_y, s_t);
+        } else {
+            kernel_mult_simd(xptr, yptr, tptr, m, p, n, s_x, s_y, s_t);
+        }
+    }
 }
@@ -44,6 +44,18 @@ pub fn generate_random_matrix(m: usize, n: usize) -> NdArray {
         data,
     }
 }
+// reproducible generate_random_matrix for tests that need a fixed draw
+pub fn generate_seeded_matrix(m: usize, n: usize, seed: u64) -> NdArray {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut data = vec![0f32; m * n];
+    for idx in 0..m * n {
+        data[idx] = rng.sample(StandardNormal);
+    }
+    NdArray {
+        dims: vec![m, n],
+        data,
+    }
+}
 pub fn generate_random_symetric(n: usize) -> NdArray {
     let mut rng = rand::rng();
     let mut data = vec![0f32; n * n];
@@ -0,0 +1,654 @@
+use crate::algebra::vector::{dot_product, norm_inf};
+use crate::decomposition::lower_upper::LuPivotDecompose;
+use crate::decomposition::qr::solve_qr;
+use crate::solver::optimize::{DIFFERENCE_STEP, Objective, Termination};
+use crate::structure::ndarray::NdArray;
+
+// linearized inequalities above -QP_TOLERANCE (1 + ||a_i||) count as satisfied
+const QP_TOLERANCE: f32 = 1e-5;
+// a_p'z at or below this leaves constraint p dependent on the active set
+const DEPENDENT: f32 = 1e-7;
+// sufficient decrease of the merit function
+const ARMIJO: f32 = 1e-4;
+// halvings of a step before the line search gives up
+const BACKTRACKS: usize = 30;
+// penalty kept above the largest multiplier by this factor
+const PENALTY_MARGIN: f32 = 1.5;
+// powell damping keeps s'y above this fraction of s'Bs
+const POWELL: f32 = 0.2;
+// regularization of the least squares restoration step
+const RESTORATION: f32 = 1e-3;
+
+/// Constraints
+/// constraint vector c: R^n -> R^m, the first equalities entries must vanish and the remaining
+/// inequalities entries must be nonnegative, only the values are required, the jacobian falls
+/// back to central differences
+///
+/// * equalities: number of constraints c_i(x) = 0
+/// * inequalities: number of constraints c_i(x) >= 0
+/// * constraints: c <- c(x), equalities first
+/// * jacobian: j <- dc/dx as a m x n row major NdArray
+pub trait Constraints {
+    fn equalities(&self) -> usize;
+    fn inequalities(&self) -> usize;
+    fn constraints(&self, x: &[f32], c: &mut [f32]);
+    fn jacobian(&self, x: &[f32], j: &mut NdArray) {
+        let (m, n) = (self.equalities() + self.inequalities(), x.len());
+        debug_assert!(j.dims[0] == m && j.dims[1] == n);
+        let mut probe = x.to_vec();
+        let mut forward = vec![0f32; m];
+        let mut backward = vec![0f32; m];
+        for k in 0..n {
+            let h = DIFFERENCE_STEP * x[k].abs().max(1f32);
+            probe[k] = x[k] + h;
+            self.constraints(&probe, &mut forward);
+            probe[k] = x[k] - h;
+            self.constraints(&probe, &mut backward);
+            probe[k] = x[k];
+            for i in 0..m {
+                j.data[i * n + k] = (forward[i] - backward[i]) / (2f32 * h);
+            }
+        }
+    }
+}
+
+/// KktResiduals
+/// first order optimality of x with multipliers l for the lagrangian f(x) - l'c(x)
+///
+/// * stationarity: ||grad f - J'l||_inf
+/// * feasibility: largest |c_i| of an equality or -c_i of a violated inequality
+/// * complementarity: largest |l_i c_i| of an inequality
+#[derive(Clone, Copy, Debug)]
+pub struct KktResiduals {
+    pub stationarity: f32,
+    pub feasibility: f32,
+    pub complementarity: f32,
+}
+
+/// ConstrainedResult
+///
+/// * x: final iterate
+/// * value: f(x)
+/// * constraints: c(x)
+/// * multipliers: lagrange multipliers of c, equalities first, inequality multipliers are
+///   nonnegative
+/// * kkt: optimality residuals of x and multipliers
+/// * iterations: sqp steps including restoration steps
+/// * qp_iterations: active set changes summed over all quadratic subproblems
+/// * evaluations: calls to value, including those made with a gradient
+/// * termination: reason the iteration stopped, Stalled also covers linearizations that stay
+///   infeasible after restoration
+#[derive(Clone, Debug)]
+pub struct ConstrainedResult {
+    pub x: Vec<f32>,
+    pub value: f32,
+    pub constraints: Vec<f32>,
+    pub multipliers: Vec<f32>,
+    pub kkt: KktResiduals,
+    pub iterations: usize,
+    pub qp_iterations: usize,
+    pub evaluations: usize,
+    pub termination: Termination,
+}
+
+/// SequentialQuadratic
+/// sqp method for min f(x) s.t. c_E(x) = 0, c_I(x) >= 0, every step solves the quadratic
+/// subproblem min 1/2 d'Bd + g'd s.t. c + Jd = 0 / >= 0 with the dual active set method of
+/// goldfarb and idnani, B is a powell damped bfgs approximation of the lagrangian hessian
+/// and steps are accepted on the l1 merit function f + mu ||c^-||_1, subproblems with
+/// inconsistent linearizations are replaced by a least squares step on the violation
+///
+/// * tolerance: bound on every kkt residual
+/// * max_iters: maximum number of sqp steps
+/// * qp_max_iters: maximum number of active set changes of one subproblem
+/// * penalty: initial l1 penalty mu, raised to stay above the multipliers
+pub struct SequentialQuadratic {
+    pub tolerance: f32,
+    pub max_iters: usize,
+    pub qp_max_iters: usize,
+    pub penalty: f32,
+}
+
+// solution of a quadratic subproblem
+struct QpStep {
+    d: Vec<f32>,
+    multipliers: Vec<f32>,
+    iterations: usize,
+}
+
+// [H, -A'; A, 0] [z; u] = [top; bottom] for the rows of a listed in active
+fn kkt_solve(h: &NdArray, a: &NdArray, active: &[usize], top: &[f32], bottom: &[f32]) -> Vec<f32> {
+    let n = h.dims[0];
+    let k = n + active.len();
+    let mut data = vec![0f32; k * k];
+    for i in 0..n {
+        data[i * k..i * k + n].copy_from_slice(&h.data[i * n..(i + 1) * n]);
+    }
+    for (r, &i) in active.iter().enumerate() {
+        for j in 0..n {
+            data[(n + r) * k + j] = a.data[i * n + j];
+            data[j * k + n + r] = -a.data[i * n + j];
+        }
+    }
+    let lu = LuPivotDecompose::new(NdArray::new(vec![k, k], data), &mut vec![0f32; k]);
+    let mut rhs = top.to_vec();
+    rhs.extend_from_slice(bottom);
+    lu.solve_inplace_vec(&mut rhs);
+    rhs
+}
+
+// min 1/2 d'Hd + g'd s.t. a_i'd + c_i = 0 for i < equalities and >= 0 otherwise, H positive
+// definite, the dual method starts from the equality constrained minimum and adds the most
+// violated inequality, dropping active ones whose multipliers reach zero on the way
+fn active_set_qp(
+    h: &NdArray,
+    g: &[f32],
+    a: &NdArray,
+    c: &[f32],
+    equalities: usize,
+    max_iters: usize,
+) -> Result<QpStep, String> {
+    let (m, n) = (a.dims[0], a.dims[1]);
+    let row = |i: usize| &a.data[i * n..(i + 1) * n];
+    let mut active: Vec<usize> = (0..equalities).collect();
+    let minus_g: Vec<f32> = g.iter().map(|v| -v).collect();
+    let minus_c: Vec<f32> = c[..equalities].iter().map(|v| -v).collect();
+    let solution = kkt_solve(h, a, &active, &minus_g, &minus_c);
+    if solution.iter().any(|v| !v.is_finite()) {
+        return Err("equality constraint gradients are dependent".to_string());
+    }
+    let mut d = solution[..n].to_vec();
+    let mut multipliers = vec![0f32; m];
+    multipliers[..equalities].copy_from_slice(&solution[n..]);
+    let mut iterations = 0;
+    loop {
+        // most violated inequality relative to the size of its gradient
+        let violated = (equalities..m)
+            .filter(|i| !active.contains(i))
+            .map(|i| {
+                (
+                    i,
+                    (dot_product(row(i), &d) + c[i]) / (1f32 + norm_inf(row(i))),
+                )
+            })
+            .filter(|&(_, s)| s < -QP_TOLERANCE)
+            .min_by(|x, y| x.1.total_cmp(&y.1));
+        let Some((p, _)) = violated else {
+            return Ok(QpStep {
+                d,
+                multipliers,
+                iterations,
+            });
+        };
+        // raise the multiplier t of p, the active constraints stay satisfied
+        loop {
+            if iterations >= max_iters {
+                return Err("active set iteration limit reached".to_string());
+            }
+            iterations += 1;
+            let zeros = vec![0f32; active.len()];
+            let step = kkt_solve(h, a, &active, row(p), &zeros);
+            let (z, r) = step.split_at(n);
+            let blocking = active
+                .iter()
+                .zip(r)
+                .enumerate()
+                .filter(|&(_, (&i, &r))| i >= equalities && r < 0f32)
+                .map(|(k, (&i, &r))| (k, multipliers[i] / -r))
+                .min_by(|x, y| x.1.total_cmp(&y.1));
+            let curvature = dot_product(row(p), z);
+            let full = if curvature > DEPENDENT {
+                Some(-(dot_product(row(p), &d) + c[p]) / curvature)
+            } else {
+                None
+            };
+            let t = match (full, blocking) {
+                (Some(full), Some((_, partial))) => full.min(partial),
+                (Some(full), None) => full,
+                (None, Some((_, partial))) => partial,
+                (None, None) => {
+                    return Err("linearized constraints are infeasible".to_string());
+                }
+            };
+            d.iter_mut().zip(z).for_each(|(d, z)| *d += t * z);
+            for (&i, r) in active.iter().zip(r) {
+                multipliers[i] += t * r;
+            }
+            multipliers[p] += t;
+            match (full, blocking) {
+                (Some(full), Some((k, partial))) if partial < full => {
+                    multipliers[active[k]] = 0f32;
+                    active.remove(k);
+                }
+                (None, Some((k, _))) => {
+                    multipliers[active[k]] = 0f32;
+                    active.remove(k);
+                }
+                _ => {
+                    active.push(p);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// l1 norm of the violation
+fn violation(c: &[f32], equalities: usize) -> f32 {
+    let equal: f32 = c[..equalities].iter().map(|v| v.abs()).sum();
+    let unequal: f32 = c[equalities..].iter().map(|v| (-v).max(0f32)).sum();
+    equal + unequal
+}
+
+// 1/2 ||c^-||^2, the measure of the least squares restoration
+fn squared_violation(c: &[f32], equalities: usize) -> f32 {
+    let equal: f32 = c[..equalities].iter().map(|v| v * v).sum();
+    let unequal: f32 = c[equalities..].iter().map(|v| v.min(0f32).powi(2)).sum();
+    0.5 * (equal + unequal)
+}
+
+// grad f - J'l
+fn lagrangian_gradient(g: &[f32], j: &NdArray, multipliers: &[f32]) -> Vec<f32> {
+    let n = j.dims[1];
+    let mut gradient = g.to_vec();
+    for (row, l) in j.data.chunks(n).zip(multipliers) {
+        gradient.iter_mut().zip(row).for_each(|(g, a)| *g -= l * a);
+    }
+    gradient
+}
+
+fn kkt_residuals(
+    g: &[f32],
+    c: &[f32],
+    j: &NdArray,
+    multipliers: &[f32],
+    equalities: usize,
+) -> KktResiduals {
+    let equal = c[..equalities].iter().fold(0f32, |acc, v| acc.max(v.abs()));
+    let unequal = c[equalities..].iter().fold(0f32, |acc, v| acc.max(-v));
+    KktResiduals {
+        stationarity: norm_inf(&lagrangian_gradient(g, j, multipliers)),
+        feasibility: equal.max(unequal),
+        complementarity: c[equalities..]
+            .iter()
+            .zip(&multipliers[equalities..])
+            .fold(0f32, |acc, (c, l)| acc.max((c * l).abs())),
+    }
+}
+
+// powell damped bfgs update of b with the step s and the change y of the lagrangian gradient
+fn damped_bfgs(b: &mut NdArray, s: &[f32], y: &[f32]) {
+    let n = s.len();
+    let bs: Vec<f32> = b.data.chunks(n).map(|row| dot_product(row, s)).collect();
+    let sbs = dot_product(s, &bs);
+    if sbs <= 0f32 || !sbs.is_finite() {
+        return;
+    }
+    let sy = dot_product(s, y);
+    let theta = if sy >= POWELL * sbs {
+        1f32
+    } else {
+        (1f32 - POWELL) * sbs / (sbs - sy)
+    };
+    let r: Vec<f32> = y
+        .iter()
+        .zip(&bs)
+        .map(|(y, bs)| theta * y + (1f32 - theta) * bs)
+        .collect();
+    let sr = dot_product(s, &r);
+    for i in 0..n {
+        for k in 0..n {
+            b.data[i * n + k] += r[i] * r[k] / sr - bs[i] * bs[k] / sbs;
+        }
+    }
+}
+
+impl SequentialQuadratic {
+    pub fn new(tolerance: f32, max_iters: usize) -> Self {
+        Self {
+            tolerance,
+            max_iters,
+            qp_max_iters: 200,
+            penalty: 1f32,
+        }
+    }
+    pub fn with_qp_max_iters(mut self, qp_max_iters: usize) -> Self {
+        self.qp_max_iters = qp_max_iters;
+        self
+    }
+    pub fn with_penalty(mut self, penalty: f32) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    // gauss newton step on the violated constraints, regularized so the least squares system
+    // keeps full column rank, returns the step and the slope of 1/2 ||c^-||^2 along it
+    fn restoration(&self, c: &[f32], j: &NdArray, equalities: usize) -> (Vec<f32>, f32) {
+        let n = j.dims[1];
+        let rows: Vec<usize> = (0..c.len())
+            .filter(|&i| i < equalities || c[i] < 0f32)
+            .collect();
+        let mut data = vec![0f32; (rows.len() + n) * n];
+        let mut rhs = vec![0f32; rows.len() + n];
+        for (r, &i) in rows.iter().enumerate() {
+            data[r * n..(r + 1) * n].copy_from_slice(&j.data[i * n..(i + 1) * n]);
+            rhs[r] = -c[i];
+        }
+        for k in 0..n {
+            data[(rows.len() + k) * n + k] = RESTORATION;
+        }
+        let d = solve_qr(NdArray::new(vec![rows.len() + n, n], data), &rhs);
+        let slope = rows
+            .iter()
+            .map(|&i| c[i] * dot_product(&j.data[i * n..(i + 1) * n], &d))
+            .sum();
+        (d, slope)
+    }
+
+    /// solve
+    /// minimizes the objective subject to the constraints from x0, which need not be feasible
+    pub fn solve(
+        &self,
+        objective: &dyn Objective,
+        constraints: &dyn Constraints,
+        x0: &[f32],
+    ) -> ConstrainedResult {
+        let n = x0.len();
+        let equalities = constraints.equalities();
+        let m = equalities + constraints.inequalities();
+        let mut x = x0.to_vec();
+        let mut g = vec![0f32; n];
+        let mut value = objective.value_gradient(&x, &mut g);
+        let mut evaluations = 1;
+        let mut c = vec![0f32; m];
+        constraints.constraints(&x, &mut c);
+        let mut j = NdArray::new(vec![m, n], vec![0f32; m * n]);
+        constraints.jacobian(&x, &mut j);
+        let mut b = NdArray::new(vec![n, n], vec![0f32; n * n]);
+        (0..n).for_each(|i| b.data[i * n + i] = 1f32);
+        let mut multipliers = vec![0f32; m];
+        let mut penalty = self.penalty;
+        let mut iterations = 0;
+        let mut qp_iterations = 0;
+        let mut termination = Termination::MaxIterations;
+        loop {
+            let kkt = kkt_residuals(&g, &c, &j, &multipliers, equalities);
+            if kkt.stationarity <= self.tolerance
+                && kkt.feasibility <= self.tolerance
+                && kkt.complementarity <= self.tolerance
+            {
+                termination = Termination::Converged;
+                break;
+            }
+            if iterations >= self.max_iters {
+                break;
+            }
+            iterations += 1;
+            let theta = violation(&c, equalities);
+            let qp = active_set_qp(&b, &g, &j, &c, equalities, self.qp_max_iters);
+            if let Ok(step) = &qp {
+                qp_iterations += step.iterations;
+            }
+            // sqp steps descend on f + mu ||c^-||_1, restoration steps on 1/2 ||c^-||^2
+            let (d, step_multipliers, slope) = match qp {
+                Ok(step) => {
+                    let largest = norm_inf(&step.multipliers);
+                    if penalty < largest * PENALTY_MARGIN {
+                        penalty = largest * PENALTY_MARGIN;
+                    }
+                    let slope = dot_product(&g, &step.d) - penalty * theta;
+                    (step.d, Some(step.multipliers), slope)
+                }
+                Err(_) => {
+                    let (d, slope) = self.restoration(&c, &j, equalities);
+                    (d, None, slope)
+                }
+            };
+            let restoring = step_multipliers.is_none();
+            let merit = if restoring {
+                squared_violation(&c, equalities)
+            } else {
+                value + penalty * theta
+            };
+            let mut trial_c = vec![0f32; m];
+            let mut alpha = 1f32;
+            let mut accepted = None;
+            for _ in 0..BACKTRACKS {
+                let trial: Vec<f32> = x.iter().zip(&d).map(|(x, d)| x + alpha * d).collect();
+                constraints.constraints(&trial, &mut trial_c);
+                let trial_merit = if restoring {
+                    squared_violation(&trial_c, equalities)
+                } else {
+                    evaluations += 1;
+                    objective.value(&trial) + penalty * violation(&trial_c, equalities)
+                };
+                if trial_merit <= merit + ARMIJO * alpha * slope.min(0f32) {
+                    accepted = Some(trial);
+                    break;
+                }
+                alpha *= 0.5;
+            }
+            let Some(trial) = accepted else {
+                termination = match step_multipliers {
+                    Some(_) => Termination::LineSearchFailed,
+                    None => Termination::Stalled,
+                };
+                break;
+            };
+            let s: Vec<f32> = trial.iter().zip(&x).map(|(a, b)| a - b).collect();
+            let mut trial_g = vec![0f32; n];
+            value = objective.value_gradient(&trial, &mut trial_g);
+            evaluations += 1;
+            let mut trial_j = NdArray::new(vec![m, n], vec![0f32; m * n]);
+            constraints.jacobian(&trial, &mut trial_j);
+            if let Some(step_multipliers) = step_multipliers {
+                let y: Vec<f32> = lagrangian_gradient(&trial_g, &trial_j, &step_multipliers)
+                    .iter()
+                    .zip(lagrangian_gradient(&g, &j, &step_multipliers))
+                    .map(|(a, b)| a - b)
+                    .collect();
+                damped_bfgs(&mut b, &s, &y);
+                multipliers = step_multipliers;
+            }
+            (x, g, c, j) = (trial, trial_g, trial_c, trial_j);
+            if norm_inf(&s) <= f32::EPSILON * (norm_inf(&x) + f32::EPSILON) {
+                termination = Termination::Stalled;
+                break;
+            }
+        }
+        ConstrainedResult {
+            kkt: kkt_residuals(&g, &c, &j, &multipliers, equalities),
+            x,
+            value,
+            constraints: c,
+            multipliers,
+            iterations,
+            qp_iterations,
+            evaluations,
+            termination,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_constrained {
+    use super::*;
+
+    struct Quadratic {
+        center: Vec<f32>,
+    }
+
+    impl Objective for Quadratic {
+        fn value(&self, x: &[f32]) -> f32 {
+            x.iter()
+                .zip(&self.center)
+                .map(|(x, c)| (x - c).powi(2))
+                .sum()
+        }
+        fn gradient(&self, x: &[f32], g: &mut [f32]) {
+            for i in 0..x.len() {
+                g[i] = 2f32 * (x[i] - self.center[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_active_set_qp() {
+        // min (x - 1)^2 + (y - 2.5)^2 over a pentagon, optimum (1.4, 1.7) on x - 2y + 2 >= 0
+        let h = NdArray::new(vec![2, 2], vec![2f32, 0f32, 0f32, 2f32]);
+        let a = NdArray::new(
+            vec![5, 2],
+            vec![
+                1f32, -2f32, -1f32, -2f32, -1f32, 2f32, 1f32, 0f32, 0f32, 1f32,
+            ],
+        );
+        let c = [2f32, 6f32, 2f32, 0f32, 0f32];
+        let step = active_set_qp(&h, &[-2f32, -5f32], &a, &c, 0, 50).unwrap();
+        assert!((step.d[0] - 1.4).abs() < 1e-5, "{:?}", step.d);
+        assert!((step.d[1] - 1.7).abs() < 1e-5, "{:?}", step.d);
+        assert!((step.multipliers[0] - 0.8).abs() < 1e-5);
+        assert!(step.multipliers[1..].iter().all(|l| *l == 0f32));
+        // x >= 3 contradicts x + 2y <= 2 and y >= 0
+        let a = NdArray::new(vec![3, 2], vec![-1f32, -2f32, 0f32, 1f32, 1f32, 0f32]);
+        let c = [2f32, 0f32, -3f32];
+        assert!(active_set_qp(&h, &[-2f32, -5f32], &a, &c, 0, 50).is_err());
+    }
+
+    #[test]
+    fn test_equality_multiplier() {
+        // min x^2 + y^2 s.t. x + y = 1, the multiplier of x + y - 1 is 1
+        struct Line;
+        impl Constraints for Line {
+            fn equalities(&self) -> usize {
+                1
+            }
+            fn inequalities(&self) -> usize {
+                0
+            }
+            fn constraints(&self, x: &[f32], c: &mut [f32]) {
+                c[0] = x[0] + x[1] - 1f32;
+            }
+        }
+        let objective = Quadratic {
+            center: vec![0f32, 0f32],
+        };
+        let result = SequentialQuadratic::new(1e-5, 50).solve(&objective, &Line, &[3f32, -1f32]);
+        assert_eq!(result.termination, Termination::Converged);
+        assert!((result.x[0] - 0.5).abs() < 1e-4, "{:?}", result.x);
+        assert!((result.x[1] - 0.5).abs() < 1e-4, "{:?}", result.x);
+        assert!((result.multipliers[0] - 1f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_inequalities() {
+        // min (x - 2)^2 + (y - 1)^2 s.t. y - x^2 >= 0, 2 - x - y >= 0, both active at (1, 1)
+        // with multipliers 2/3
+        struct Parabola;
+        impl Constraints for Parabola {
+            fn equalities(&self) -> usize {
+                0
+            }
+            fn inequalities(&self) -> usize {
+                2
+            }
+            fn constraints(&self, x: &[f32], c: &mut [f32]) {
+                c[0] = x[1] - x[0] * x[0];
+                c[1] = 2f32 - x[0] - x[1];
+            }
+            fn jacobian(&self, x: &[f32], j: &mut NdArray) {
+                j.data.copy_from_slice(&[-2f32 * x[0], 1f32, -1f32, -1f32]);
+            }
+        }
+        let objective = Quadratic {
+            center: vec![2f32, 1f32],
+        };
+        // infeasible start
+        let result =
+            SequentialQuadratic::new(1e-4, 100).solve(&objective, &Parabola, &[-1f32, -2f32]);
+        assert_eq!(result.termination, Termination::Converged);
+        assert!((result.x[0] - 1f32).abs() < 1e-3, "{:?}", result.x);
+        assert!((result.x[1] - 1f32).abs() < 1e-3, "{:?}", result.x);
+        for l in &result.multipliers {
+            assert!((l - 2f32 / 3f32).abs() < 1e-2, "{:?}", result.multipliers);
+        }
+        assert!(result.kkt.feasibility <= 1e-4);
+    }
+
+    #[test]
+    fn test_restoration() {
+        // x^2 + xy + y^2 = 1 and x <= 1/2 have parallel gradients at (0.02, -0.01), the
+        // linearization there is inconsistent and the first step restores feasibility
+        struct Ellipse;
+        impl Constraints for Ellipse {
+            fn equalities(&self) -> usize {
+                1
+            }
+            fn inequalities(&self) -> usize {
+                1
+            }
+            fn constraints(&self, x: &[f32], c: &mut [f32]) {
+                c[0] = x[0] * x[0] + x[0] * x[1] + x[1] * x[1] - 1f32;
+                c[1] = 0.5 - x[0];
+            }
+            fn jacobian(&self, x: &[f32], j: &mut NdArray) {
+                j.data[0] = 2f32 * x[0] + x[1];
+                j.data[1] = x[0] + 2f32 * x[1];
+                j.data[2] = -1f32;
+                j.data[3] = 0f32;
+            }
+        }
+        let x0 = [0.02, -0.01];
+        let mut c = vec![0f32; 2];
+        let mut j = NdArray::new(vec![2, 2], vec![0f32; 4]);
+        Ellipse.constraints(&x0, &mut c);
+        Ellipse.jacobian(&x0, &mut j);
+        let identity = NdArray::new(vec![2, 2], vec![1f32, 0f32, 0f32, 1f32]);
+        assert!(active_set_qp(&identity, &[0f32, 0f32], &j, &c, 1, 50).is_err());
+        let objective = Quadratic {
+            center: vec![1f32, 0f32],
+        };
+        let result = SequentialQuadratic::new(1e-4, 100).solve(&objective, &Ellipse, &x0);
+        assert_eq!(result.termination, Termination::Converged);
+        assert!(result.kkt.feasibility <= 1e-4);
+        // the bound is active with a positive multiplier
+        assert!((result.x[0] - 0.5).abs() < 1e-3, "{:?}", result.x);
+        assert!(result.multipliers[1] > 0f32);
+    }
+
+    #[test]
+    fn test_hock_schittkowski_71() {
+        // min x0 x3 (x0 + x1 + x2) + x2 s.t. x0 x1 x2 x3 >= 25, ||x||^2 = 40, 1 <= x <= 5 with
+        // difference gradients and jacobians
+        struct Hs71;
+        impl Objective for Hs71 {
+            fn value(&self, x: &[f32]) -> f32 {
+                x[0] * x[3] * (x[0] + x[1] + x[2]) + x[2]
+            }
+        }
+        impl Constraints for Hs71 {
+            fn equalities(&self) -> usize {
+                1
+            }
+            fn inequalities(&self) -> usize {
+                9
+            }
+            fn constraints(&self, x: &[f32], c: &mut [f32]) {
+                c[0] = x.iter().map(|v| v * v).sum::<f32>() - 40f32;
+                c[1] = x.iter().product::<f32>() - 25f32;
+                for i in 0..4 {
+                    c[2 + i] = x[i] - 1f32;
+                    c[6 + i] = 5f32 - x[i];
+                }
+            }
+        }
+        let result =
+            SequentialQuadratic::new(1e-3, 200).solve(&Hs71, &Hs71, &[1f32, 5f32, 5f32, 1f32]);
+        assert_eq!(result.termination, Termination::Converged);
+        let expected = [1f32, 4.743, 3.821, 1.379];
+        for (x, e) in result.x.iter().zip(expected) {
+            assert!((x - e).abs() < 1e-2, "{:?}", result.x);
+        }
+        assert!((result.value - 17.014).abs() < 1e-2);
+        assert!(result.multipliers[1..].iter().all(|l| *l >= 0f32));
+    }
+}
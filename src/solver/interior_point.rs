@@ -0,0 +1,569 @@
+use crate::algebra::ndmethods::matrix_mult;
+use crate::algebra::vector::{dot_product, norm_inf};
+use crate::decomposition::cholesky::Cholesky;
+use crate::solver::linear::{LinearProgram, LpSolution, LpStatus, Sense, StandardForm};
+use crate::structure::ndarray::NdArray;
+use crate::structure::operator::LinearOperator;
+
+// fraction of the distance to the boundary taken by a step
+const STEP_FRACTION: f32 = 0.99;
+// iterates this large relative to the data signal an infeasible or unbounded problem
+const DIVERGENCE: f32 = 1e8;
+// primal regularization of the newton system and dual regularization of the augmented one
+const REGULARIZATION: f32 = 1e-6;
+// largest common part of the two halves of a split free variable
+const FREE_SPLIT: f32 = 1f32;
+// iterative refinement steps of every newton solve
+const REFINEMENT: usize = 2;
+// iterations without progress on the residuals before the best iterate is returned as stalled
+const STALL: usize = 5;
+const EPSILON: f64 = 1e-12;
+
+/// NewtonSolve
+/// how the newton system of every iteration is solved, D = Q + X^-1 S + rI with a small
+/// regularization r and a few steps of iterative refinement against the exact system
+///
+/// * NormalEquations: cholesky of A D^-1 A', D is diagonal for linear programs and is factored
+///   with cholesky as well otherwise, iterations whose step misses A dx = rp fall back to the
+///   augmented system
+/// * Augmented: ldl' of the quasi definite [D, A'; A, -rI]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NewtonSolve {
+    NormalEquations,
+    Augmented,
+}
+
+/// InteriorPoint
+/// mehrotra predictor corrector primal dual method for min 1/2 x'Qx + c'x s.t. Ax = b, x >= 0
+/// on the StandardForm of a LinearProgram
+///
+/// * tolerance: bound on the relative primal residual, dual residual and duality gap
+/// * max_iters: maximum number of predictor corrector steps
+/// * newton: newton system solver
+pub struct InteriorPoint {
+    pub tolerance: f32,
+    pub max_iters: usize,
+    pub newton: NewtonSolve,
+}
+
+// factored newton matrix of one iteration, shared by the predictor and the corrector
+enum NewtonFactor {
+    Normal {
+        // 1 / diag(D) when Q is zero, otherwise the cholesky of D
+        d_inv: Option<Vec<f32>>,
+        d: Option<Cholesky>,
+        // D^-1 A'
+        dat: NdArray,
+        m: Cholesky,
+    },
+    Augmented(Ldl),
+}
+
+// ldl' without pivoting, valid for quasi definite matrices, kept in f64 since near the
+// optimum of a degenerate problem the eliminated dual block is conditioned beyond f32
+struct Ldl {
+    l: Vec<f64>,
+    d: Vec<f64>,
+}
+
+impl Ldl {
+    fn new(a: NdArray) -> Self {
+        let n = a.dims[0];
+        let mut m: Vec<f64> = a.data.iter().map(|v| *v as f64).collect();
+        let mut d = vec![0f64; n];
+        for j in 0..n {
+            let mut djj = m[j * n + j];
+            for k in 0..j {
+                djj -= m[j * n + k] * m[j * n + k] * d[k];
+            }
+            if djj.abs() < EPSILON {
+                djj = if djj < 0f64 { -EPSILON } else { EPSILON };
+            }
+            d[j] = djj;
+            for i in j + 1..n {
+                let mut lij = m[i * n + j];
+                for k in 0..j {
+                    lij -= m[i * n + k] * m[j * n + k] * d[k];
+                }
+                m[i * n + j] = lij / djj;
+            }
+        }
+        Self { l: m, d }
+    }
+    fn solve_inplace_vec(&self, y: &mut [f32]) {
+        let n = self.d.len();
+        let l = &self.l;
+        let mut z: Vec<f64> = y.iter().map(|v| *v as f64).collect();
+        for i in 0..n {
+            for k in 0..i {
+                z[i] -= l[i * n + k] * z[k];
+            }
+        }
+        z.iter_mut().zip(&self.d).for_each(|(zi, di)| *zi /= di);
+        for i in (0..n).rev() {
+            for k in i + 1..n {
+                z[i] -= l[k * n + i] * z[k];
+            }
+        }
+        y.iter_mut().zip(&z).for_each(|(yi, zi)| *yi = *zi as f32);
+    }
+}
+
+// largest step in [0, 1] keeping x + alpha dx nonnegative
+fn max_step(x: &[f32], dx: &[f32]) -> f32 {
+    x.iter()
+        .zip(dx)
+        .filter(|&(_, d)| *d < 0f32)
+        .map(|(x, d)| -x / d)
+        .fold(1f32, f32::min)
+}
+
+impl InteriorPoint {
+    pub fn new(tolerance: f32, max_iters: usize) -> Self {
+        Self {
+            tolerance,
+            max_iters,
+            newton: NewtonSolve::NormalEquations,
+        }
+    }
+    pub fn with_newton(mut self, newton: NewtonSolve) -> Self {
+        self.newton = newton;
+        self
+    }
+    pub fn solve(&self, lp: &LinearProgram) -> LpSolution {
+        let form = lp.standard_form();
+        let (status, x, y, iterations) = self.run(&form, &form.c, None);
+        lp.report(&form, status, &x, &y, None, iterations)
+    }
+    /// solve_qp
+    /// min or max 1/2 x'Qx + c'x over the constraints of lp, Q ~ n x n must be positive
+    /// semidefinite when minimizing and negative semidefinite when maximizing
+    pub fn solve_qp(&self, lp: &LinearProgram, q: &NdArray) -> LpSolution {
+        let form = lp.standard_form();
+        let sign = match lp.sense {
+            Sense::Minimize => 1f32,
+            Sense::Maximize => -1f32,
+        };
+        // x = o + Tz gives sign (1/2 z'T'QTz + (T'Qo)'z) on top of the linear costs
+        let (t, offset) = form.embedding();
+        let tt = t.transpose();
+        let mut q_std = matrix_mult(&tt, &matrix_mult(q, &t));
+        q_std.data.iter_mut().for_each(|v| *v *= sign);
+        let mut qo = vec![0f32; offset.len()];
+        q.apply(&offset, &mut qo);
+        let mut c = vec![0f32; form.c.len()];
+        t.apply_transpose(&qo, &mut c);
+        c.iter_mut()
+            .zip(&form.c)
+            .for_each(|(ci, fc)| *ci = fc + sign * *ci);
+        let (status, x, y, iterations) = self.run(&form, &c, Some(&q_std));
+        lp.report(&form, status, &x, &y, Some(q), iterations)
+    }
+    fn factor(
+        newton: NewtonSolve,
+        a: &NdArray,
+        q: Option<&NdArray>,
+        x: &[f32],
+        s: &[f32],
+    ) -> NewtonFactor {
+        let (m, n) = (a.dims[0], a.dims[1]);
+        // D = Q + X^-1 S + rI, the primal regularization keeps D^-1 bounded as x and s part ways
+        let diagonal: Vec<f32> = x
+            .iter()
+            .zip(s)
+            .map(|(x, s)| s / x + REGULARIZATION)
+            .collect();
+        match newton {
+            NewtonSolve::NormalEquations => {
+                let (d_inv, d, dat) = if let Some(q) = q {
+                    let mut d = q.clone();
+                    for (k, v) in diagonal.iter().enumerate() {
+                        d.data[k * n + k] += v;
+                    }
+                    let d = Cholesky::new(d);
+                    let mut dat = a.transpose();
+                    let mut column = vec![0f32; n];
+                    for i in 0..m {
+                        for k in 0..n {
+                            column[k] = dat.data[k * m + i];
+                        }
+                        d.solve_inplace_vec(&mut column);
+                        for k in 0..n {
+                            dat.data[k * m + i] = column[k];
+                        }
+                    }
+                    (None, Some(d), dat)
+                } else {
+                    let d_inv: Vec<f32> = diagonal.iter().map(|v| 1f32 / v).collect();
+                    let mut dat = a.transpose();
+                    for (row, di) in dat.data.chunks_mut(m.max(1)).zip(&d_inv) {
+                        row.iter_mut().for_each(|v| *v *= di);
+                    }
+                    (Some(d_inv), None, dat)
+                };
+                let normal = Cholesky::new(matrix_mult(a, &dat));
+                NewtonFactor::Normal {
+                    d_inv,
+                    d,
+                    dat,
+                    m: normal,
+                }
+            }
+            NewtonSolve::Augmented => {
+                let size = n + m;
+                let mut k = NdArray::new(vec![size, size], vec![0f32; size * size]);
+                for i in 0..n {
+                    for j in 0..n {
+                        if let Some(q) = q {
+                            k.data[i * size + j] = q.data[i * n + j];
+                        }
+                    }
+                    k.data[i * size + i] += diagonal[i];
+                }
+                for i in 0..m {
+                    for j in 0..n {
+                        let v = a.data[i * n + j];
+                        k.data[(n + i) * size + j] = v;
+                        k.data[j * size + n + i] = v;
+                    }
+                    k.data[(n + i) * size + n + i] = -REGULARIZATION;
+                }
+                NewtonFactor::Augmented(Ldl::new(k))
+            }
+        }
+    }
+}
+
+// newton system of one iterate, A dx = rp, -Q dx + A'dy + ds = rd, S dx + X ds = rxs
+struct NewtonSystem<'a> {
+    factor: NewtonFactor,
+    a: &'a NdArray,
+    q: Option<&'a NdArray>,
+    x: &'a [f32],
+    s: &'a [f32],
+}
+
+impl NewtonSystem<'_> {
+    fn solve(&self, rp: &[f32], rd: &[f32], rxs: &[f32]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let (a, x, s) = (self.a, self.x, self.s);
+        let (m, n) = (a.dims[0], a.dims[1]);
+        // D dx - A'dy = X^-1 rxs - rd = r1
+        let r1: Vec<f32> = (0..n).map(|k| rxs[k] / x[k] - rd[k]).collect();
+        let (dx, dy) = match &self.factor {
+            NewtonFactor::Normal {
+                d_inv,
+                d,
+                dat,
+                m: normal,
+            } => {
+                // A D^-1 A' dy = rp - A D^-1 r1, dx = D^-1 (r1 + A'dy)
+                let mut d_inv_r1 = r1.clone();
+                match (d_inv, d) {
+                    (Some(d_inv), _) => d_inv_r1.iter_mut().zip(d_inv).for_each(|(v, di)| *v *= di),
+                    (None, Some(d)) => d.solve_inplace_vec(&mut d_inv_r1),
+                    (None, None) => unreachable!(),
+                }
+                let mut dy = vec![0f32; m];
+                a.apply(&d_inv_r1, &mut dy);
+                dy.iter_mut().zip(rp).for_each(|(v, r)| *v = r - *v);
+                normal.solve_inplace_vec(&mut dy);
+                let mut dx = vec![0f32; n];
+                dat.apply(&dy, &mut dx);
+                dx.iter_mut().zip(&d_inv_r1).for_each(|(v, w)| *v += w);
+                (dx, dy)
+            }
+            NewtonFactor::Augmented(ldl) => {
+                // [D A'; A 0] [dx; -dy] = [r1; rp]
+                let mut rhs = r1;
+                rhs.extend_from_slice(rp);
+                ldl.solve_inplace_vec(&mut rhs);
+                let dy = rhs[n..].iter().map(|v| -v).collect();
+                rhs.truncate(n);
+                (rhs, dy)
+            }
+        };
+        let ds = (0..n).map(|k| (rxs[k] - s[k] * dx[k]) / x[k]).collect();
+        (dx, dy, ds)
+    }
+    // mehrotra predictor corrector direction
+    fn direction(&self, rp: &[f32], rd: &[f32]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let (x, s) = (self.x, self.s);
+        let n = x.len();
+        let mu = dot_product(x, s) / n as f32;
+
+        // predictor, affine scaling direction
+        let rxs: Vec<f32> = x.iter().zip(s).map(|(x, s)| -x * s).collect();
+        let (dx, _, ds) = self.step(rp, rd, &rxs);
+        let (mut alpha_p, mut alpha_d) = (max_step(x, &dx), max_step(s, &ds));
+        if self.q.is_some() {
+            alpha_p = alpha_p.min(alpha_d);
+            alpha_d = alpha_p;
+        }
+        let mu_affine = (0..n)
+            .map(|k| (x[k] + alpha_p * dx[k]) * (s[k] + alpha_d * ds[k]))
+            .sum::<f32>()
+            / n as f32;
+        let sigma = (mu_affine / mu).powi(3).min(1f32);
+
+        // corrector, centering plus the second order term of the predictor
+        let rxs: Vec<f32> = (0..n)
+            .map(|k| sigma * mu - x[k] * s[k] - dx[k] * ds[k])
+            .collect();
+        self.step(rp, rd, &rxs)
+    }
+    // solve followed by iterative refinement on the unreduced system
+    fn step(&self, rp: &[f32], rd: &[f32], rxs: &[f32]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let (a, x, s) = (self.a, self.x, self.s);
+        let (m, n) = (a.dims[0], a.dims[1]);
+        let (mut dx, mut dy, mut ds) = self.solve(rp, rd, rxs);
+        for _ in 0..REFINEMENT {
+            let mut ep = vec![0f32; m];
+            a.apply(&dx, &mut ep);
+            ep.iter_mut().zip(rp).for_each(|(e, r)| *e = r - *e);
+            let mut ed = vec![0f32; n];
+            a.apply_transpose(&dy, &mut ed);
+            let mut qdx = vec![0f32; n];
+            if let Some(q) = self.q {
+                q.apply(&dx, &mut qdx);
+            }
+            for k in 0..n {
+                ed[k] = rd[k] - (ed[k] - qdx[k] + ds[k]);
+            }
+            let exs: Vec<f32> = (0..n)
+                .map(|k| rxs[k] - (s[k] * dx[k] + x[k] * ds[k]))
+                .collect();
+            let (cx, cy, cs) = self.solve(&ep, &ed, &exs);
+            dx.iter_mut().zip(&cx).for_each(|(v, c)| *v += c);
+            dy.iter_mut().zip(&cy).for_each(|(v, c)| *v += c);
+            ds.iter_mut().zip(&cs).for_each(|(v, c)| *v += c);
+        }
+        (dx, dy, ds)
+    }
+}
+
+impl InteriorPoint {
+    // mehrotra's heuristic, least squares x and (y, s) shifted into the positive orthant
+    fn starting_point(&self, a: &NdArray, b: &[f32], c: &[f32]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let (m, n) = (a.dims[0], a.dims[1]);
+        let aat = Cholesky::new(matrix_mult(a, &a.transpose()));
+        let mut w = b.to_vec();
+        aat.solve_inplace_vec(&mut w);
+        let mut x = vec![0f32; n];
+        a.apply_transpose(&w, &mut x);
+        let mut y = vec![0f32; m];
+        a.apply(c, &mut y);
+        aat.solve_inplace_vec(&mut y);
+        let mut s = vec![0f32; n];
+        a.apply_transpose(&y, &mut s);
+        s.iter_mut().zip(c).for_each(|(s, c)| *s = c - *s);
+        let shift_x = (-1.5 * x.iter().fold(f32::INFINITY, |acc, &v| acc.min(v))).max(0f32);
+        let shift_s = (-1.5 * s.iter().fold(f32::INFINITY, |acc, &v| acc.min(v))).max(0f32);
+        x.iter_mut().for_each(|v| *v += shift_x);
+        s.iter_mut().for_each(|v| *v += shift_s);
+        let xs = dot_product(&x, &s);
+        let (sum_x, sum_s) = (x.iter().sum::<f32>(), s.iter().sum::<f32>());
+        let shift_x = if sum_s > 0f32 { 0.5 * xs / sum_s } else { 0f32 };
+        let shift_s = if sum_x > 0f32 { 0.5 * xs / sum_x } else { 0f32 };
+        // keep the start strictly interior when the shifts vanish
+        x.iter_mut().for_each(|v| *v = (*v + shift_x).max(1e-2));
+        s.iter_mut().for_each(|v| *v = (*v + shift_s).max(1e-2));
+        (x, y, s)
+    }
+    // returns the status, x, y and the number of iterations
+    fn run(
+        &self,
+        form: &StandardForm,
+        c: &[f32],
+        q: Option<&NdArray>,
+    ) -> (LpStatus, Vec<f32>, Vec<f32>, usize) {
+        let (a, b) = (&form.a, &form.b);
+        let pairs = form.free_pairs();
+        let (m, n) = (a.dims[0], a.dims[1]);
+        let (mut x, mut y, mut s) = self.starting_point(a, b, c);
+        let scale_b = 1f32 + norm_inf(b);
+        let scale_c = 1f32 + norm_inf(c);
+        // best iterate by the largest relative residual, rounding limits how far f32 gets
+        let (mut best, mut best_merit, mut best_iteration) =
+            ((x.clone(), y.clone()), f32::INFINITY, 0);
+        for iteration in 0..self.max_iters {
+            // rp = b - Ax, rd = c + Qx - A'y - s
+            let mut rp = vec![0f32; m];
+            a.apply(&x, &mut rp);
+            rp.iter_mut().zip(b).for_each(|(r, b)| *r = b - *r);
+            let mut rd = vec![0f32; n];
+            a.apply_transpose(&y, &mut rd);
+            let mut qx = vec![0f32; n];
+            if let Some(q) = q {
+                q.apply(&x, &mut qx);
+            }
+            for k in 0..n {
+                rd[k] = c[k] + qx[k] - rd[k] - s[k];
+            }
+            let gap = dot_product(&x, &s);
+            let objective = dot_product(c, &x) + 0.5 * dot_product(&x, &qx);
+            let merit = (norm_inf(&rp) / scale_b)
+                .max(norm_inf(&rd) / scale_c)
+                .max(gap / (1f32 + objective.abs()));
+            let finite = gap.is_finite() && rd.iter().all(|v| v.is_finite());
+            if !finite || iteration >= best_iteration + STALL {
+                let (x, y) = best;
+                return (LpStatus::Stalled, x, y, iteration);
+            }
+            if merit <= self.tolerance {
+                return (LpStatus::Optimal, x, y, iteration);
+            }
+            if merit < best_merit {
+                (best, best_merit, best_iteration) = ((x.clone(), y.clone()), merit, iteration);
+            }
+            if norm_inf(&x) > DIVERGENCE * scale_b {
+                return (LpStatus::Unbounded, x, y, iteration);
+            }
+            if norm_inf(&y).max(norm_inf(&s)) > DIVERGENCE * scale_c {
+                return (LpStatus::Infeasible, x, y, iteration);
+            }
+            let mut system = NewtonSystem {
+                factor: Self::factor(self.newton, a, q, &x, &s),
+                a,
+                q,
+                x: &x,
+                s: &s,
+            };
+            let (mut dx, mut dy, mut ds) = system.direction(&rp, &rd);
+            // A D^-1 A' loses definiteness in f32 once x and s part far enough, the quasi
+            // definite augmented system still factors there, fall back once the step misses
+            // A dx = rp by more than rp itself
+            let mut adx = vec![0f32; m];
+            a.apply(&dx, &mut adx);
+            adx.iter_mut().zip(&rp).for_each(|(v, r)| *v -= r);
+            let broken = dx.iter().chain(&dy).chain(&ds).any(|v| !v.is_finite())
+                || norm_inf(&adx) > norm_inf(&rp) + self.tolerance * scale_b;
+            if broken && self.newton == NewtonSolve::NormalEquations {
+                system.factor = Self::factor(NewtonSolve::Augmented, a, q, &x, &s);
+                (dx, dy, ds) = system.direction(&rp, &rd);
+            }
+            let (mut alpha_p, mut alpha_d) = (
+                (STEP_FRACTION * max_step(&x, &dx)).min(1f32),
+                (STEP_FRACTION * max_step(&s, &ds)).min(1f32),
+            );
+            if q.is_some() {
+                alpha_p = alpha_p.min(alpha_d);
+                alpha_d = alpha_p;
+            }
+            x.iter_mut().zip(&dx).for_each(|(v, d)| *v += alpha_p * d);
+            y.iter_mut().zip(&dy).for_each(|(v, d)| *v += alpha_d * d);
+            s.iter_mut().zip(&ds).for_each(|(v, d)| *v += alpha_d * d);
+            // both halves of a split free variable drift upwards and blow up D^-1, pull the
+            // common part back without moving the free variable
+            for &(pos, neg) in &pairs {
+                let excess = x[pos].min(x[neg]) - FREE_SPLIT;
+                if excess > 0f32 {
+                    x[pos] -= excess;
+                    x[neg] -= excess;
+                }
+            }
+        }
+        let (x, y) = best;
+        (LpStatus::IterationLimit, x, y, self.max_iters)
+    }
+}
+
+#[cfg(test)]
+mod test_interior_point {
+    use super::*;
+    use crate::random::generation::generate_seeded_matrix;
+    use crate::solver::linear::Relation;
+
+    const SEED: u64 = 46;
+
+    #[test]
+    fn test_matches_simplex() {
+        let (m, n) = (25, 15);
+        let a = generate_seeded_matrix(m, n, SEED);
+        let mut lp = LinearProgram::new(
+            Sense::Minimize,
+            (0..n).map(|j| 1f32 + (j % 3) as f32).collect(),
+        );
+        for (i, row) in a.data.chunks(n).enumerate() {
+            let row: Vec<f32> = row.iter().map(|v| v.abs() + 0.1).collect();
+            lp = lp.with_constraint(row, Relation::GreaterEqual, 1f32 + (i % 4) as f32);
+        }
+        // -x1 + sum of the others <= 10 keeps the free x1 from running off to -inf
+        let row = (0..n).map(|j| if j == 1 { -1f32 } else { 1f32 }).collect();
+        let lp = lp
+            .with_constraint(row, Relation::LessEqual, 10f32)
+            .with_bounds(0, 0f32, 0.5f32)
+            .with_free(1);
+        let simplex = lp.solve();
+        assert_eq!(simplex.status, LpStatus::Optimal);
+        for newton in [NewtonSolve::NormalEquations, NewtonSolve::Augmented] {
+            let solution = InteriorPoint::new(1e-6, 60).with_newton(newton).solve(&lp);
+            assert_eq!(solution.status, LpStatus::Optimal);
+            assert!((solution.objective - simplex.objective).abs() < 1e-3 * simplex.objective);
+            // b'y plus the upper bound of x0 priced by its reduced cost
+            let dual_objective: f32 = solution
+                .duals
+                .iter()
+                .zip(&lp.b)
+                .map(|(y, b)| y * b)
+                .sum::<f32>()
+                + 0.5 * solution.reduced_costs[0].min(0f32);
+            assert!((dual_objective - simplex.objective).abs() < 1e-3 * simplex.objective);
+        }
+    }
+    #[test]
+    fn test_maximize_duals() {
+        let lp = LinearProgram::new(Sense::Maximize, vec![3f32, 5f32])
+            .with_constraint(vec![1f32, 0f32], Relation::LessEqual, 4f32)
+            .with_constraint(vec![0f32, 2f32], Relation::LessEqual, 12f32)
+            .with_constraint(vec![3f32, 2f32], Relation::LessEqual, 18f32);
+        let solution = InteriorPoint::new(1e-6, 50).solve(&lp);
+        assert_eq!(solution.status, LpStatus::Optimal);
+        for (x, y) in solution.x.iter().zip([2f32, 6f32]) {
+            assert!((x - y).abs() < 1e-3);
+        }
+        for (x, y) in solution.duals.iter().zip([0f32, 1.5f32, 1f32]) {
+            assert!((x - y).abs() < 1e-3);
+        }
+        let limited = InteriorPoint::new(1e-6, 2).solve(&lp);
+        assert_eq!(limited.status, LpStatus::IterationLimit);
+        assert_eq!(limited.iterations, 2);
+        // rounding keeps the residuals above a zero tolerance
+        let stalled = InteriorPoint::new(0f32, 500).solve(&lp);
+        assert_eq!(stalled.status, LpStatus::Stalled);
+        assert!(stalled.iterations > solution.iterations && stalled.iterations < 500);
+    }
+    #[test]
+    fn test_simplex_projection_qp() {
+        // min 1/2 ||x - p||^2 s.t. sum(x) = 1, x >= 0 is the projection onto the simplex
+        let p = [0.8f32, 0.6f32, -0.2f32, 0.1f32];
+        let lp = LinearProgram::new(Sense::Minimize, p.iter().map(|v| -v).collect())
+            .with_constraint(vec![1f32; 4], Relation::Equal, 1f32);
+        let mut q = NdArray::new(vec![4, 4], vec![0f32; 16]);
+        (0..4).for_each(|i| q.data[i * 5] = 1f32);
+        for newton in [NewtonSolve::NormalEquations, NewtonSolve::Augmented] {
+            let solution = InteriorPoint::new(1e-6, 50)
+                .with_newton(newton)
+                .solve_qp(&lp, &q);
+            assert_eq!(solution.status, LpStatus::Optimal);
+            for (x, y) in solution.x.iter().zip([0.6f32, 0.4f32, 0f32, 0f32]) {
+                assert!((x - y).abs() < 1e-3, "{:?}", solution.x);
+            }
+            // x - p = y - reduced costs, with the threshold y = -0.2
+            assert!((solution.duals[0] + 0.2f32).abs() < 1e-3);
+        }
+    }
+    #[test]
+    fn test_bounded_qp() {
+        // max -(x0 - 3)^2 - (x1 + 2)^2 = -x0^2 + 6 x0 - x1^2 - 4 x1 + const with x0 <= 1,
+        // x1 free and x0 + x1 >= -4
+        let lp = LinearProgram::new(Sense::Maximize, vec![6f32, -4f32])
+            .with_constraint(vec![1f32, 1f32], Relation::GreaterEqual, -4f32)
+            .with_bounds(0, f32::NEG_INFINITY, 1f32)
+            .with_free(1);
+        let q = NdArray::new(vec![2, 2], vec![-2f32, 0f32, 0f32, -2f32]);
+        let solution = InteriorPoint::new(1e-6, 50).solve_qp(&lp, &q);
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert!((solution.x[0] - 1f32).abs() < 1e-3, "{:?}", solution.x);
+        assert!((solution.x[1] + 2f32).abs() < 1e-3, "{:?}", solution.x);
+    }
+}
@@ -0,0 +1,428 @@
+use crate::algebra::vector::{axpy, dot_product, magnitude};
+use crate::solver::preconditioner::Preconditioner;
+use crate::structure::operator::LinearOperator;
+
+/// KrylovResult
+/// outcome of an iterative solve of Ax = b
+///
+/// * x: final iterate
+/// * iterations: matrix vector products with A, one per step
+/// * converged: relative residual reached the tolerance
+/// * history: relative residual ||r|| / ||b|| at the start and after every step
+pub struct KrylovResult {
+    pub x: Vec<f32>,
+    pub iterations: usize,
+    pub converged: bool,
+    pub history: Vec<f32>,
+}
+
+/// Krylov
+/// settings shared by the krylov solvers
+///
+/// * tolerance: stop once ||r|| / ||b|| <= tolerance
+/// * max_iters: maximum number of steps
+/// * restart: gmres subspace dimension before a restart
+/// * preconditioner: M ~ A^-1, symmetric positive definite for cg and minres, applied on the right for gmres and bicgstab
+/// * callback: called with (iteration, relative residual) after every step, returning false stops the solve
+pub struct Krylov<'a> {
+    pub tolerance: f32,
+    pub max_iters: usize,
+    pub restart: usize,
+    pub preconditioner: Option<&'a dyn Preconditioner>,
+    pub callback: Option<Box<dyn FnMut(usize, f32) -> bool + 'a>>,
+}
+
+// r = b - Ax
+fn residual(a: &dyn LinearOperator, b: &[f32], x: &[f32]) -> Vec<f32> {
+    let mut r = vec![0f32; b.len()];
+    a.apply(x, &mut r);
+    r.iter_mut().zip(b).for_each(|(ri, bi)| *ri = bi - *ri);
+    r
+}
+
+impl<'a> Krylov<'a> {
+    pub fn new(tolerance: f32, max_iters: usize) -> Self {
+        Self {
+            tolerance,
+            max_iters,
+            restart: 30,
+            preconditioner: None,
+            callback: None,
+        }
+    }
+    pub fn with_restart(mut self, restart: usize) -> Self {
+        self.restart = restart.max(1);
+        self
+    }
+    pub fn with_preconditioner(mut self, preconditioner: &'a dyn Preconditioner) -> Self {
+        self.preconditioner = Some(preconditioner);
+        self
+    }
+    pub fn with_callback(mut self, callback: impl FnMut(usize, f32) -> bool + 'a) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+    // z = Mr
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        match self.preconditioner {
+            Some(m) => m.precondition(r, z),
+            None => z.copy_from_slice(r),
+        }
+    }
+    // records the step, returns whether to keep iterating
+    fn record(&mut self, history: &mut Vec<f32>, iteration: usize, relative: f32) -> bool {
+        history.push(relative);
+        match self.callback.as_mut() {
+            Some(callback) => callback(iteration, relative),
+            None => true,
+        }
+    }
+    fn start(a: &dyn LinearOperator, b: &[f32], x0: Option<&[f32]>) -> (Vec<f32>, Vec<f32>, f32) {
+        debug_assert_eq!(a.dims(), (b.len(), b.len()));
+        let x = x0.map_or_else(|| vec![0f32; b.len()], |x0| x0.to_vec());
+        let r = residual(a, b, &x);
+        (x, r, magnitude(b).max(f32::MIN_POSITIVE))
+    }
+
+    /// cg
+    /// preconditioned conjugate gradient for symmetric positive definite A
+    ///
+    /// * a: operator ~ n x n
+    /// * b: right hand side
+    /// * x0: initial guess, zero if none
+    pub fn cg(&mut self, a: &dyn LinearOperator, b: &[f32], x0: Option<&[f32]>) -> KrylovResult {
+        let n = b.len();
+        let (mut x, mut r, bnorm) = Self::start(a, b, x0);
+        let mut history = vec![magnitude(&r) / bnorm];
+        let mut z = vec![0f32; n];
+        self.precondition(&r, &mut z);
+        let mut p = z.clone();
+        let mut q = vec![0f32; n];
+        let mut rz = dot_product(&r, &z);
+        let mut iterations = 0;
+        while history[iterations] > self.tolerance && iterations < self.max_iters {
+            a.apply(&p, &mut q);
+            let alpha = rz / dot_product(&p, &q);
+            axpy(alpha, &p, &mut x);
+            axpy(-alpha, &q, &mut r);
+            iterations += 1;
+            if !self.record(&mut history, iterations, magnitude(&r) / bnorm) {
+                break;
+            }
+            self.precondition(&r, &mut z);
+            let rz_next = dot_product(&r, &z);
+            let beta = rz_next / rz;
+            rz = rz_next;
+            p.iter_mut()
+                .zip(&z)
+                .for_each(|(pi, zi)| *pi = zi + beta * *pi);
+        }
+        KrylovResult {
+            x,
+            iterations,
+            converged: history[history.len() - 1] <= self.tolerance,
+            history,
+        }
+    }
+
+    /// minres
+    /// paige saunders minimum residual for symmetric, possibly indefinite A,
+    /// the history tracks the recurrence estimate of the residual in the preconditioner norm
+    ///
+    /// * a: symmetric operator ~ n x n
+    /// * b: right hand side
+    /// * x0: initial guess, zero if none
+    pub fn minres(
+        &mut self,
+        a: &dyn LinearOperator,
+        b: &[f32],
+        x0: Option<&[f32]>,
+    ) -> KrylovResult {
+        let n = b.len();
+        let (mut x, mut r1, _) = Self::start(a, b, x0);
+        let mut y = vec![0f32; n];
+        self.precondition(b, &mut y);
+        let bnorm = dot_product(b, &y).sqrt().max(f32::MIN_POSITIVE);
+        self.precondition(&r1, &mut y);
+        let mut beta = dot_product(&r1, &y).sqrt();
+        let mut history = vec![beta / bnorm];
+        let mut r2 = r1.clone();
+        let (mut w, mut w1, mut w2) = (vec![0f32; n], vec![0f32; n], vec![0f32; n]);
+        let mut v = vec![0f32; n];
+        let (mut oldb, mut dbar, mut epsln, mut phibar) = (0f32, 0f32, 0f32, beta);
+        let (mut cs, mut sn) = (-1f32, 0f32);
+        let mut iterations = 0;
+        while history[iterations] > self.tolerance && iterations < self.max_iters && beta > 0f32 {
+            // lanczos step, v = y / beta
+            v.iter_mut().zip(&y).for_each(|(vi, yi)| *vi = yi / beta);
+            a.apply(&v, &mut y);
+            if iterations > 0 {
+                axpy(-beta / oldb, &r1, &mut y);
+            }
+            let alpha = dot_product(&v, &y);
+            axpy(-alpha / beta, &r2, &mut y);
+            std::mem::swap(&mut r1, &mut r2);
+            r2.copy_from_slice(&y);
+            self.precondition(&r2, &mut y);
+            oldb = beta;
+            beta = dot_product(&r2, &y).max(0f32).sqrt();
+            // previous rotation on the new column of the tridiagonal, then the next rotation
+            let oldeps = epsln;
+            let delta = cs * dbar + sn * alpha;
+            let gbar = sn * dbar - cs * alpha;
+            epsln = sn * beta;
+            dbar = -cs * beta;
+            let gamma = gbar.hypot(beta).max(f32::EPSILON);
+            cs = gbar / gamma;
+            sn = beta / gamma;
+            let phi = cs * phibar;
+            phibar *= sn;
+            // w = (v - oldeps w1 - delta w2) / gamma
+            std::mem::swap(&mut w1, &mut w2);
+            std::mem::swap(&mut w2, &mut w);
+            for i in 0..n {
+                w[i] = (v[i] - oldeps * w1[i] - delta * w2[i]) / gamma;
+            }
+            axpy(phi, &w, &mut x);
+            iterations += 1;
+            if !self.record(&mut history, iterations, phibar.abs() / bnorm) {
+                break;
+            }
+        }
+        KrylovResult {
+            x,
+            iterations,
+            converged: history[history.len() - 1] <= self.tolerance,
+            history,
+        }
+    }
+
+    /// gmres
+    /// restarted gmres with modified gram schmidt arnoldi and givens rotations on the hessenberg matrix
+    ///
+    /// * a: operator ~ n x n
+    /// * b: right hand side
+    /// * x0: initial guess, zero if none
+    pub fn gmres(&mut self, a: &dyn LinearOperator, b: &[f32], x0: Option<&[f32]>) -> KrylovResult {
+        let (n, m) = (b.len(), self.restart);
+        let (mut x, mut r, bnorm) = Self::start(a, b, x0);
+        let mut history = vec![magnitude(&r) / bnorm];
+        let mut iterations = 0;
+        let mut stopped = false;
+        // h ~ (m + 1) x m, column j stored at j * (m + 1)
+        let mut h = vec![0f32; (m + 1) * m];
+        let (mut cs, mut sn, mut g) = (vec![0f32; m], vec![0f32; m], vec![0f32; m + 1]);
+        while !stopped && history[iterations] > self.tolerance && iterations < self.max_iters {
+            let beta = magnitude(&r);
+            let mut basis: Vec<Vec<f32>> = vec![r.iter().map(|v| v / beta).collect()];
+            let mut directions: Vec<Vec<f32>> = Vec::with_capacity(m);
+            g.fill(0f32);
+            g[0] = beta;
+            let mut k = 0;
+            while k < m && iterations < self.max_iters {
+                let mut z = vec![0f32; n];
+                self.precondition(&basis[k], &mut z);
+                let mut w = vec![0f32; n];
+                a.apply(&z, &mut w);
+                directions.push(z);
+                let col = &mut h[k * (m + 1)..(k + 1) * (m + 1)];
+                for (i, vi) in basis.iter().enumerate() {
+                    col[i] = dot_product(&w, vi);
+                    axpy(-col[i], vi, &mut w);
+                }
+                col[k + 1] = magnitude(&w);
+                let next = col[k + 1];
+                for i in 0..k {
+                    let (p, q) = (col[i], col[i + 1]);
+                    col[i] = cs[i] * p + sn[i] * q;
+                    col[i + 1] = -sn[i] * p + cs[i] * q;
+                }
+                let rho = col[k].hypot(col[k + 1]).max(f32::MIN_POSITIVE);
+                cs[k] = col[k] / rho;
+                sn[k] = col[k + 1] / rho;
+                col[k] = rho;
+                col[k + 1] = 0f32;
+                g[k + 1] = -sn[k] * g[k];
+                g[k] *= cs[k];
+                k += 1;
+                iterations += 1;
+                let relative = g[k].abs() / bnorm;
+                if !self.record(&mut history, iterations, relative) {
+                    stopped = true;
+                    break;
+                }
+                // lucky breakdown means the krylov space holds the solution
+                if relative <= self.tolerance || next == 0f32 {
+                    break;
+                }
+                basis.push(w.iter().map(|v| v / next).collect());
+            }
+            // back substitution on the triangularized hessenberg matrix
+            let mut y = g[..k].to_vec();
+            for i in (0..k).rev() {
+                for j in i + 1..k {
+                    y[i] -= h[j * (m + 1) + i] * y[j];
+                }
+                y[i] /= h[i * (m + 1) + i];
+            }
+            for (yi, z) in y.iter().zip(&directions) {
+                axpy(*yi, z, &mut x);
+            }
+            r = residual(a, b, &x);
+            // the true residual replaces the recurrence estimate at every restart
+            let last = history.len() - 1;
+            history[last] = magnitude(&r) / bnorm;
+        }
+        KrylovResult {
+            x,
+            iterations,
+            converged: history[history.len() - 1] <= self.tolerance,
+            history,
+        }
+    }
+
+    /// bicgstab
+    /// van der vorst biconjugate gradient stabilized for general A
+    ///
+    /// * a: operator ~ n x n
+    /// * b: right hand side
+    /// * x0: initial guess, zero if none
+    pub fn bicgstab(
+        &mut self,
+        a: &dyn LinearOperator,
+        b: &[f32],
+        x0: Option<&[f32]>,
+    ) -> KrylovResult {
+        let n = b.len();
+        let (mut x, mut r, bnorm) = Self::start(a, b, x0);
+        let mut history = vec![magnitude(&r) / bnorm];
+        let shadow = r.clone();
+        let (mut rho, mut alpha, mut omega) = (1f32, 1f32, 1f32);
+        let (mut p, mut v) = (vec![0f32; n], vec![0f32; n]);
+        let (mut phat, mut shat, mut t) = (vec![0f32; n], vec![0f32; n], vec![0f32; n]);
+        let mut iterations = 0;
+        while history[iterations] > self.tolerance && iterations < self.max_iters {
+            let rho_next = dot_product(&shadow, &r);
+            // breakdown of the underlying bicg recurrence
+            if rho_next == 0f32 || omega == 0f32 {
+                break;
+            }
+            let beta = (rho_next / rho) * (alpha / omega);
+            rho = rho_next;
+            for i in 0..n {
+                p[i] = r[i] + beta * (p[i] - omega * v[i]);
+            }
+            self.precondition(&p, &mut phat);
+            a.apply(&phat, &mut v);
+            alpha = rho / dot_product(&shadow, &v);
+            // s = r - alpha v overwrites r
+            axpy(-alpha, &v, &mut r);
+            axpy(alpha, &phat, &mut x);
+            iterations += 1;
+            let relative = magnitude(&r) / bnorm;
+            if relative <= self.tolerance {
+                self.record(&mut history, iterations, relative);
+                break;
+            }
+            self.precondition(&r, &mut shat);
+            a.apply(&shat, &mut t);
+            omega = dot_product(&t, &r) / dot_product(&t, &t).max(f32::MIN_POSITIVE);
+            axpy(omega, &shat, &mut x);
+            axpy(-omega, &t, &mut r);
+            if !self.record(&mut history, iterations, magnitude(&r) / bnorm) {
+                break;
+            }
+        }
+        KrylovResult {
+            x,
+            iterations,
+            converged: history[history.len() - 1] <= self.tolerance,
+            history,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_krylov {
+    use super::*;
+    use crate::algebra::ndmethods::matrix_mult;
+    use crate::random::generation::{
+        generate_random_matrix, generate_random_symetric, generate_random_vector,
+    };
+    use crate::structure::ndarray::NdArray;
+    use crate::structure::operator::FunctionOperator;
+
+    fn relative_residual(a: &dyn LinearOperator, b: &[f32], x: &[f32]) -> f32 {
+        magnitude(&residual(a, b, x)) / magnitude(b)
+    }
+
+    #[test]
+    fn test_cg_laplacian() {
+        // matrix free 1d laplacian with a jacobi preconditioner
+        let n = 500;
+        let laplacian = FunctionOperator::symmetric(n, |x: &[f32], y: &mut [f32]| {
+            for i in 0..x.len() {
+                let left = if i > 0 { x[i - 1] } else { 0f32 };
+                let right = if i + 1 < x.len() { x[i + 1] } else { 0f32 };
+                y[i] = 2.5 * x[i] - left - right;
+            }
+        });
+        let jacobi = FunctionOperator::symmetric(n, |x: &[f32], y: &mut [f32]| {
+            y.iter_mut().zip(x).for_each(|(yi, xi)| *yi = xi / 2.5);
+        });
+        let b = generate_random_vector(n);
+        let result = Krylov::new(1e-5, 200)
+            .with_preconditioner(&jacobi)
+            .cg(&laplacian, &b, None);
+        assert!(result.converged);
+        assert_eq!(result.history.len(), result.iterations + 1);
+        assert!(relative_residual(&laplacian, &b, &result.x) < 1e-4);
+    }
+    #[test]
+    fn test_minres_indefinite() {
+        let n = 12;
+        let a = generate_random_symetric(n);
+        let b = generate_random_vector(n);
+        let result = Krylov::new(1e-5, 10 * n).minres(&a, &b, None);
+        assert!(result.converged);
+        assert!(relative_residual(&a, &b, &result.x) < 1e-3);
+    }
+    #[test]
+    fn test_nonsymmetric_solvers() {
+        let n = 20;
+        // diagonally dominant nonsymmetric matrix
+        let mut a = generate_random_matrix(n, n);
+        a.data.iter_mut().for_each(|v| *v *= 0.1);
+        (0..n).for_each(|i| a.data[i * n + i] += 2f32);
+        let b = generate_random_vector(n);
+        let gmres = Krylov::new(1e-5, 200).with_restart(5).gmres(&a, &b, None);
+        let bicgstab = Krylov::new(1e-5, 200).bicgstab(&a, &b, None);
+        for result in [gmres, bicgstab] {
+            assert!(result.converged);
+            assert!(relative_residual(&a, &b, &result.x) < 1e-4);
+        }
+        // the callback can stop the solve early
+        let mut seen = Vec::new();
+        let stopped = Krylov::new(1e-7, 200)
+            .with_callback(|k, residual| {
+                seen.push(residual);
+                k < 3
+            })
+            .gmres(&a, &b, None);
+        assert_eq!(stopped.iterations, 3);
+        assert_eq!(seen.len(), 3);
+    }
+    #[test]
+    fn test_operator_transpose() {
+        let a = generate_random_matrix(4, 6);
+        let x = generate_random_vector(4);
+        let mut y = vec![0f32; 6];
+        a.apply_transpose(&x, &mut y);
+        let expected = matrix_mult(&NdArray::new(vec![1, 4], x), &a);
+        assert!(
+            y.iter()
+                .zip(&expected.data)
+                .all(|(p, q)| (p - q).abs() < 1e-5)
+        );
+    }
+}
@@ -0,0 +1,563 @@
+use crate::algebra::ndmethods::symmetrize;
+use crate::algebra::vector::{axpy, dot_product, magnitude};
+use crate::decomposition::eigen::general::Eigen;
+use crate::decomposition::eigen::symmetric::SymmetricEigen;
+use crate::decomposition::lower_upper::LuPivotDecompose;
+use crate::decomposition::schur::{block_eigenvalue, real_schur, schur_blocks};
+use crate::random::generation::generate_random_vector;
+use crate::structure::ndarray::NdArray;
+use crate::structure::ndsignal::{Complex, NdSignal};
+use crate::structure::operator::LinearOperator;
+
+const MIN_SUBSPACE: usize = 20;
+
+/// Spectrum
+/// end of the spectrum to converge to, algebraic for lanczos and by magnitude for arnoldi
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spectrum {
+    Largest,
+    Smallest,
+}
+
+/// ShiftInvert
+/// operator (A - sI)^-1 from one lu factorization, its largest eigenvalues t give the
+/// eigenvalues s + 1 / t of A closest to s
+///
+/// * lu: factorization of A - sI
+pub struct ShiftInvert {
+    pub lu: LuPivotDecompose,
+}
+
+impl ShiftInvert {
+    pub fn new(a: &NdArray, sigma: f32) -> Self {
+        let n = a.dims[0];
+        let mut shifted = a.clone();
+        (0..n).for_each(|i| shifted.data[i * n + i] -= sigma);
+        Self {
+            lu: LuPivotDecompose::new(shifted, &mut vec![0f32; n]),
+        }
+    }
+}
+
+impl LinearOperator for ShiftInvert {
+    fn dims(&self) -> (usize, usize) {
+        (self.lu.matrix.dims[0], self.lu.matrix.dims[1])
+    }
+    fn apply(&self, x: &[f32], y: &mut [f32]) {
+        y.copy_from_slice(x);
+        self.lu.solve_inplace_vec(y);
+    }
+    fn apply_transpose(&self, x: &[f32], y: &mut [f32]) {
+        y.copy_from_slice(x);
+        self.lu.solve_transpose_inplace_vec(y);
+    }
+}
+
+/// Lanczos
+/// thick restarted lanczos with full reorthogonalization for symmetric operators,
+/// equivalent to implicit restarts with exact shifts
+///
+/// * k: number of eigenpairs
+/// * subspace: krylov dimension before a restart, zero picks max(2k + 1, 20)
+/// * tolerance: ritz residual relative to the ritz value
+/// * max_restarts: restart budget
+/// * spectrum: which end of the spectrum
+pub struct Lanczos {
+    pub k: usize,
+    pub subspace: usize,
+    pub tolerance: f32,
+    pub max_restarts: usize,
+    pub spectrum: Spectrum,
+}
+
+/// LanczosEigen
+///
+/// * values: ritz values, best first
+/// * vectors: ritz vectors stored as columns ~ n x k
+/// * restarts: restarts taken
+/// * converged: every ritz residual met the tolerance
+pub struct LanczosEigen {
+    pub values: Vec<f32>,
+    pub vectors: NdArray,
+    pub restarts: usize,
+    pub converged: bool,
+}
+
+/// Arnoldi
+/// krylov schur restarted arnoldi for general operators, the projected hessenberg matrix is
+/// reduced by francis steps and the wanted ritz values are moved forward by schur reordering
+///
+/// * k: number of eigenpairs
+/// * subspace: krylov dimension before a restart, zero picks max(2k + 1, 20)
+/// * tolerance: schur vector residual relative to the ritz value
+/// * max_restarts: restart budget
+/// * spectrum: largest or smallest magnitude
+pub struct Arnoldi {
+    pub k: usize,
+    pub subspace: usize,
+    pub tolerance: f32,
+    pub max_restarts: usize,
+    pub spectrum: Spectrum,
+}
+
+/// ArnoldiEigen
+///
+/// * values: ritz values, best first
+/// * vectors: ritz vectors stored as columns ~ n x k
+/// * restarts: restarts taken
+/// * converged: every wanted schur vector residual met the tolerance
+pub struct ArnoldiEigen {
+    pub values: Vec<Complex>,
+    pub vectors: NdSignal,
+    pub restarts: usize,
+    pub converged: bool,
+}
+
+// unit vector orthogonal to the basis, zero once the basis spans the space
+fn fresh_vector(basis: &[Vec<f32>], n: usize) -> Vec<f32> {
+    let mut v = generate_random_vector(n);
+    let initial = magnitude(&v);
+    for _ in 0..2 {
+        for b in basis {
+            axpy(-dot_product(b, &v), b, &mut v);
+        }
+    }
+    let length = magnitude(&v);
+    let scale = if length > 1e-3 * initial {
+        1f32 / length
+    } else {
+        0f32
+    };
+    v.iter_mut().for_each(|x| *x *= scale);
+    v
+}
+
+// ritz residuals below tolerance, with the arpack floor for values near zero
+fn small_residual(residual: f32, value: f32, tolerance: f32) -> bool {
+    residual.abs() <= tolerance * value.abs().max(f32::EPSILON.powf(2f32 / 3f32))
+}
+
+fn subspace_dimension(subspace: usize, k: usize, n: usize) -> usize {
+    let m = if subspace == 0 {
+        (2 * k + 1).max(MIN_SUBSPACE)
+    } else {
+        subspace
+    };
+    m.clamp(k, n)
+}
+
+/// expand
+/// arnoldi steps p..m with two pass gram schmidt, AV_m = V_{m + 1}H
+///
+/// * a: operator ~ n x n
+/// * v: orthonormal basis v_0..v_p, extended to v_0..v_m
+/// * h: projected matrix ~ (m + 1) x m, columns p..m are written
+/// * m: subspace dimension
+/// * p: vectors kept from the last restart
+fn expand(a: &dyn LinearOperator, v: &mut Vec<Vec<f32>>, h: &mut [f32], m: usize, p: usize) {
+    let n = v[0].len();
+    for j in p..m {
+        let mut w = vec![0f32; n];
+        a.apply(&v[j], &mut w);
+        let initial = magnitude(&w);
+        for _ in 0..2 {
+            for (i, vi) in v.iter().enumerate() {
+                let c = dot_product(vi, &w);
+                h[i * m + j] += c;
+                axpy(-c, vi, &mut w);
+            }
+        }
+        let beta = magnitude(&w);
+        // an invariant subspace was found, continue with a fresh direction
+        if beta <= f32::EPSILON * initial || beta == 0f32 {
+            h[(j + 1) * m + j] = 0f32;
+            v.push(fresh_vector(v, n));
+        } else {
+            h[(j + 1) * m + j] = beta;
+            v.push(w.iter().map(|x| x / beta).collect());
+        }
+    }
+}
+
+fn start_vector(n: usize) -> Vec<Vec<f32>> {
+    vec![fresh_vector(&[], n)]
+}
+
+impl Lanczos {
+    pub fn new(k: usize, spectrum: Spectrum) -> Self {
+        Self {
+            k,
+            subspace: 0,
+            tolerance: 1e-5,
+            max_restarts: 300,
+            spectrum,
+        }
+    }
+    pub fn with_subspace(mut self, subspace: usize) -> Self {
+        self.subspace = subspace;
+        self
+    }
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+    pub fn with_max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+    /// compute
+    ///
+    /// * a: symmetric operator ~ n x n
+    pub fn compute(&self, a: &dyn LinearOperator) -> LanczosEigen {
+        match self.spectrum {
+            Spectrum::Largest => self.run(a, |theta| theta),
+            Spectrum::Smallest => self.run(a, |theta| -theta),
+        }
+    }
+    /// shift_invert
+    /// k eigenpairs of a symmetric matrix closest to sigma
+    ///
+    /// * a: symmetric matrix ~ n x n
+    /// * sigma: shift, must not be an eigenvalue
+    pub fn shift_invert(&self, a: &NdArray, sigma: f32) -> LanczosEigen {
+        let mut result = self.run(&ShiftInvert::new(a, sigma), |theta| theta.abs());
+        result
+            .values
+            .iter_mut()
+            .for_each(|theta| *theta = sigma + 1f32 / *theta);
+        result
+    }
+    fn run(&self, a: &dyn LinearOperator, score: impl Fn(f32) -> f32) -> LanczosEigen {
+        let n = a.dims().0;
+        let k = self.k.min(n);
+        let m = subspace_dimension(self.subspace, k, n);
+        let keep = (k + (m - k) / 2).min(m.saturating_sub(1)).max(k);
+        let mut v = start_vector(n);
+        let mut h = vec![0f32; (m + 1) * m];
+        let mut p = 0;
+        let mut restart = 0;
+        loop {
+            expand(a, &mut v, &mut h, m, p);
+            let mut t = NdArray::new(vec![m, m], h[..m * m].to_vec());
+            symmetrize(&mut t);
+            let eigen = SymmetricEigen::compute(&t);
+            let (values, y) = (&eigen.values, &eigen.vectors.data);
+            let mut order: Vec<usize> = (0..m).collect();
+            order.sort_by(|&i, &j| score(values[j]).total_cmp(&score(values[i])));
+            // residual of ritz pair c is |beta y_mc|
+            let beta = h[m * m + m - 1];
+            let converged = order[..k]
+                .iter()
+                .all(|&c| small_residual(beta * y[(m - 1) * m + c], values[c], self.tolerance));
+            // a full subspace leaves nothing to restart with
+            let last = converged || restart == self.max_restarts || keep >= m;
+            p = if last { k } else { keep };
+            // V <- VY over the kept ritz vectors, the residual direction v_m follows them
+            let mut next: Vec<Vec<f32>> = order[..p]
+                .iter()
+                .map(|&c| {
+                    let mut x = vec![0f32; n];
+                    for (i, vi) in v.iter().take(m).enumerate() {
+                        axpy(y[i * m + c], vi, &mut x);
+                    }
+                    x
+                })
+                .collect();
+            if last {
+                let mut vectors = vec![0f32; n * k];
+                for (j, x) in next.iter().enumerate() {
+                    for i in 0..n {
+                        vectors[i * k + j] = x[i];
+                    }
+                }
+                return LanczosEigen {
+                    values: order[..k].iter().map(|&c| values[c]).collect(),
+                    vectors: NdArray::new(vec![n, k], vectors),
+                    restarts: restart,
+                    converged,
+                };
+            }
+            // arrowhead restart, diag(theta) with the coupling b' in row p
+            h.fill(0f32);
+            for (j, &c) in order[..p].iter().enumerate() {
+                h[j * m + j] = values[c];
+                h[p * m + j] = beta * y[(m - 1) * m + c];
+            }
+            next.push(v.swap_remove(m));
+            v = next;
+            restart += 1;
+        }
+    }
+}
+
+fn complex_score(spectrum: Spectrum) -> impl Fn(Complex) -> f32 {
+    move |lambda| match spectrum {
+        Spectrum::Largest => lambda.norm(),
+        Spectrum::Smallest => -lambda.norm(),
+    }
+}
+
+impl Arnoldi {
+    pub fn new(k: usize, spectrum: Spectrum) -> Self {
+        Self {
+            k,
+            subspace: 0,
+            tolerance: 1e-5,
+            max_restarts: 300,
+            spectrum,
+        }
+    }
+    pub fn with_subspace(mut self, subspace: usize) -> Self {
+        self.subspace = subspace;
+        self
+    }
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+    pub fn with_max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+    /// compute
+    ///
+    /// * a: operator ~ n x n
+    pub fn compute(&self, a: &dyn LinearOperator) -> ArnoldiEigen {
+        self.run(a, complex_score(self.spectrum))
+    }
+    /// shift_invert
+    /// k eigenpairs closest to sigma
+    ///
+    /// * a: matrix ~ n x n
+    /// * sigma: real shift, must not be an eigenvalue
+    pub fn shift_invert(&self, a: &NdArray, sigma: f32) -> ArnoldiEigen {
+        let mut result = self.run(
+            &ShiftInvert::new(a, sigma),
+            complex_score(Spectrum::Largest),
+        );
+        for theta in result.values.iter_mut() {
+            // s + 1 / t
+            let modulus = theta.real() * theta.real() + theta.imag() * theta.imag();
+            *theta = Complex::new(sigma + theta.real() / modulus, -theta.imag() / modulus);
+        }
+        result
+    }
+    fn run(&self, a: &dyn LinearOperator, score: impl Fn(Complex) -> f32) -> ArnoldiEigen {
+        let n = a.dims().0;
+        let k = self.k.min(n);
+        let m = subspace_dimension(self.subspace, k, n);
+        // room for a conjugate partner of the last kept value
+        let keep = (k + (m - k) / 2).min(m.saturating_sub(2)).max(k);
+        let mut v = start_vector(n);
+        let mut h = vec![0f32; (m + 1) * m];
+        let mut p = 0;
+        let mut restart = 0;
+        loop {
+            expand(a, &mut v, &mut h, m, p);
+            let (mut schur, _, reduced) =
+                real_schur(&NdArray::new(vec![m, m], h[..m * m].to_vec()));
+            let mut scores: Vec<f32> = schur_blocks(&mut schur.kernel.data, m)
+                .into_iter()
+                .flat_map(|(start, size)| {
+                    let value = score(block_eigenvalue(&schur.kernel.data, m, start, size));
+                    std::iter::repeat_n(value, size)
+                })
+                .collect();
+            scores.sort_by(|x, y| y.total_cmp(x));
+            let threshold = |s: f32| s - 1e-6 * s.abs();
+            let (wanted, kept) = (threshold(scores[k - 1]), threshold(scores[keep - 1]));
+            p = schur.reorder(|lambda| score(lambda) >= kept).dims[1];
+            // schur vectors are the rows of the rotation, residual of row j is |beta r_j,m-1|
+            let beta = h[m * m + m - 1];
+            let r = &schur.rotation.data;
+            let t = &mut schur.kernel.data;
+            let converged = reduced
+                && schur_blocks(t, m)
+                    .into_iter()
+                    .filter(|&(start, size)| {
+                        start < p && score(block_eigenvalue(t, m, start, size)) >= wanted
+                    })
+                    .all(|(start, size)| {
+                        let value = block_eigenvalue(t, m, start, size).norm();
+                        (start..start + size)
+                            .all(|j| small_residual(beta * r[j * m + m - 1], value, self.tolerance))
+                    });
+            let last = converged || restart == self.max_restarts || p >= m;
+            // V <- VQ over the leading p schur vectors
+            let mut next: Vec<Vec<f32>> = (0..p)
+                .map(|j| {
+                    let mut x = vec![0f32; n];
+                    for (i, vi) in v.iter().take(m).enumerate() {
+                        axpy(r[j * m + i], vi, &mut x);
+                    }
+                    x
+                })
+                .collect();
+            if last {
+                let (values, vectors) = Self::ritz(&next, &schur.kernel, p, k, score);
+                return ArnoldiEigen {
+                    values,
+                    vectors,
+                    restarts: restart,
+                    converged,
+                };
+            }
+            // krylov schur restart, leading block of T with the coupling b' in row p
+            h.fill(0f32);
+            for i in 0..p {
+                for j in 0..p {
+                    h[i * m + j] = schur.kernel.data[i * m + j];
+                }
+                h[p * m + i] = beta * r[i * m + m - 1];
+            }
+            next.push(v.swap_remove(m));
+            v = next;
+            restart += 1;
+        }
+    }
+    fn ritz(
+        basis: &[Vec<f32>],
+        t: &NdArray,
+        p: usize,
+        k: usize,
+        score: impl Fn(Complex) -> f32,
+    ) -> (Vec<Complex>, NdSignal) {
+        let n = basis.first().map_or(0, |b| b.len());
+        let m = t.dims[0];
+        let mut tp = vec![0f32; p * p];
+        for i in 0..p {
+            tp[i * p..(i + 1) * p].copy_from_slice(&t.data[i * m..i * m + p]);
+        }
+        let eigen = Eigen::compute_with(&NdArray::new(vec![p, p], tp), false);
+        let mut order: Vec<usize> = (0..p).collect();
+        order.sort_by(|&i, &j| score(eigen.values[j]).total_cmp(&score(eigen.values[i])));
+        let k = k.min(p);
+        // x = V_p y
+        let mut vectors = vec![Complex::zero(); n * k];
+        for (col, &c) in order[..k].iter().enumerate() {
+            for (i, vi) in basis.iter().enumerate() {
+                let y = eigen.right.data[i * p + c];
+                for row in 0..n {
+                    vectors[row * k + col] += Complex::new(vi[row] * y.real(), vi[row] * y.imag());
+                }
+            }
+        }
+        (
+            order[..k].iter().map(|&c| eigen.values[c]).collect(),
+            NdSignal::new(vec![n, k], vectors),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_lanczos {
+    use super::*;
+    use crate::random::generation::{generate_random_matrix, generate_random_symetric};
+    use crate::structure::operator::FunctionOperator;
+
+    #[test]
+    fn test_lanczos_ends() {
+        // matrix free diagonal operator with eigenvalues 1..=n
+        let n = 200;
+        let diagonal = FunctionOperator::symmetric(n, |x: &[f32], y: &mut [f32]| {
+            for (i, (yi, xi)) in y.iter_mut().zip(x).enumerate() {
+                *yi = (i + 1) as f32 * xi;
+            }
+        });
+        let largest = Lanczos::new(4, Spectrum::Largest)
+            .with_subspace(30)
+            .compute(&diagonal);
+        let smallest = Lanczos::new(4, Spectrum::Smallest)
+            .with_subspace(30)
+            .compute(&diagonal);
+        assert!(largest.converged && smallest.converged);
+        for j in 0..4 {
+            assert!((largest.values[j] - (n - j) as f32).abs() < 1e-2);
+            assert!((smallest.values[j] - (j + 1) as f32).abs() < 1e-2);
+            // the ritz vector is a coordinate vector
+            let x = largest.vectors.data[(n - 1 - j) * 4 + j];
+            assert!((x.abs() - 1f32).abs() < 1e-3);
+        }
+    }
+    #[test]
+    fn test_lanczos_shift_invert() {
+        let n = 40;
+        let a = generate_random_symetric(n);
+        let sigma = 0.3;
+        let dense = SymmetricEigen::compute(&a);
+        let mut closest = dense.values.clone();
+        closest.sort_by(|x, y| (x - sigma).abs().total_cmp(&(y - sigma).abs()));
+        let result = Lanczos::new(3, Spectrum::Largest).shift_invert(&a, sigma);
+        assert!(result.converged);
+        for (value, expected) in result.values.iter().zip(&closest) {
+            assert!((value - expected).abs() < 1e-3);
+        }
+    }
+    #[test]
+    fn test_lanczos_full_subspace() {
+        // subspace = k leaves no room to restart, the first pass is returned
+        let a = generate_random_symetric(20);
+        let result = Lanczos::new(3, Spectrum::Largest)
+            .with_subspace(3)
+            .compute(&a);
+        assert_eq!(result.restarts, 0);
+        assert_eq!(result.values.len(), 3);
+    }
+    #[test]
+    fn test_arnoldi_shift_invert() {
+        let n = 40;
+        // real spectrum spaced by 0.1 with a small nonsymmetric perturbation
+        let mut a = generate_random_matrix(n, n);
+        a.data.iter_mut().for_each(|v| *v *= 0.02);
+        for i in 0..n {
+            a.data[i * n + i] += 4f32 * i as f32 / n as f32;
+        }
+        let sigma = 1.03;
+        let result = Arnoldi::new(3, Spectrum::Largest).shift_invert(&a, sigma);
+        assert!(result.converged);
+        let distance = |v: &Complex| (*v - Complex::new(sigma, 0f32)).norm();
+        let dense = Eigen::compute(&a).values;
+        let mut closest = dense.clone();
+        closest.sort_by(|x, y| distance(x).total_cmp(&distance(y)));
+        // either member of a conjugate pair is as close to the real shift
+        for (value, expected) in result.values.iter().zip(&closest) {
+            assert!((distance(value) - distance(expected)).abs() < 1e-3);
+            assert!(dense.iter().any(|d| (*value - *d).norm() < 1e-3));
+        }
+    }
+    #[test]
+    fn test_arnoldi_largest() {
+        let n = 60;
+        // spread real spectrum with an outlying conjugate pair 5 +- 3i
+        let mut a = generate_random_matrix(n, n);
+        a.data.iter_mut().for_each(|v| *v *= 0.02);
+        for i in 0..n {
+            a.data[i * n + i] += 4f32 * i as f32 / n as f32;
+        }
+        a.data[0] += 5f32;
+        a.data[n + 1] += 5f32;
+        a.data[1] += 3f32;
+        a.data[n] -= 3f32;
+        let result = Arnoldi::new(3, Spectrum::Largest).compute(&a);
+        assert!(result.converged);
+        let dense = Eigen::compute(&a);
+        let mut moduli: Vec<f32> = dense.values.iter().map(|v| v.norm()).collect();
+        moduli.sort_by(|x, y| y.total_cmp(x));
+        for (value, expected) in result.values.iter().zip(&moduli) {
+            assert!((value.norm() - expected).abs() < 1e-3);
+        }
+        // Ax = lx for the complex ritz vectors
+        for (col, lambda) in result.values.iter().enumerate() {
+            for i in 0..n {
+                let mut ax = Complex::zero();
+                for j in 0..n {
+                    let x = result.vectors.data[j * 3 + col];
+                    ax += Complex::new(a.data[i * n + j] * x.real(), a.data[i * n + j] * x.imag());
+                }
+                let mut lx = result.vectors.data[i * 3 + col];
+                lx *= *lambda;
+                assert!((ax - lx).norm() < 1e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,563 @@
+use crate::algebra::vector::{dot_product, magnitude, norm_inf};
+use crate::decomposition::qr::{QrDecomposition, RANK_TOLERANCE, solve_qr};
+use crate::solver::optimize::{DIFFERENCE_STEP, Termination};
+use crate::structure::ndarray::NdArray;
+use std::cell::Cell;
+
+// backtracking halvings of a gauss newton step before giving up
+const BACKTRACKS: usize = 30;
+// sufficient decrease of the backtracking search
+const ARMIJO: f32 = 1e-4;
+// damping beyond this leaves levenberg marquardt taking steps lost in rounding
+const MAX_DAMPING: f32 = 1e16;
+
+/// Residual
+/// residual vector r: R^n -> R^m of the problem min 0.5 sum r_i(x)^2, only the residuals are
+/// required, the jacobian falls back to central differences
+///
+/// * residuals: m, the length of r
+/// * residual: r <- r(x)
+/// * jacobian: j <- dr/dx as a m x n row major NdArray
+pub trait Residual {
+    fn residuals(&self) -> usize;
+    fn residual(&self, x: &[f32], r: &mut [f32]);
+    fn jacobian(&self, x: &[f32], j: &mut NdArray) {
+        let (m, n) = (self.residuals(), x.len());
+        debug_assert!(j.dims[0] == m && j.dims[1] == n);
+        let mut probe = x.to_vec();
+        let mut forward = vec![0f32; m];
+        let mut backward = vec![0f32; m];
+        for k in 0..n {
+            let h = DIFFERENCE_STEP * x[k].abs().max(1f32);
+            probe[k] = x[k] + h;
+            self.residual(&probe, &mut forward);
+            probe[k] = x[k] - h;
+            self.residual(&probe, &mut backward);
+            probe[k] = x[k];
+            for i in 0..m {
+                j.data[i * n + k] = (forward[i] - backward[i]) / (2f32 * h);
+            }
+        }
+    }
+}
+
+/// LeastSquaresResult
+///
+/// * x: final iterate
+/// * residual: r(x)
+/// * cost: 0.5 ||r(x)||^2
+/// * gradient: J'r at x, components pushing against an active bound are kept
+/// * covariance: s^2 (J'J)^-1 with s^2 = ||r||^2 / (m - n), None when m <= n or J is rank
+///   deficient, parameters held at a bound are treated as free
+/// * iterations: accepted gauss newton steps or levenberg marquardt trial steps
+/// * residual_evaluations: calls to residual, excluding those of a difference jacobian
+/// * jacobian_evaluations: calls to jacobian
+/// * termination: reason the iteration stopped
+/// * history: cost at the start and after every accepted step
+#[derive(Clone, Debug)]
+pub struct LeastSquaresResult {
+    pub x: Vec<f32>,
+    pub residual: Vec<f32>,
+    pub cost: f32,
+    pub gradient: Vec<f32>,
+    pub covariance: Option<NdArray>,
+    pub iterations: usize,
+    pub residual_evaluations: usize,
+    pub jacobian_evaluations: usize,
+    pub termination: Termination,
+    pub history: Vec<f32>,
+}
+
+/// LeastSquares
+/// settings of the nonlinear least squares solvers, every step is the least squares solution
+/// of a linearization computed with a QR decomposition of the jacobian, the normal equations
+/// J'J are never formed
+///
+/// * tolerance: stop once the projected gradient ||P(x - J'r) - x||_inf <= tolerance
+/// * max_iters: maximum number of iterations
+/// * damping: initial levenberg marquardt damping relative to max diag(D'D)
+/// * lower: lower bounds of x, iterates are projected onto the box
+/// * upper: upper bounds of x
+pub struct LeastSquares {
+    pub tolerance: f32,
+    pub max_iters: usize,
+    pub damping: f32,
+    pub lower: Option<Vec<f32>>,
+    pub upper: Option<Vec<f32>>,
+}
+
+// counts the calls made by a solver
+struct Counted<'a> {
+    problem: &'a dyn Residual,
+    residuals: Cell<usize>,
+    jacobians: Cell<usize>,
+}
+
+impl Residual for Counted<'_> {
+    fn residuals(&self) -> usize {
+        self.problem.residuals()
+    }
+    fn residual(&self, x: &[f32], r: &mut [f32]) {
+        self.residuals.set(self.residuals.get() + 1);
+        self.problem.residual(x, r)
+    }
+    fn jacobian(&self, x: &[f32], j: &mut NdArray) {
+        self.jacobians.set(self.jacobians.get() + 1);
+        self.problem.jacobian(x, j)
+    }
+}
+
+// columns of J listed in free with rows appended for the damping of levenberg marquardt
+fn columns(j: &NdArray, free: &[usize], extra: usize) -> NdArray {
+    let (m, n) = (j.dims[0], j.dims[1]);
+    let mut data = vec![0f32; (m + extra) * free.len()];
+    for i in 0..m {
+        for (c, &k) in free.iter().enumerate() {
+            data[i * free.len() + c] = j.data[i * n + k];
+        }
+    }
+    NdArray::new(vec![m + extra, free.len()], data)
+}
+
+// J'r
+fn gradient(j: &NdArray, r: &[f32]) -> Vec<f32> {
+    let n = j.dims[1];
+    let mut g = vec![0f32; n];
+    for (row, ri) in j.data.chunks(n).zip(r) {
+        g.iter_mut().zip(row).for_each(|(g, a)| *g += a * ri);
+    }
+    g
+}
+
+// s^2 (R'R)^-1 from the QR decomposition of J
+fn covariance(j: &NdArray, r: &[f32]) -> Option<NdArray> {
+    let (m, n) = (j.dims[0], j.dims[1]);
+    if m <= n {
+        return None;
+    }
+    let qr = QrDecomposition::new(j.clone());
+    let t = &qr.triangle.data;
+    let scale = (0..n).fold(0f32, |acc, i| acc.max(t[i * n + i].abs()));
+    if (0..n).any(|i| t[i * n + i].abs() <= RANK_TOLERANCE * scale) {
+        return None;
+    }
+    let variance = dot_product(r, r) / (m - n) as f32;
+    // R^-1 column by column, then (R'R)^-1 = R^-1 R^-T
+    let mut inverse = vec![0f32; n * n];
+    for c in 0..n {
+        for i in (0..=c).rev() {
+            let mut sum = if i == c { 1f32 } else { 0f32 };
+            for k in i + 1..=c {
+                sum -= t[i * n + k] * inverse[k * n + c];
+            }
+            inverse[i * n + c] = sum / t[i * n + i];
+        }
+    }
+    let mut data = vec![0f32; n * n];
+    for a in 0..n {
+        for b in a..n {
+            let sum: f32 = (b..n)
+                .map(|k| inverse[a * n + k] * inverse[b * n + k])
+                .sum();
+            data[a * n + b] = variance * sum;
+            data[b * n + a] = variance * sum;
+        }
+    }
+    Some(NdArray::new(vec![n, n], data))
+}
+
+impl LeastSquares {
+    pub fn new(tolerance: f32, max_iters: usize) -> Self {
+        Self {
+            tolerance,
+            max_iters,
+            damping: 1e-3,
+            lower: None,
+            upper: None,
+        }
+    }
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+    pub fn with_bounds(mut self, lower: Vec<f32>, upper: Vec<f32>) -> Self {
+        debug_assert!(lower.len() == upper.len());
+        debug_assert!(lower.iter().zip(&upper).all(|(l, u)| l <= u));
+        self.lower = Some(lower);
+        self.upper = Some(upper);
+        self
+    }
+
+    fn project(&self, x: &mut [f32]) {
+        if let Some(lower) = &self.lower {
+            x.iter_mut().zip(lower).for_each(|(x, l)| *x = x.max(*l));
+        }
+        if let Some(upper) = &self.upper {
+            x.iter_mut().zip(upper).for_each(|(x, u)| *x = x.min(*u));
+        }
+    }
+
+    // ||P(x - g) - x||_inf, the gradient with components blocked by a bound removed
+    fn projected_gradient(&self, x: &[f32], g: &[f32]) -> f32 {
+        let mut step: Vec<f32> = x.iter().zip(g).map(|(x, g)| x - g).collect();
+        self.project(&mut step);
+        step.iter()
+            .zip(x)
+            .fold(0f32, |acc, (p, x)| acc.max((p - x).abs()))
+    }
+
+    // variables not held at a bound by the gradient, steps are taken in these alone
+    fn free(&self, x: &[f32], g: &[f32]) -> Vec<usize> {
+        (0..x.len())
+            .filter(|&k| {
+                let at_lower = self
+                    .lower
+                    .as_ref()
+                    .is_some_and(|l| x[k] <= l[k] && g[k] > 0f32);
+                let at_upper = self
+                    .upper
+                    .as_ref()
+                    .is_some_and(|u| x[k] >= u[k] && g[k] < 0f32);
+                !(at_lower || at_upper)
+            })
+            .collect()
+    }
+
+    fn result(
+        &self,
+        counted: &Counted,
+        x: Vec<f32>,
+        r: Vec<f32>,
+        j: &NdArray,
+        iterations: usize,
+        termination: Termination,
+        history: Vec<f32>,
+    ) -> LeastSquaresResult {
+        LeastSquaresResult {
+            cost: 0.5 * dot_product(&r, &r),
+            gradient: gradient(j, &r),
+            covariance: covariance(j, &r),
+            x,
+            residual: r,
+            iterations,
+            residual_evaluations: counted.residuals.get(),
+            jacobian_evaluations: counted.jacobians.get(),
+            termination,
+            history,
+        }
+    }
+
+    /// gauss_newton
+    /// steps to the least squares solution of J d = -r, the step is halved until the
+    /// projected point decreases the cost sufficiently
+    pub fn gauss_newton(&self, problem: &dyn Residual, x0: &[f32]) -> LeastSquaresResult {
+        let counted = Counted {
+            problem,
+            residuals: Cell::new(0),
+            jacobians: Cell::new(0),
+        };
+        let (m, n) = (problem.residuals(), x0.len());
+        let mut x = x0.to_vec();
+        self.project(&mut x);
+        let mut r = vec![0f32; m];
+        counted.residual(&x, &mut r);
+        let mut cost = 0.5 * dot_product(&r, &r);
+        let mut j = NdArray::new(vec![m, n], vec![0f32; m * n]);
+        let mut history = vec![cost];
+        let mut iterations = 0;
+        let mut stalled = false;
+        let mut termination = Termination::MaxIterations;
+        loop {
+            counted.jacobian(&x, &mut j);
+            let g = gradient(&j, &r);
+            if self.projected_gradient(&x, &g) <= self.tolerance {
+                termination = Termination::Converged;
+                break;
+            }
+            if stalled {
+                termination = Termination::Stalled;
+                break;
+            }
+            if iterations >= self.max_iters {
+                break;
+            }
+            let minus_r: Vec<f32> = r.iter().map(|v| -v).collect();
+            let free = self.free(&x, &g);
+            let mut d = vec![0f32; n];
+            let step = solve_qr(columns(&j, &free, 0), &minus_r);
+            free.iter().zip(step).for_each(|(&k, v)| d[k] = v);
+            let mut trial_r = vec![0f32; m];
+            let mut alpha = 1f32;
+            let mut accepted = None;
+            for _ in 0..BACKTRACKS {
+                let mut trial: Vec<f32> = x.iter().zip(&d).map(|(x, d)| x + alpha * d).collect();
+                self.project(&mut trial);
+                let s: Vec<f32> = trial.iter().zip(&x).map(|(a, b)| a - b).collect();
+                counted.residual(&trial, &mut trial_r);
+                let trial_cost = 0.5 * dot_product(&trial_r, &trial_r);
+                if trial_cost <= cost + ARMIJO * dot_product(&g, &s) {
+                    accepted = Some((trial, s, trial_cost));
+                    break;
+                }
+                alpha *= 0.5;
+            }
+            let Some((trial, s, trial_cost)) = accepted else {
+                termination = Termination::LineSearchFailed;
+                break;
+            };
+            iterations += 1;
+            let step = norm_inf(&s);
+            (x, cost) = (trial, trial_cost);
+            std::mem::swap(&mut r, &mut trial_r);
+            history.push(cost);
+            stalled = step <= f32::EPSILON * (norm_inf(&x) + f32::EPSILON);
+        }
+        self.result(&counted, x, r, &j, iterations, termination, history)
+    }
+
+    /// levenberg_marquardt
+    /// steps to the least squares solution of [J; sqrt(lambda) D] d = [-r; 0] with D the
+    /// largest column norms of J seen so far, lambda shrinks with the ratio of actual to
+    /// predicted decrease of accepted steps and doubles geometrically on rejections
+    pub fn levenberg_marquardt(&self, problem: &dyn Residual, x0: &[f32]) -> LeastSquaresResult {
+        let counted = Counted {
+            problem,
+            residuals: Cell::new(0),
+            jacobians: Cell::new(0),
+        };
+        let (m, n) = (problem.residuals(), x0.len());
+        let mut x = x0.to_vec();
+        self.project(&mut x);
+        let mut r = vec![0f32; m];
+        counted.residual(&x, &mut r);
+        let mut cost = 0.5 * dot_product(&r, &r);
+        let mut j = NdArray::new(vec![m, n], vec![0f32; m * n]);
+        counted.jacobian(&x, &mut j);
+        let mut g = gradient(&j, &r);
+        let mut scaling = vec![0f32; n];
+        let mut lambda = None;
+        let mut growth = 2f32;
+        let mut history = vec![cost];
+        let mut iterations = 0;
+        let mut termination = Termination::MaxIterations;
+        loop {
+            if self.projected_gradient(&x, &g) <= self.tolerance {
+                termination = Termination::Converged;
+                break;
+            }
+            if iterations >= self.max_iters {
+                break;
+            }
+            for (k, d) in scaling.iter_mut().enumerate() {
+                let column: Vec<f32> = (0..m).map(|i| j.data[i * n + k]).collect();
+                *d = d.max(magnitude(&column)).max(f32::EPSILON);
+            }
+            let damping = *lambda.get_or_insert_with(|| {
+                self.damping * scaling.iter().fold(0f32, |a, d| a.max(d * d))
+            });
+            if damping > MAX_DAMPING || !damping.is_finite() {
+                termination = Termination::Stalled;
+                break;
+            }
+            let free = self.free(&x, &g);
+            let mut augmented = columns(&j, &free, free.len());
+            for (c, &k) in free.iter().enumerate() {
+                augmented.data[(m + c) * free.len() + c] = damping.sqrt() * scaling[k];
+            }
+            let mut minus_r: Vec<f32> = r.iter().map(|v| -v).collect();
+            minus_r.resize(m + free.len(), 0f32);
+            let mut d = vec![0f32; n];
+            let step = solve_qr(augmented, &minus_r);
+            free.iter().zip(step).for_each(|(&k, v)| d[k] = v);
+            let mut trial: Vec<f32> = x.iter().zip(&d).map(|(x, d)| x + d).collect();
+            self.project(&mut trial);
+            let s: Vec<f32> = trial.iter().zip(&x).map(|(a, b)| a - b).collect();
+            iterations += 1;
+            // 0.5 ||r||^2 - 0.5 ||r + J s||^2 of the linearization
+            let linear: Vec<f32> = j
+                .data
+                .chunks(n)
+                .zip(&r)
+                .map(|(row, ri)| ri + dot_product(row, &s))
+                .collect();
+            let predicted = cost - 0.5 * dot_product(&linear, &linear);
+            let mut trial_r = vec![0f32; m];
+            counted.residual(&trial, &mut trial_r);
+            let trial_cost = 0.5 * dot_product(&trial_r, &trial_r);
+            let actual = cost - trial_cost;
+            if predicted > 0f32 && actual > 0f32 {
+                let rho = actual / predicted;
+                lambda = Some(damping * (1f32 - (2f32 * rho - 1f32).powi(3)).max(1f32 / 3f32));
+                growth = 2f32;
+                (x, r, cost) = (trial, trial_r, trial_cost);
+                counted.jacobian(&x, &mut j);
+                g = gradient(&j, &r);
+                history.push(cost);
+                if actual <= f32::EPSILON * cost.max(f32::MIN_POSITIVE)
+                    && self.projected_gradient(&x, &g) > self.tolerance
+                {
+                    termination = Termination::Stalled;
+                    break;
+                }
+            } else {
+                lambda = Some(damping * growth);
+                growth *= 2f32;
+                if norm_inf(&s) <= f32::EPSILON * (norm_inf(&x) + f32::EPSILON) {
+                    termination = Termination::Stalled;
+                    break;
+                }
+            }
+        }
+        self.result(&counted, x, r, &j, iterations, termination, history)
+    }
+}
+
+#[cfg(test)]
+mod test_least_squares {
+    use super::*;
+
+    // y = a exp(b t) sampled without noise
+    struct Exponential {
+        t: Vec<f32>,
+        y: Vec<f32>,
+    }
+
+    impl Exponential {
+        fn new(a: f32, b: f32) -> Self {
+            let t: Vec<f32> = (0..12).map(|i| i as f32 * 0.25).collect();
+            let y = t.iter().map(|t| a * (b * t).exp()).collect();
+            Self { t, y }
+        }
+    }
+
+    impl Residual for Exponential {
+        fn residuals(&self) -> usize {
+            self.t.len()
+        }
+        fn residual(&self, x: &[f32], r: &mut [f32]) {
+            for i in 0..self.t.len() {
+                r[i] = x[0] * (x[1] * self.t[i]).exp() - self.y[i];
+            }
+        }
+        fn jacobian(&self, x: &[f32], j: &mut NdArray) {
+            for i in 0..self.t.len() {
+                let e = (x[1] * self.t[i]).exp();
+                j.data[i * 2] = e;
+                j.data[i * 2 + 1] = x[0] * self.t[i] * e;
+            }
+        }
+    }
+
+    // the same residuals with the difference jacobian of the trait
+    struct Differenced(Exponential);
+
+    impl Residual for Differenced {
+        fn residuals(&self) -> usize {
+            self.0.residuals()
+        }
+        fn residual(&self, x: &[f32], r: &mut [f32]) {
+            self.0.residual(x, r)
+        }
+    }
+
+    // rosenbrock as residuals (10 (x1 - x0^2), 1 - x0)
+    struct Rosenbrock;
+
+    impl Residual for Rosenbrock {
+        fn residuals(&self) -> usize {
+            2
+        }
+        fn residual(&self, x: &[f32], r: &mut [f32]) {
+            r[0] = 10f32 * (x[1] - x[0] * x[0]);
+            r[1] = 1f32 - x[0];
+        }
+    }
+
+    #[test]
+    fn test_curve_fit() {
+        let solver = LeastSquares::new(1e-4, 100);
+        let analytic = Exponential::new(2f32, -0.7);
+        let differenced = Differenced(Exponential::new(2f32, -0.7));
+        for problem in [&analytic as &dyn Residual, &differenced] {
+            for result in [
+                solver.gauss_newton(problem, &[1f32, 0f32]),
+                solver.levenberg_marquardt(problem, &[1f32, 0f32]),
+            ] {
+                assert_eq!(result.termination, Termination::Converged);
+                assert!((result.x[0] - 2f32).abs() < 1e-3, "{:?}", result.x);
+                assert!((result.x[1] + 0.7).abs() < 1e-3, "{:?}", result.x);
+                assert!(result.cost < 1e-6);
+                assert!(result.history.windows(2).all(|w| w[1] <= w[0]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_levenberg_marquardt_rosenbrock() {
+        let result = LeastSquares::new(1e-4, 200).levenberg_marquardt(&Rosenbrock, &[-1.2, 1f32]);
+        assert_eq!(result.termination, Termination::Converged);
+        assert!((result.x[0] - 1f32).abs() < 1e-3, "{:?}", result.x);
+        assert!((result.x[1] - 1f32).abs() < 1e-3, "{:?}", result.x);
+        // m == n leaves no degrees of freedom for the residual variance
+        assert!(result.covariance.is_none());
+    }
+
+    #[test]
+    fn test_box_bounds() {
+        // unconstrained minimum at (1, 1), x0 <= 0.5 holds it on the parabola x1 = x0^2, the
+        // cost of 0.125 left there hides smaller gradients in f32 rounding
+        let solver = LeastSquares::new(1e-3, 200).with_bounds(vec![-2f32, -2f32], vec![0.5, 2f32]);
+        for result in [
+            solver.gauss_newton(&Rosenbrock, &[-1.2, 1f32]),
+            solver.levenberg_marquardt(&Rosenbrock, &[-1.2, 1f32]),
+        ] {
+            assert_eq!(result.termination, Termination::Converged);
+            assert!((result.x[0] - 0.5).abs() < 1e-5, "{:?}", result.x);
+            assert!((result.x[1] - 0.25).abs() < 1e-3, "{:?}", result.x);
+            // the bound blocks a gradient pushing x0 up
+            assert!(result.gradient[0] < 0f32);
+        }
+    }
+
+    #[test]
+    fn test_linear_covariance() {
+        // y = c0 + c1 t, the covariance is s^2 (X'X)^-1 in closed form
+        struct Line {
+            t: Vec<f32>,
+            y: Vec<f32>,
+        }
+        impl Residual for Line {
+            fn residuals(&self) -> usize {
+                self.t.len()
+            }
+            fn residual(&self, x: &[f32], r: &mut [f32]) {
+                for i in 0..self.t.len() {
+                    r[i] = x[0] + x[1] * self.t[i] - self.y[i];
+                }
+            }
+            fn jacobian(&self, _: &[f32], j: &mut NdArray) {
+                for i in 0..self.t.len() {
+                    j.data[i * 2] = 1f32;
+                    j.data[i * 2 + 1] = self.t[i];
+                }
+            }
+        }
+        let t = vec![0f32, 1f32, 2f32, 3f32, 4f32];
+        let noise = [0.1, -0.2, 0.05, 0.15, -0.1];
+        let y = t
+            .iter()
+            .zip(noise)
+            .map(|(t, e)| 1f32 + 2f32 * t + e)
+            .collect();
+        let problem = Line { t, y };
+        let result = LeastSquares::new(1e-5, 10).gauss_newton(&problem, &[0f32, 0f32]);
+        assert_eq!(result.termination, Termination::Converged);
+        let covariance = result.covariance.unwrap();
+        let variance = 2f32 * result.cost / 3f32;
+        // X'X = [[5, 10], [10, 30]], det = 50
+        let expected = [30f32 / 50f32, -10f32 / 50f32, -10f32 / 50f32, 5f32 / 50f32];
+        for (c, e) in covariance.data.iter().zip(expected) {
+            assert!((c - variance * e).abs() < 1e-5, "{:?}", covariance.data);
+        }
+    }
+}
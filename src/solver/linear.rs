@@ -1,321 +1,963 @@
 use crate::decomposition::lower_upper::LuPivotDecompose;
 use crate::structure::ndarray::NdArray;
+use crate::structure::operator::LinearOperator;
 
-const EPSILON: f32 = 1e-9;
+const FEASIBILITY_TOLERANCE: f32 = 1e-5;
+const OPTIMALITY_TOLERANCE: f32 = 1e-5;
+const PIVOT_TOLERANCE: f32 = 1e-6;
+// eta updates kept on top of the LU before the basis is factored again
+const REFACTOR_PERIOD: usize = 32;
 
-pub struct DualLinear {}
-impl DualLinear {
-    pub fn transform(a: NdArray) -> NdArray {
-        debug_assert!(a.dims[0] >= a.dims[1], "m < n should not take dual");
-        let (m, n) = (a.dims[0], a.dims[1]);
-        let n_cols = 2 * m + 2 * n;
-        let mut data = Vec::with_capacity(n * n_cols);
-        for row in 0..n {
-            // A'
-            for col in 0..m {
-                data.push(a.data[col * n + row]);
-            }
-            // -A'
-            for col in 0..m {
-                data.push(-a.data[col * n + row]);
-            }
-            // I_slack
-            for col in 0..n {
-                data.push(if row == col { 1.0 } else { 0.0 });
-            }
-            // I_artificial
-            for col in 0..n {
-                data.push(if row == col { 1.0 } else { 0.0 });
-            }
-        }
-        debug_assert!(2 * n * (m + n) == data.len(), "data length not correct");
-        NdArray {
-            dims: vec![n, n_cols],
-            data,
-        }
-    }
+/// Sense
+/// direction of the objective
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sense {
+    Minimize,
+    Maximize,
+}
+
+/// Relation
+/// row i of the constraints reads a_i'x (<=, >=, =) b_i
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Relation {
+    LessEqual,
+    GreaterEqual,
+    Equal,
 }
 
-// Ax = b; min sum(x); x[i] >= 0;
+/// LinearProgram
+/// min or max c'x s.t. Ax (<=, >=, =) b, lower <= x <= upper
+///
+/// * sense: minimize or maximize
+/// * c: objective ~ n
+/// * a: constraint matrix ~ m x n
+/// * relations: relation of every constraint row ~ m
+/// * b: right hand side ~ m
+/// * lower: lower bounds, -inf when unbounded below ~ n
+/// * upper: upper bounds, inf when unbounded above ~ n
+#[derive(Clone, Debug)]
 pub struct LinearProgram {
-    pub class: ProgramClass,
-    // base matrix dimension
-    m: usize,
-    n: usize,
-    // cost vector, target
-    c: Vec<f32>,
-    b: Vec<f32>,
+    pub sense: Sense,
+    pub c: Vec<f32>,
+    pub a: NdArray,
+    pub relations: Vec<Relation>,
+    pub b: Vec<f32>,
+    pub lower: Vec<f32>,
+    pub upper: Vec<f32>,
+}
+
+/// Pricing
+/// rule choosing the entering column
+///
+/// * Bland: lowest index with a negative reduced cost, cannot cycle
+/// * SteepestEdge: largest d_j^2 / (1 + ||B^-1 a_j||^2) with goldfarb reid weight updates,
+///   falls back to bland during long runs of degenerate pivots
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pricing {
+    Bland,
+    SteepestEdge,
+}
+
+/// LpStatus
+///
+/// * Stalled: the interior point residuals stopped improving before the iteration limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LpStatus {
+    Optimal,
+    Infeasible,
+    Unbounded,
+    IterationLimit,
+    Stalled,
+}
+
+/// LpSolution
+///
+/// * status: optimal, infeasible, unbounded, out of iterations or stalled
+/// * x: primal point in the original variables, the last iterate unless optimal
+/// * objective: c'x, plus 1/2 x'Qx for quadratic programs
+/// * duals: multiplier of every constraint row for the original objective ~ m
+/// * reduced_costs: c + Qx - A'y, the multipliers of the active bounds ~ n
+/// * iterations: simplex pivots over both phases or interior point steps
+#[derive(Clone, Debug)]
+pub struct LpSolution {
+    pub status: LpStatus,
     pub x: Vec<f32>,
-    basis: Vec<usize>,
-    // [A', -A', I_slack, I_artificial];
-    // [m, m, n, n];
-    constraint: NdArray,
+    pub objective: f32,
+    pub duals: Vec<f32>,
+    pub reduced_costs: Vec<f32>,
+    pub iterations: usize,
 }
 
-pub enum ProgramClass {
-    Primal,
-    Dual,
+// how an original variable is written with nonnegative standard form columns
+#[derive(Clone, Copy, Debug)]
+enum Column {
+    // x = offset + x[col]
+    Shifted(usize, f32),
+    // x = offset - x[col]
+    Mirrored(usize, f32),
+    // x = x[pos] - x[neg]
+    Split(usize, usize),
+}
+
+/// StandardForm
+/// min c'x s.t. Ax = b, x >= 0 with b >= 0, equivalent to a LinearProgram up to a constant
+/// the first m rows are the constraint rows in order, followed by one row per finite range
+/// l <= x <= u
+///
+/// * a: structural then slack columns ~ rows x cols
+/// * b: nonnegative right hand side ~ rows
+/// * c: minimization costs ~ cols
+/// * row_sign: -1 where a row was negated to make b nonnegative ~ rows
+/// * slack: slack column of every row, none for equalities ~ rows
+#[derive(Clone, Debug)]
+pub struct StandardForm {
+    pub a: NdArray,
+    pub b: Vec<f32>,
+    pub c: Vec<f32>,
+    pub row_sign: Vec<f32>,
+    pub slack: Vec<Option<usize>>,
+    columns: Vec<Column>,
 }
 
 impl LinearProgram {
-    pub fn new(c: Vec<f32>, b: Vec<f32>, matrix: NdArray) -> Self {
-        let (m, n) = (matrix.dims[0], matrix.dims[1]);
-        debug_assert!(m >= n, "Primal initializer not yet created");
-        debug_assert_eq!(c.len(), n);
-        debug_assert_eq!(b.len(), m);
-        let x = vec![1.0; n];
-        let basis = (2 * m + n..2 * m + 2 * n).collect();
-        LinearProgram {
-            class: ProgramClass::Dual,
-            m,
-            n,
+    /// new
+    /// variables default to 0 <= x < inf
+    pub fn new(sense: Sense, c: Vec<f32>) -> Self {
+        let n = c.len();
+        Self {
+            sense,
             c,
-            b,
-            x,
-            basis,
-            constraint: DualLinear::transform(matrix),
+            a: NdArray::new(vec![0, n], vec![]),
+            relations: vec![],
+            b: vec![],
+            lower: vec![0f32; n],
+            upper: vec![f32::INFINITY; n],
         }
     }
-    fn setup_phase_one(&mut self) {
-        for (idx, val) in (2 * self.m + self.n..2 * self.m + 2 * self.n).enumerate() {
-            self.basis[idx] = val;
-        }
+    pub fn with_constraint(mut self, row: Vec<f32>, relation: Relation, rhs: f32) -> Self {
+        debug_assert_eq!(row.len(), self.c.len());
+        self.a.data.extend(row);
+        self.a.dims[0] += 1;
+        self.relations.push(relation);
+        self.b.push(rhs);
+        self
+    }
+    pub fn with_bounds(mut self, j: usize, lower: f32, upper: f32) -> Self {
+        self.lower[j] = lower;
+        self.upper[j] = upper;
+        self
     }
-    pub fn get_basis_matrix(&self) -> NdArray {
-        let mut data = Vec::with_capacity(self.n * self.n);
-        let n_cols = 2 * self.m + 2 * self.n;
-        for row in 0..self.n {
-            for &col in &self.basis {
-                data.push(self.constraint.data[row * n_cols + col]);
+    pub fn with_free(self, j: usize) -> Self {
+        self.with_bounds(j, f32::NEG_INFINITY, f32::INFINITY)
+    }
+    pub fn standard_form(&self) -> StandardForm {
+        let (m, n) = (self.a.dims[0], self.a.dims[1]);
+        let sign = match self.sense {
+            Sense::Minimize => 1f32,
+            Sense::Maximize => -1f32,
+        };
+        // x = l + x', x = u - x' or x = x+ - x-, finite ranges become rows x' <= u - l
+        let mut columns = Vec::with_capacity(n);
+        let mut ranges = vec![];
+        let mut structural = 0;
+        for j in 0..n {
+            let (l, u) = (self.lower[j], self.upper[j]);
+            if l.is_finite() {
+                columns.push(Column::Shifted(structural, l));
+                if u.is_finite() {
+                    ranges.push((structural, u - l));
+                }
+                structural += 1;
+            } else if u.is_finite() {
+                columns.push(Column::Mirrored(structural, u));
+                structural += 1;
+            } else {
+                columns.push(Column::Split(structural, structural + 1));
+                structural += 2;
             }
         }
-        NdArray {
-            dims: vec![self.n, self.n],
-            data,
+        let rows = m + ranges.len();
+        let slacks = self
+            .relations
+            .iter()
+            .filter(|&&r| r != Relation::Equal)
+            .count()
+            + ranges.len();
+        let cols = structural + slacks;
+
+        let mut c = vec![0f32; cols];
+        for (j, column) in columns.iter().enumerate() {
+            match *column {
+                Column::Shifted(col, _) => c[col] += sign * self.c[j],
+                Column::Mirrored(col, _) => c[col] -= sign * self.c[j],
+                Column::Split(pos, neg) => {
+                    c[pos] += sign * self.c[j];
+                    c[neg] -= sign * self.c[j];
+                }
+            }
         }
-    }
-    pub fn get_basis_matrix_transpose(&self) -> NdArray {
-        let mut data = Vec::with_capacity(self.n * self.n);
-        let n_cols = 2 * self.m + 2 * self.n;
-        for &col in &self.basis {
-            for row in 0..self.n {
-                data.push(self.constraint.data[row * n_cols + col]);
+        let mut a = NdArray::new(vec![rows, cols], vec![0f32; rows * cols]);
+        let mut b = vec![0f32; rows];
+        let mut slack = vec![None; rows];
+        let mut next_slack = structural;
+        for i in 0..m {
+            let row = &mut a.data[i * cols..(i + 1) * cols];
+            let mut rhs = self.b[i];
+            for (j, column) in columns.iter().enumerate() {
+                let v = self.a.data[i * n + j];
+                if v == 0f32 {
+                    continue;
+                }
+                match *column {
+                    Column::Shifted(col, offset) => {
+                        row[col] += v;
+                        rhs -= v * offset;
+                    }
+                    Column::Mirrored(col, offset) => {
+                        row[col] -= v;
+                        rhs -= v * offset;
+                    }
+                    Column::Split(pos, neg) => {
+                        row[pos] += v;
+                        row[neg] -= v;
+                    }
+                }
+            }
+            match self.relations[i] {
+                Relation::LessEqual => row[next_slack] = 1f32,
+                Relation::GreaterEqual => row[next_slack] = -1f32,
+                Relation::Equal => {}
+            }
+            if self.relations[i] != Relation::Equal {
+                slack[i] = Some(next_slack);
+                next_slack += 1;
             }
+            b[i] = rhs;
         }
-        NdArray {
-            dims: vec![self.n, self.n],
-            data,
+        for (k, &(col, width)) in ranges.iter().enumerate() {
+            let i = m + k;
+            a.data[i * cols + col] = 1f32;
+            a.data[i * cols + next_slack] = 1f32;
+            slack[i] = Some(next_slack);
+            next_slack += 1;
+            b[i] = width;
+        }
+        let mut row_sign = vec![1f32; rows];
+        for i in 0..rows {
+            if b[i] < 0f32 {
+                a.data[i * cols..(i + 1) * cols]
+                    .iter_mut()
+                    .for_each(|v| *v = -*v);
+                b[i] = -b[i];
+                row_sign[i] = -1f32;
+            }
+        }
+        StandardForm {
+            a,
+            b,
+            c,
+            row_sign,
+            slack,
+            columns,
         }
     }
-    fn pivot(&mut self, entering_idx: usize, leaving_idx: usize) {
-        println!("in pivot {entering_idx:?}, {leaving_idx:?}");
-        self.basis[leaving_idx] = entering_idx;
+    pub fn solve(&self) -> LpSolution {
+        self.solve_with(Pricing::SteepestEdge)
     }
-    fn compute_direction(&self, entering_idx: usize, workspace: &mut [f32]) -> Vec<f32> {
-        // travels in the inverse of this vector
-        let b_t = self.get_basis_matrix_transpose();
-        let lu = LuPivotDecompose::new(b_t, workspace);
-        let n_cols = 2 * self.n + 2 * self.m;
-
-        let mut a_j = vec![0.0; self.n];
-        for i in 0..self.n {
-            // column basis vector
-            // a_j[i] = self.constraint.data[i * n_cols + entering_idx];
-            a_j[i] = self.constraint.data[i * n_cols + entering_idx];
-        }
-        lu.solve_inplace_vec(&mut a_j);
-        a_j
-    }
-    fn select_entering_variable(&self, delta_costs: &[f32]) -> Option<usize> {
-        // option returned in order to get terminating condition
-        let mut best_idx = None;
-        let mut best_val = EPSILON;
+    pub fn solve_with(&self, pricing: Pricing) -> LpSolution {
+        let form = self.standard_form();
+        let mut simplex = RevisedSimplex::new(&form).with_pricing(pricing);
+        let status = match simplex
+            .run_phase_one()
+            .and_then(|_| simplex.run_phase_two())
+        {
+            Ok(()) => LpStatus::Optimal,
+            Err(status) => status,
+        };
+        self.report(
+            &form,
+            status,
+            &simplex.primal(),
+            &simplex.duals(),
+            None,
+            simplex.iterations,
+        )
+    }
+    /// report
+    /// LpSolution from a standard form point x and row multipliers y, q adds 1/2 x'Qx to the
+    /// objective
+    pub(crate) fn report(
+        &self,
+        form: &StandardForm,
+        status: LpStatus,
+        x: &[f32],
+        y: &[f32],
+        q: Option<&NdArray>,
+        iterations: usize,
+    ) -> LpSolution {
+        let x = form.recover(x);
+        // gradient c + Qx of the objective
+        let mut gradient = self.c.clone();
+        if let Some(q) = q {
+            let mut qx = vec![0f32; x.len()];
+            q.apply(&x, &mut qx);
+            gradient.iter_mut().zip(&qx).for_each(|(g, v)| *g += v);
+        }
+        let objective = x
+            .iter()
+            .zip(self.c.iter().zip(&gradient))
+            .map(|(x, (c, g))| 0.5 * x * (c + g))
+            .sum();
+        // the standard form minimizes sign * f(x) over rows scaled by row_sign
+        let sign = match self.sense {
+            Sense::Minimize => 1f32,
+            Sense::Maximize => -1f32,
+        };
+        let duals: Vec<f32> = (0..self.a.dims[0])
+            .map(|i| sign * form.row_sign[i] * y[i])
+            .collect();
+        let mut reduced_costs = vec![0f32; self.c.len()];
+        self.a.apply_transpose(&duals, &mut reduced_costs);
+        reduced_costs
+            .iter_mut()
+            .zip(&gradient)
+            .for_each(|(d, g)| *d = g - *d);
+        LpSolution {
+            status,
+            x,
+            objective,
+            duals,
+            reduced_costs,
+            iterations,
+        }
+    }
+}
 
-        for idx in 0..delta_costs.len() {
-            if delta_costs[idx] > best_val {
-                best_val = delta_costs[idx];
-                best_idx = Some(idx);
+impl StandardForm {
+    /// recover
+    /// original variables from a standard form point
+    pub fn recover(&self, x: &[f32]) -> Vec<f32> {
+        self.columns
+            .iter()
+            .map(|column| match *column {
+                Column::Shifted(col, offset) => offset + x[col],
+                Column::Mirrored(col, offset) => offset - x[col],
+                Column::Split(pos, neg) => x[pos] - x[neg],
+            })
+            .collect()
+    }
+    /// embedding
+    /// T and o with original variables x = o + Tz for a standard form point z
+    pub fn embedding(&self) -> (NdArray, Vec<f32>) {
+        let (n, cols) = (self.columns.len(), self.a.dims[1]);
+        let mut t = NdArray::new(vec![n, cols], vec![0f32; n * cols]);
+        let mut offset = vec![0f32; n];
+        for (j, column) in self.columns.iter().enumerate() {
+            match *column {
+                Column::Shifted(col, o) => {
+                    t.data[j * cols + col] = 1f32;
+                    offset[j] = o;
+                }
+                Column::Mirrored(col, o) => {
+                    t.data[j * cols + col] = -1f32;
+                    offset[j] = o;
+                }
+                Column::Split(pos, neg) => {
+                    t.data[j * cols + pos] = 1f32;
+                    t.data[j * cols + neg] = -1f32;
+                }
             }
         }
-        best_idx
+        (t, offset)
+    }
+    /// with_row
+    /// appends the row a'x (<=, >=, =) rhs over the standard form columns ~ cols, the slack
+    /// becomes the last column so every existing row and column keeps its index
+    pub fn with_row(&self, row: &[f32], relation: Relation, rhs: f32) -> StandardForm {
+        let (rows, cols) = (self.a.dims[0], self.a.dims[1]);
+        debug_assert_eq!(row.len(), cols);
+        let new_cols = cols + (relation != Relation::Equal) as usize;
+        let mut data = vec![0f32; (rows + 1) * new_cols];
+        for i in 0..rows {
+            data[i * new_cols..i * new_cols + cols]
+                .copy_from_slice(&self.a.data[i * cols..(i + 1) * cols]);
+        }
+        let last = &mut data[rows * new_cols..];
+        last[..cols].copy_from_slice(row);
+        let mut slack = self.slack.clone();
+        match relation {
+            Relation::LessEqual => last[cols] = 1f32,
+            Relation::GreaterEqual => last[cols] = -1f32,
+            Relation::Equal => {}
+        }
+        slack.push((relation != Relation::Equal).then_some(cols));
+        let (mut b, mut row_sign) = (self.b.clone(), self.row_sign.clone());
+        if rhs < 0f32 {
+            last.iter_mut().for_each(|v| *v = -*v);
+        }
+        b.push(rhs.abs());
+        row_sign.push(if rhs < 0f32 { -1f32 } else { 1f32 });
+        let mut c = self.c.clone();
+        c.resize(new_cols, 0f32);
+        StandardForm {
+            a: NdArray::new(vec![rows + 1, new_cols], data),
+            b,
+            c,
+            row_sign,
+            slack,
+            columns: self.columns.clone(),
+        }
+    }
+    /// free_pairs
+    /// (positive, negative) standard form columns of every split free variable
+    pub fn free_pairs(&self) -> Vec<(usize, usize)> {
+        self.columns
+            .iter()
+            .filter_map(|column| match *column {
+                Column::Split(pos, neg) => Some((pos, neg)),
+                _ => None,
+            })
+            .collect()
     }
-    fn compute_phase_one_delta_cost(&self, workspace: &mut [f32]) -> Vec<f32> {
-        let b_t = self.get_basis_matrix_transpose();
-        let lu = LuPivotDecompose::new(b_t, workspace);
-        let n_cols = 2 * self.m + 2 * self.n;
+}
 
-        let mut cost_b = vec![0.0; self.n];
-        for (i, &basis_idx) in self.basis.iter().enumerate() {
-            if basis_idx >= 2 * self.m + self.n {
-                cost_b[i] = 1.0;
+// B^-1 = E_k^-1 ... E_1^-1 (LU)^-1 where E_i is the identity with column r replaced by
+// alpha = B^-1 a_q of the pivot that brought a_q into position r
+struct BasisFactor {
+    lu: LuPivotDecompose,
+    etas: Vec<(usize, Vec<f32>)>,
+}
+
+impl BasisFactor {
+    fn new(a: &NdArray, basis: &[usize]) -> Self {
+        let (m, cols) = (a.dims[0], a.dims[1]);
+        let mut data = vec![0f32; m * m];
+        for i in 0..m {
+            for (k, &j) in basis.iter().enumerate() {
+                data[i * m + k] = a.data[i * cols + j];
             }
         }
-        lu.solve_inplace_vec(&mut cost_b);
-        let mut delta = vec![0.0; n_cols];
-        for j in 0..n_cols {
-            if self.basis.contains(&j) {
-                continue;
-            }
-            // penalize if in artificial
-            let c_j = if j >= 2 * self.m + self.n { 1.0 } else { 0.0 };
-            let mut y_dot_aj = 0.0;
-            for i in 0..self.n {
-                y_dot_aj += cost_b[i] * self.constraint.data[i * n_cols + j];
-            }
-            delta[j] = y_dot_aj - c_j;
-            // delta[j] = c_j - y_dot_aj;
+        let lu = LuPivotDecompose::new(NdArray::new(vec![m, m], data), &mut vec![0f32; m]);
+        Self { lu, etas: vec![] }
+    }
+    // x <- B^-1 x
+    fn ftran(&self, x: &mut [f32]) {
+        self.lu.solve_inplace_vec(x);
+        for (r, alpha) in &self.etas {
+            let xr = x[*r] / alpha[*r];
+            x.iter_mut().zip(alpha).for_each(|(xi, ai)| *xi -= ai * xr);
+            x[*r] = xr;
+        }
+    }
+    // y <- B^-T y, E^-T only changes entry r
+    fn btran(&self, y: &mut [f32]) {
+        for (r, alpha) in self.etas.iter().rev() {
+            let off: f32 = y
+                .iter()
+                .zip(alpha)
+                .enumerate()
+                .filter(|&(i, _)| i != *r)
+                .map(|(_, (yi, ai))| yi * ai)
+                .sum();
+            y[*r] = (y[*r] - off) / alpha[*r];
         }
-        delta
+        self.lu.solve_transpose_inplace_vec(y);
     }
-    fn compute_phase_two_delta_cost(&self, workspace: &mut [f32]) -> Vec<f32> {
-        let b_t = self.get_basis_matrix_transpose();
-        let lu = LuPivotDecompose::new(b_t, workspace);
-        let n_cols = 2 * self.m + 2 * self.n;
+}
 
-        let mut dual_costs = vec![0.0; n_cols];
-        for i in 0..self.m {
-            dual_costs[i] = self.b[i];
-            dual_costs[i + self.m] = -self.b[i];
-        }
-        let mut cost_b = vec![0.0; self.n];
-        for (idx, &basis_idx) in self.basis.iter().enumerate() {
-            cost_b[idx] = dual_costs[basis_idx];
-        }
-        let mut y = cost_b;
-        println!("y {y:?}");
-        lu.solve_inplace_vec(&mut y);
-        let mut delta = vec![0.0; n_cols];
+/// RevisedSimplex
+/// two phase revised simplex on a StandardForm, the basis is kept as an LU with eta updates
+/// and factored again every few pivots, phase one starts from the slacks and one artificial
+/// column per row without a usable slack
+///
+/// * pricing: entering column rule
+/// * max_iterations: pivot limit over both phases
+/// * iterations: pivots so far
+/// * basis: basic column of every row
+pub struct RevisedSimplex {
+    pub pricing: Pricing,
+    pub max_iterations: usize,
+    pub iterations: usize,
+    pub basis: Vec<usize>,
+    // standard form columns followed by the artificials
+    a: NdArray,
+    b: Vec<f32>,
+    c: Vec<f32>,
+    artificial: usize,
+    position: Vec<Option<usize>>,
+    x_b: Vec<f32>,
+    factor: BasisFactor,
+    // 1 + ||B^-1 a_j||^2 for nonbasic j
+    weights: Vec<f32>,
+}
 
-        println!("dual costs {dual_costs:?}");
-        println!("y {y:?}");
-        for j in 0..n_cols {
-            if self.basis.contains(&j) {
+impl RevisedSimplex {
+    pub fn new(form: &StandardForm) -> Self {
+        let (m, n) = (form.a.dims[0], form.a.dims[1]);
+        let needs_artificial: Vec<usize> = (0..m)
+            .filter(|&i| !matches!(form.slack[i], Some(s) if form.a.data[i * n + s] == 1f32))
+            .collect();
+        let cols = n + needs_artificial.len();
+        let mut data = vec![0f32; m * cols];
+        for i in 0..m {
+            data[i * cols..i * cols + n].copy_from_slice(&form.a.data[i * n..(i + 1) * n]);
+        }
+        let mut basis: Vec<usize> = form.slack.iter().map(|s| s.unwrap_or(0)).collect();
+        for (k, &i) in needs_artificial.iter().enumerate() {
+            data[i * cols + n + k] = 1f32;
+            basis[i] = n + k;
+        }
+        let a = NdArray::new(vec![m, cols], data);
+        let mut position = vec![None; cols];
+        for (i, &j) in basis.iter().enumerate() {
+            position[j] = Some(i);
+        }
+        let mut c = form.c.clone();
+        c.resize(cols, 0f32);
+        // the initial basis is the identity
+        let factor = BasisFactor::new(&a, &basis);
+        Self {
+            pricing: Pricing::SteepestEdge,
+            max_iterations: 50 * (m + cols).max(1),
+            iterations: 0,
+            basis,
+            a,
+            b: form.b.clone(),
+            c,
+            artificial: n,
+            position,
+            x_b: form.b.clone(),
+            factor,
+            weights: vec![1f32; cols],
+        }
+    }
+    /// from_basis
+    /// starts from a given basis of the standard form columns without artificials, the basis
+    /// of a solved problem stays dual feasible after rows with basic slacks are appended, see
+    /// run_dual
+    pub fn from_basis(form: &StandardForm, basis: Vec<usize>) -> Self {
+        let (m, n) = (form.a.dims[0], form.a.dims[1]);
+        debug_assert!(basis.len() == m && basis.iter().all(|&j| j < n));
+        let mut position = vec![None; n];
+        for (i, &j) in basis.iter().enumerate() {
+            position[j] = Some(i);
+        }
+        let factor = BasisFactor::new(&form.a, &basis);
+        let mut x_b = form.b.clone();
+        factor.ftran(&mut x_b);
+        Self {
+            pricing: Pricing::SteepestEdge,
+            max_iterations: 50 * (m + n).max(1),
+            iterations: 0,
+            basis,
+            a: form.a.clone(),
+            b: form.b.clone(),
+            c: form.c.clone(),
+            artificial: n,
+            position,
+            x_b,
+            factor,
+            weights: vec![1f32; n],
+        }
+    }
+    pub fn with_pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = pricing;
+        self
+    }
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+    /// run_phase_one
+    /// minimizes the sum of the artificials, then pivots the zero artificials out of the
+    /// basis where the row is not redundant
+    pub fn run_phase_one(&mut self) -> Result<(), LpStatus> {
+        let cols = self.a.dims[1];
+        if self.artificial == cols {
+            return Ok(());
+        }
+        let cost: Vec<f32> = (0..cols)
+            .map(|j| if j >= self.artificial { 1f32 } else { 0f32 })
+            .collect();
+        self.iterate(&cost)?;
+        self.refactor();
+        let infeasibility: f32 = self
+            .basis
+            .iter()
+            .zip(&self.x_b)
+            .filter(|&(&j, _)| j >= self.artificial)
+            .map(|(_, x)| x.abs())
+            .sum();
+        let scale = self.b.iter().fold(1f32, |acc, b| acc.max(b.abs()));
+        if infeasibility > FEASIBILITY_TOLERANCE * scale {
+            return Err(LpStatus::Infeasible);
+        }
+        for r in 0..self.basis.len() {
+            if self.basis[r] < self.artificial {
                 continue;
             }
-            let mut y_dot_ai = 0.0;
-            for i in 0..self.n {
-                y_dot_ai += y[i] * self.constraint.data[i * n_cols + j];
-            }
-            // for maximization want positive delta
-            delta[j] = dual_costs[j] - y_dot_ai;
-            // delta[j] = y_dot_ai - dual_costs[j];
-        }
-        delta
-    }
-    fn ratio_test(&self, direction: &[f32], workspace: &mut [f32]) -> Option<usize> {
-        let x_b = self.get_basic_solution(workspace);
-        let mut min_ratio = f32::INFINITY;
-        let mut idx_leaving = None;
-        println!("direction {direction:?}");
-        for idx in 0..x_b.len() {
-            if direction[idx] > EPSILON {
-                let ratio = x_b[idx] / direction[idx];
-                if ratio < min_ratio {
-                    println!("better");
-                    min_ratio = ratio;
-                    idx_leaving = Some(idx);
-                }
+            let row = self.tableau_row(r);
+            let entering = (0..self.artificial)
+                .filter(|&j| self.position[j].is_none())
+                .max_by(|&i, &j| row[i].abs().total_cmp(&row[j].abs()));
+            // otherwise the row is redundant and its artificial stays basic at zero
+            if let Some(q) = entering.filter(|&q| row[q].abs() > PIVOT_TOLERANCE) {
+                let alpha = self.column(q);
+                let theta = self.x_b[r] / alpha[r];
+                self.pivot(q, r, alpha, theta);
             }
         }
-        idx_leaving
+        self.refactor();
+        Ok(())
     }
-    fn get_basic_solution(&self, workspace: &mut [f32]) -> Vec<f32> {
-        let b = self.get_basis_matrix();
-        let lu = LuPivotDecompose::new(b, workspace);
-        let mut rhs = self.c.clone();
-        lu.solve_inplace_vec(&mut rhs);
-        rhs
+    pub fn run_phase_two(&mut self) -> Result<(), LpStatus> {
+        let cost = self.c.clone();
+        self.iterate(&cost)?;
+        self.refactor();
+        Ok(())
     }
-    pub fn run_phase_one(&mut self, workspace: &mut [f32]) -> Result<(), String> {
-        self.setup_phase_one();
+    /// run_dual
+    /// dual simplex from a dual feasible basis with negative basic values, the most
+    /// infeasible row leaves and the entering column keeps the reduced costs nonnegative
+    pub fn run_dual(&mut self) -> Result<(), LpStatus> {
+        let cost = self.c.clone();
         loop {
-            let delta = self.compute_phase_one_delta_cost(workspace);
-            let entering = match self.select_entering_variable(&delta) {
-                Some(idx) => idx,
-                None => break,
+            let leaving = (0..self.basis.len())
+                .filter(|&i| self.x_b[i] < -FEASIBILITY_TOLERANCE)
+                .min_by(|&i, &j| self.x_b[i].total_cmp(&self.x_b[j]));
+            let Some(r) = leaving else {
+                return Ok(());
             };
-            let direction = self.compute_direction(entering, workspace);
-            println!("direction {direction:?}");
-            let leaving = match self.ratio_test(&direction, workspace) {
-                Some(idx) => idx,
-                None => return Err(format!("Unbounded should not happen in phase 1").into()),
+            if self.iterations >= self.max_iterations {
+                return Err(LpStatus::IterationLimit);
+            }
+            let d = self.reduced_costs(&cost);
+            let row = self.tableau_row(r);
+            // min d_j / -row_j over row_j < 0, ties go to the larger pivot
+            let mut entering: Option<(usize, f32)> = None;
+            for j in (0..self.artificial).filter(|&j| self.position[j].is_none()) {
+                if row[j] >= -PIVOT_TOLERANCE {
+                    continue;
+                }
+                let ratio = d[j].max(0f32) / -row[j];
+                let better = match entering {
+                    None => true,
+                    Some((k, t)) => {
+                        let tie = (ratio - t).abs() <= 1e-7 * (1f32 + t);
+                        if tie { row[j] < row[k] } else { ratio < t }
+                    }
+                };
+                if better {
+                    entering = Some((j, ratio));
+                }
+            }
+            // the row reads x_r = negative value + nonnegative terms
+            let Some((q, _)) = entering else {
+                return Err(LpStatus::Infeasible);
             };
-            self.pivot(entering, leaving)
+            let alpha = self.column(q);
+            let theta = self.x_b[r] / alpha[r];
+            self.pivot(q, r, alpha, theta);
         }
-        let x = self.get_basic_solution(workspace);
-        for (idx, &basic_idx) in self.basis.iter().enumerate() {
-            if basic_idx >= 2 * self.m + self.n {
-                if x[idx].abs() > EPSILON {
-                    return Err(format!("Infeasable Problem").into());
-                }
+    }
+    /// primal
+    /// current basic solution in the standard form columns
+    pub fn primal(&self) -> Vec<f32> {
+        let mut x = vec![0f32; self.artificial];
+        for (&j, &v) in self.basis.iter().zip(&self.x_b) {
+            if j < self.artificial {
+                x[j] = v;
             }
         }
-        Ok(())
+        x
+    }
+    /// duals
+    /// y = B^-T c_B of the phase two costs
+    pub fn duals(&self) -> Vec<f32> {
+        let mut y: Vec<f32> = self.basis.iter().map(|&j| self.c[j]).collect();
+        self.factor.btran(&mut y);
+        y
+    }
+    // B^-1 a_j
+    fn column(&self, j: usize) -> Vec<f32> {
+        let cols = self.a.dims[1];
+        let mut alpha: Vec<f32> = self.a.data.iter().skip(j).step_by(cols).copied().collect();
+        self.factor.ftran(&mut alpha);
+        alpha
+    }
+    /// tableau_row
+    /// e_r' B^-1 A over the standard form columns followed by the artificials
+    pub fn tableau_row(&self, r: usize) -> Vec<f32> {
+        let mut rho = vec![0f32; self.basis.len()];
+        rho[r] = 1f32;
+        self.factor.btran(&mut rho);
+        let mut row = vec![0f32; self.a.dims[1]];
+        self.a.apply_transpose(&rho, &mut row);
+        row
+    }
+    // d = c - A'B^-T c_B
+    fn reduced_costs(&self, cost: &[f32]) -> Vec<f32> {
+        let mut y: Vec<f32> = self.basis.iter().map(|&j| cost[j]).collect();
+        self.factor.btran(&mut y);
+        let mut d = vec![0f32; self.a.dims[1]];
+        self.a.apply_transpose(&y, &mut d);
+        d.iter_mut().zip(cost).for_each(|(dj, cj)| *dj = cj - *dj);
+        d
+    }
+    fn refactor(&mut self) {
+        self.factor = BasisFactor::new(&self.a, &self.basis);
+        self.x_b = self.b.clone();
+        self.factor.ftran(&mut self.x_b);
     }
-    pub fn run_phase_two(&mut self) -> Result<Vec<f32>, String> {
-        let mut workspace = vec![0f32; 64];
-        println!("Basis indices at start of Phase 2: {:?}", self.basis);
-        let b = self.get_basis_matrix();
-        println!("B {b:?}");
+    fn iterate(&mut self, cost: &[f32]) -> Result<(), LpStatus> {
+        let m = self.basis.len();
+        if self.pricing == Pricing::SteepestEdge {
+            for j in 0..self.artificial {
+                if self.position[j].is_none() {
+                    self.weights[j] = 1f32 + self.column(j).iter().map(|a| a * a).sum::<f32>();
+                }
+            }
+        }
+        let mut degenerate = 0;
         loop {
-            let delta = self.compute_phase_two_delta_cost(&mut workspace);
-            println!("delta");
-            let entering = match self.select_entering_variable(&delta) {
-                Some(idx) => idx,
-                None => break,
+            let d = self.reduced_costs(cost);
+            let bland = self.pricing == Pricing::Bland || degenerate > m;
+            let mut candidates = (0..self.artificial)
+                .filter(|&j| self.position[j].is_none() && d[j] < -OPTIMALITY_TOLERANCE);
+            let entering = if bland {
+                candidates.next()
+            } else {
+                candidates.max_by(|&i, &j| {
+                    (d[i] * d[i] / self.weights[i]).total_cmp(&(d[j] * d[j] / self.weights[j]))
+                })
             };
-            println!("entering");
-            let direction = self.compute_direction(entering, &mut workspace);
-            let leaving = match self.ratio_test(&direction, &mut workspace) {
-                Some(idx) => idx,
-                None => return Err(format!("Unbounded").into()),
+            let Some(q) = entering else {
+                return Ok(());
+            };
+            if self.iterations >= self.max_iterations {
+                return Err(LpStatus::IterationLimit);
+            }
+            let alpha = self.column(q);
+            let Some(r) = self.ratio_test(&alpha, bland) else {
+                return Err(LpStatus::Unbounded);
             };
-            println!("pivot");
-            self.pivot(entering, leaving);
+            let theta = self.x_b[r].max(0f32) / alpha[r];
+            if theta * alpha[r] <= FEASIBILITY_TOLERANCE {
+                degenerate += 1;
+            } else {
+                degenerate = 0;
+            }
+            if self.pricing == Pricing::SteepestEdge {
+                self.update_weights(q, r, &alpha);
+            }
+            self.pivot(q, r, alpha, theta);
+        }
+    }
+    // min x_b[i] / alpha[i] over alpha[i] > 0, ties go to the larger pivot or under bland to
+    // the lower column index
+    fn ratio_test(&self, alpha: &[f32], bland: bool) -> Option<usize> {
+        let mut best: Option<(usize, f32)> = None;
+        for (i, &a) in alpha.iter().enumerate() {
+            if a <= PIVOT_TOLERANCE {
+                continue;
+            }
+            let ratio = self.x_b[i].max(0f32) / a;
+            best = match best {
+                None => Some((i, ratio)),
+                Some((k, t)) => {
+                    let tie = (ratio - t).abs() <= 1e-7 * (1f32 + t);
+                    let better = if tie && bland {
+                        self.basis[i] < self.basis[k]
+                    } else if tie {
+                        a > alpha[k]
+                    } else {
+                        ratio < t
+                    };
+                    if better { Some((i, ratio)) } else { best }
+                }
+            };
+        }
+        best.map(|(i, _)| i)
+    }
+    // goldfarb reid recurrences with the old basis
+    // g_j <- max(g_j - 2 (a_rj / a_rq) a_j'B^-T alpha + (a_rj / a_rq)^2 g_q, 1 + (a_rj / a_rq)^2)
+    fn update_weights(&mut self, q: usize, r: usize, alpha: &[f32]) {
+        let cols = self.a.dims[1];
+        let row = self.tableau_row(r);
+        let mut w = alpha.to_vec();
+        self.factor.btran(&mut w);
+        let mut aw = vec![0f32; cols];
+        self.a.apply_transpose(&w, &mut aw);
+        let gamma = 1f32 + alpha.iter().map(|a| a * a).sum::<f32>();
+        for j in 0..self.artificial {
+            if j == q || self.position[j].is_some() || row[j] == 0f32 {
+                continue;
+            }
+            let ratio = row[j] / alpha[r];
+            self.weights[j] = (self.weights[j] - 2f32 * ratio * aw[j] + ratio * ratio * gamma)
+                .max(1f32 + ratio * ratio);
+        }
+        let leaving = self.basis[r];
+        self.weights[leaving] = (gamma / (alpha[r] * alpha[r])).max(1f32);
+    }
+    fn pivot(&mut self, q: usize, r: usize, alpha: Vec<f32>, theta: f32) {
+        self.x_b
+            .iter_mut()
+            .zip(&alpha)
+            .for_each(|(x, a)| *x -= theta * a);
+        self.x_b[r] = theta;
+        self.position[self.basis[r]] = None;
+        self.position[q] = Some(r);
+        self.basis[r] = q;
+        self.iterations += 1;
+        self.factor.etas.push((r, alpha));
+        if self.factor.etas.len() >= REFACTOR_PERIOD {
+            self.refactor();
         }
-        Ok(self.get_basic_solution(&mut workspace))
     }
 }
 
-pub enum Phase {
-    FindVertex,
-    FindOptimum,
-}
+#[cfg(test)]
+mod test_linear {
+    use super::*;
+    use crate::random::generation::generate_random_matrix;
 
-// fn main() {
-//     // let thing = vec![vec![3], vec![1,3], vec![2], vec![2,3], vec![0,2], vec![0,1]];
-//     let c = vec![1.0; 4];
-//     let b = vec![3.0, 5.0, 4.0, 7.0];
-//     let matrix = NdArray::new(
-//         vec![4, 4],
-//         vec![
-//             0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0,
-//             0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0,
-//         ],
-//     );
-//     let mut lp = LinearProgram::new(c,  b, matrix);
-//     println!("phase one");
-//     let res_one = lp.run_phase_one();
-//     println!("phase two");
-//     let res_two = lp.run_phase_two();
-//     match (res_one, res_two) {
-//         (Ok(r1), Ok(r2)) => {
-//             println!("Successful run of both!");
-//             println!("Result {r2:?}");
-//         },
-//         (Err(e1), Err(e2)) => {
-//             println!("Unsucessful\ne1: {e1:?}\ne2: {e2:?}");
-//         },
-//         (Err(e1), Ok(_)) => {
-//             println!("Unsucessful\ne1: {e1:?}");
-//         }
-//         (Ok(_), Err(e2)) => {
-//             println!("Unsucessful\ne2: {e2:?}");
-//         }
-//     }
-//     // // test_gmm_3d_kmeans_gmm();
-//     // // test_gmm_3d();
-//     // if let Err(e) = test_kmeans_gmm_visual() {
-//     //     eprintln!("Error: {}", e);
-//     // }
-// }
+    fn assert_close(x: &[f32], y: &[f32], tol: f32) {
+        for (a, b) in x.iter().zip(y) {
+            assert!((a - b).abs() < tol, "{x:?} {y:?}");
+        }
+    }
+
+    #[test]
+    fn test_maximize_with_duals() {
+        // max 3x + 5y s.t. x <= 4, 2y <= 12, 3x + 2y <= 18
+        for pricing in [Pricing::Bland, Pricing::SteepestEdge] {
+            let lp = LinearProgram::new(Sense::Maximize, vec![3f32, 5f32])
+                .with_constraint(vec![1f32, 0f32], Relation::LessEqual, 4f32)
+                .with_constraint(vec![0f32, 2f32], Relation::LessEqual, 12f32)
+                .with_constraint(vec![3f32, 2f32], Relation::LessEqual, 18f32);
+            let solution = lp.solve_with(pricing);
+            assert_eq!(solution.status, LpStatus::Optimal);
+            assert_close(&solution.x, &[2f32, 6f32], 1e-4);
+            assert!((solution.objective - 36f32).abs() < 1e-3);
+            assert_close(&solution.duals, &[0f32, 1.5f32, 1f32], 1e-4);
+            assert_close(&solution.reduced_costs, &[0f32, 0f32], 1e-4);
+        }
+    }
+    #[test]
+    fn test_bounds_free_and_equality() {
+        // min x0 + 2 x1 - x2 s.t. x0 + x1 + x2 = 4, x0 - x2 >= -3,
+        // 1 <= x0 <= 5, x1 free, x2 <= 2
+        let lp = LinearProgram::new(Sense::Minimize, vec![1f32, 2f32, -1f32])
+            .with_constraint(vec![1f32, 1f32, 1f32], Relation::Equal, 4f32)
+            .with_constraint(vec![1f32, 0f32, -1f32], Relation::GreaterEqual, -3f32)
+            .with_constraint(vec![0f32, 1f32, 0f32], Relation::GreaterEqual, -1f32)
+            .with_bounds(0, 1f32, 5f32)
+            .with_free(1)
+            .with_bounds(2, f32::NEG_INFINITY, 2f32);
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        // x1 = 4 - x0 - x2 makes the cost 8 - x0 - 3 x2, x2 sits at its bound and x0 grows
+        // until x1 reaches -1
+        assert_close(&solution.x, &[3f32, -1f32, 2f32], 1e-4);
+        assert!((solution.objective + 1f32).abs() < 1e-3);
+        // stationarity c = A'y + reduced costs with reduced costs zero off the bounds
+        assert!(solution.reduced_costs[0].abs() < 1e-4);
+        assert!(solution.reduced_costs[1].abs() < 1e-4);
+        assert!(solution.reduced_costs[2] < 1e-4);
+    }
+    #[test]
+    fn test_infeasible_and_unbounded() {
+        let infeasible = LinearProgram::new(Sense::Minimize, vec![1f32, 1f32])
+            .with_constraint(vec![1f32, 1f32], Relation::LessEqual, 1f32)
+            .with_constraint(vec![1f32, 1f32], Relation::GreaterEqual, 2f32);
+        assert_eq!(infeasible.solve().status, LpStatus::Infeasible);
+        let unbounded = LinearProgram::new(Sense::Maximize, vec![1f32, 0f32]).with_constraint(
+            vec![1f32, -1f32],
+            Relation::LessEqual,
+            1f32,
+        );
+        assert_eq!(unbounded.solve().status, LpStatus::Unbounded);
+        let crossed = LinearProgram::new(Sense::Minimize, vec![1f32]).with_bounds(0, 2f32, 1f32);
+        assert_eq!(crossed.solve().status, LpStatus::Infeasible);
+    }
+    #[test]
+    fn test_redundant_rows_and_phases() {
+        // the second equality repeats the first, its artificial stays basic at zero
+        let lp = LinearProgram::new(Sense::Minimize, vec![1f32, -1f32, 0f32])
+            .with_constraint(vec![1f32, 1f32, 1f32], Relation::Equal, 2f32)
+            .with_constraint(vec![2f32, 2f32, 2f32], Relation::Equal, 4f32)
+            .with_constraint(vec![0f32, 1f32, 0f32], Relation::LessEqual, 1.5f32);
+        let form = lp.standard_form();
+        let mut simplex = RevisedSimplex::new(&form);
+        assert_eq!(simplex.run_phase_one(), Ok(()));
+        let x = form.recover(&simplex.primal());
+        assert!((x.iter().sum::<f32>() - 2f32).abs() < 1e-4);
+        assert_eq!(simplex.run_phase_two(), Ok(()));
+        assert_close(
+            &form.recover(&simplex.primal()),
+            &[0f32, 1.5f32, 0.5f32],
+            1e-4,
+        );
+    }
+    #[test]
+    fn test_dual_warm_start() {
+        // max 3x + 5y from test_maximize_with_duals with the extra row x + y <= 7
+        let lp = LinearProgram::new(Sense::Maximize, vec![3f32, 5f32])
+            .with_constraint(vec![1f32, 0f32], Relation::LessEqual, 4f32)
+            .with_constraint(vec![0f32, 2f32], Relation::LessEqual, 12f32)
+            .with_constraint(vec![3f32, 2f32], Relation::LessEqual, 18f32);
+        let form = lp.standard_form();
+        let mut simplex = RevisedSimplex::new(&form);
+        assert_eq!(simplex.run_phase_one(), Ok(()));
+        assert_eq!(simplex.run_phase_two(), Ok(()));
+        let mut row = vec![0f32; form.a.dims[1]];
+        row[..2].copy_from_slice(&[1f32, 1f32]);
+        let cut = form.with_row(&row, Relation::LessEqual, 7f32);
+        let mut basis = simplex.basis.clone();
+        basis.push(form.a.dims[1]);
+        let mut warm = RevisedSimplex::from_basis(&cut, basis);
+        // x = 2, y = 6 violates the new row
+        assert!(warm.primal()[form.a.dims[1]] < 0f32);
+        assert_eq!(warm.run_dual(), Ok(()));
+        assert_eq!(warm.run_phase_two(), Ok(()));
+        // the optimum moves to x + y = 7 on 2y = 12
+        assert_close(&cut.recover(&warm.primal()), &[1f32, 6f32], 1e-4);
+        row.push(0f32);
+        let infeasible = cut.with_row(&row, Relation::GreaterEqual, 8f32);
+        let mut basis = warm.basis.clone();
+        basis.push(cut.a.dims[1]);
+        let mut warm = RevisedSimplex::from_basis(&infeasible, basis);
+        assert_eq!(warm.run_dual(), Err(LpStatus::Infeasible));
+    }
+    #[test]
+    fn test_random_strong_duality() {
+        // min c'x s.t. Ax >= b with positive data is feasible and bounded
+        let (m, n) = (30, 20);
+        let a = generate_random_matrix(m, n);
+        let mut lp = LinearProgram::new(
+            Sense::Minimize,
+            (0..n).map(|j| 1f32 + (j % 3) as f32).collect(),
+        );
+        for (i, row) in a.data.chunks(n).enumerate() {
+            let row: Vec<f32> = row.iter().map(|v| v.abs() + 0.1).collect();
+            lp = lp.with_constraint(row, Relation::GreaterEqual, 1f32 + (i % 4) as f32);
+        }
+        let bland = lp.solve_with(Pricing::Bland);
+        let steepest = lp.solve_with(Pricing::SteepestEdge);
+        for solution in [&bland, &steepest] {
+            assert_eq!(solution.status, LpStatus::Optimal);
+            let dual_objective: f32 = solution.duals.iter().zip(&lp.b).map(|(y, b)| y * b).sum();
+            let scale = solution.objective.abs().max(1f32);
+            assert!((solution.objective - dual_objective).abs() < 1e-3 * scale);
+            assert!(solution.duals.iter().all(|&y| y > -1e-4));
+            assert!(solution.reduced_costs.iter().all(|&d| d > -1e-4));
+            for (row, b) in lp.a.data.chunks(n).zip(&lp.b) {
+                let ax: f32 = row.iter().zip(&solution.x).map(|(a, x)| a * x).sum();
+                assert!(ax >= b - 1e-3);
+            }
+        }
+        let scale = bland.objective.abs().max(1f32);
+        assert!((bland.objective - steepest.objective).abs() < 1e-3 * scale);
+    }
+}
@@ -0,0 +1,1011 @@
+use crate::solver::linear::{LinearProgram, Relation, Sense};
+use crate::structure::ndarray::NdArray;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+// magnitudes at or above this are infinite in mps bounds
+const MPS_INFINITY: f32 = 1e30;
+// terms per line in written lp expressions
+const TERMS_PER_LINE: usize = 8;
+
+/// ModelInfo
+/// names and integrality carried by the text formats but not by LinearProgram
+///
+/// * name: model name
+/// * objective: objective row name
+/// * variables: column names ~ n
+/// * constraints: row names ~ m
+/// * integer: integrality of every column ~ n
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelInfo {
+    pub name: String,
+    pub objective: String,
+    pub variables: Vec<String>,
+    pub constraints: Vec<String>,
+    pub integer: Vec<bool>,
+}
+
+impl ModelInfo {
+    /// new
+    /// generated names x0.., r0.. and continuous variables
+    pub fn new(lp: &LinearProgram) -> Self {
+        let (m, n) = (lp.a.dims[0], lp.a.dims[1]);
+        Self {
+            name: "model".to_string(),
+            objective: "obj".to_string(),
+            variables: (0..n).map(|j| format!("x{j}")).collect(),
+            constraints: (0..m).map(|i| format!("r{i}")).collect(),
+            integer: vec![false; n],
+        }
+    }
+}
+
+// rows, columns and entries collected by the readers before the dense matrix is built
+struct ModelBuilder {
+    sense: Sense,
+    info: ModelInfo,
+    rows: HashMap<String, usize>,
+    relations: Vec<Relation>,
+    b: Vec<f32>,
+    columns: HashMap<String, usize>,
+    c: Vec<f32>,
+    lower: Vec<f32>,
+    upper: Vec<f32>,
+    entries: Vec<(usize, usize, f32)>,
+}
+
+impl ModelBuilder {
+    fn new() -> Self {
+        Self {
+            sense: Sense::Minimize,
+            info: ModelInfo {
+                name: String::new(),
+                objective: String::new(),
+                variables: vec![],
+                constraints: vec![],
+                integer: vec![],
+            },
+            rows: HashMap::new(),
+            relations: vec![],
+            b: vec![],
+            columns: HashMap::new(),
+            c: vec![],
+            lower: vec![],
+            upper: vec![],
+            entries: vec![],
+        }
+    }
+    fn add_row(&mut self, name: &str, relation: Relation, rhs: f32) -> Result<usize, String> {
+        if self.rows.contains_key(name) {
+            return Err(format!("duplicate row {name}"));
+        }
+        let i = self.relations.len();
+        self.rows.insert(name.to_string(), i);
+        self.info.constraints.push(name.to_string());
+        self.relations.push(relation);
+        self.b.push(rhs);
+        Ok(i)
+    }
+    fn column(&mut self, name: &str) -> usize {
+        if let Some(&j) = self.columns.get(name) {
+            return j;
+        }
+        let j = self.c.len();
+        self.columns.insert(name.to_string(), j);
+        self.info.variables.push(name.to_string());
+        self.info.integer.push(false);
+        self.c.push(0f32);
+        self.lower.push(0f32);
+        self.upper.push(f32::INFINITY);
+        j
+    }
+    fn build(self) -> (LinearProgram, ModelInfo) {
+        let (m, n) = (self.relations.len(), self.c.len());
+        let mut data = vec![0f32; m * n];
+        for (i, j, v) in self.entries {
+            data[i * n + j] += v;
+        }
+        let lp = LinearProgram {
+            sense: self.sense,
+            c: self.c,
+            a: NdArray::new(vec![m, n], data),
+            relations: self.relations,
+            b: self.b,
+            lower: self.lower,
+            upper: self.upper,
+        };
+        (lp, self.info)
+    }
+}
+
+fn parse_number(token: &str, line: usize) -> Result<f32, String> {
+    token
+        .parse::<f32>()
+        .map_err(|_| format!("line {line}: invalid number {token}"))
+}
+
+fn parse_sense(token: &str) -> Option<Sense> {
+    match token.to_ascii_lowercase().as_str() {
+        "max" | "maximize" | "maximise" | "maximum" => Some(Sense::Maximize),
+        "min" | "minimize" | "minimise" | "minimum" => Some(Sense::Minimize),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MpsSection {
+    Start,
+    Name,
+    ObjSense,
+    Rows,
+    Columns,
+    Rhs,
+    Ranges,
+    Bounds,
+}
+
+/// read_mps
+/// free format mps, section headers start in the first column and data lines are indented
+/// ranged rows keep their relation and gain a row named <row>_range for the other side,
+/// a constant on the objective row of the rhs is ignored
+pub fn read_mps(text: &str) -> Result<(LinearProgram, ModelInfo), String> {
+    let mut model = ModelBuilder::new();
+    let mut section = MpsSection::Start;
+    let mut free_rows: HashSet<String> = HashSet::new();
+    let mut ranges: Vec<Option<f32>> = vec![];
+    let mut lower_set: Vec<bool> = vec![];
+    let mut integer_block = false;
+    let mut ended = false;
+    let mut last = 0;
+    for (k, raw) in text.lines().enumerate() {
+        let line = k + 1;
+        last = line;
+        let tokens: Vec<&str> = raw.split_whitespace().collect();
+        if tokens.is_empty() || raw.starts_with('*') {
+            continue;
+        }
+        if !raw.starts_with(char::is_whitespace) {
+            section = match tokens[0].to_ascii_uppercase().as_str() {
+                "NAME" => {
+                    model.info.name = tokens[1..].join(" ");
+                    MpsSection::Name
+                }
+                "OBJSENSE" => {
+                    if let Some(token) = tokens.get(1) {
+                        model.sense = parse_sense(token)
+                            .ok_or(format!("line {line}: unknown objective sense {token}"))?;
+                    }
+                    MpsSection::ObjSense
+                }
+                "ROWS" => MpsSection::Rows,
+                "COLUMNS" => MpsSection::Columns,
+                "RHS" => MpsSection::Rhs,
+                "RANGES" => MpsSection::Ranges,
+                "BOUNDS" => MpsSection::Bounds,
+                "ENDATA" => {
+                    ended = true;
+                    break;
+                }
+                other => return Err(format!("line {line}: unknown section {other}")),
+            };
+            continue;
+        }
+        match section {
+            MpsSection::Start | MpsSection::Name => {
+                return Err(format!("line {line}: data before the ROWS section"));
+            }
+            MpsSection::ObjSense => {
+                model.sense = parse_sense(tokens[0]).ok_or(format!(
+                    "line {line}: unknown objective sense {}",
+                    tokens[0]
+                ))?;
+            }
+            MpsSection::Rows => {
+                let [kind, name] = tokens[..] else {
+                    return Err(format!("line {line}: expected a row type and a name"));
+                };
+                let relation = match kind.to_ascii_uppercase().as_str() {
+                    "N" => {
+                        // the first free row is the objective, the rest are dropped
+                        if model.info.objective.is_empty() {
+                            model.info.objective = name.to_string();
+                        } else {
+                            free_rows.insert(name.to_string());
+                        }
+                        continue;
+                    }
+                    "L" => Relation::LessEqual,
+                    "G" => Relation::GreaterEqual,
+                    "E" => Relation::Equal,
+                    other => return Err(format!("line {line}: unknown row type {other}")),
+                };
+                model
+                    .add_row(name, relation, 0f32)
+                    .map_err(|e| format!("line {line}: {e}"))?;
+                ranges.push(None);
+            }
+            MpsSection::Columns => {
+                if tokens.len() == 3 && tokens[1] == "'MARKER'" {
+                    integer_block = match tokens[2] {
+                        "'INTORG'" => true,
+                        "'INTEND'" => false,
+                        other => return Err(format!("line {line}: unknown marker {other}")),
+                    };
+                    continue;
+                }
+                if tokens.len() != 3 && tokens.len() != 5 {
+                    return Err(format!(
+                        "line {line}: expected a column and one or two row value pairs"
+                    ));
+                }
+                let j = model.column(tokens[0]);
+                model.info.integer[j] |= integer_block;
+                lower_set.resize(model.c.len(), false);
+                for pair in tokens[1..].chunks(2) {
+                    let value = parse_number(pair[1], line)?;
+                    if pair[0] == model.info.objective {
+                        model.c[j] += value;
+                    } else if let Some(&i) = model.rows.get(pair[0]) {
+                        model.entries.push((i, j, value));
+                    } else if !free_rows.contains(pair[0]) {
+                        return Err(format!("line {line}: unknown row {}", pair[0]));
+                    }
+                }
+            }
+            MpsSection::Rhs | MpsSection::Ranges => {
+                // an optional set name precedes the row value pairs
+                let pairs = if tokens.len() % 2 == 1 {
+                    &tokens[1..]
+                } else {
+                    &tokens[..]
+                };
+                if pairs.is_empty() || pairs.len() > 4 {
+                    return Err(format!("line {line}: expected one or two row value pairs"));
+                }
+                for pair in pairs.chunks(2) {
+                    let value = parse_number(pair[1], line)?;
+                    if let Some(&i) = model.rows.get(pair[0]) {
+                        if section == MpsSection::Rhs {
+                            model.b[i] = value;
+                        } else {
+                            ranges[i] = Some(value);
+                        }
+                    } else if pair[0] != model.info.objective && !free_rows.contains(pair[0]) {
+                        return Err(format!("line {line}: unknown row {}", pair[0]));
+                    }
+                }
+            }
+            MpsSection::Bounds => {
+                let kind = tokens[0].to_ascii_uppercase();
+                let valued = matches!(kind.as_str(), "UP" | "LO" | "FX" | "LI" | "UI");
+                let (name, value) = match (valued, tokens.len()) {
+                    (true, 3 | 4) => (
+                        tokens[tokens.len() - 2],
+                        parse_number(tokens[tokens.len() - 1], line)?,
+                    ),
+                    (false, 2 | 3) => (tokens[tokens.len() - 1], 0f32),
+                    _ => return Err(format!("line {line}: malformed {kind} bound")),
+                };
+                let Some(&j) = model.columns.get(name) else {
+                    return Err(format!("line {line}: unknown column {name}"));
+                };
+                let value = if value >= MPS_INFINITY {
+                    f32::INFINITY
+                } else if value <= -MPS_INFINITY {
+                    f32::NEG_INFINITY
+                } else {
+                    value
+                };
+                match kind.as_str() {
+                    "UP" | "UI" => {
+                        model.upper[j] = value;
+                        // a negative upper bound on a default lower bound makes it free below
+                        if value < 0f32 && !lower_set[j] {
+                            model.lower[j] = f32::NEG_INFINITY;
+                        }
+                    }
+                    "LO" | "LI" => {
+                        model.lower[j] = value;
+                        lower_set[j] = true;
+                    }
+                    "FX" => {
+                        model.lower[j] = value;
+                        model.upper[j] = value;
+                        lower_set[j] = true;
+                    }
+                    "FR" => {
+                        model.lower[j] = f32::NEG_INFINITY;
+                        model.upper[j] = f32::INFINITY;
+                        lower_set[j] = true;
+                    }
+                    "MI" => {
+                        model.lower[j] = f32::NEG_INFINITY;
+                        lower_set[j] = true;
+                    }
+                    "PL" => model.upper[j] = f32::INFINITY,
+                    "BV" => {
+                        model.lower[j] = 0f32;
+                        model.upper[j] = 1f32;
+                        lower_set[j] = true;
+                    }
+                    other => return Err(format!("line {line}: unsupported bound type {other}")),
+                }
+                if matches!(kind.as_str(), "BV" | "LI" | "UI") {
+                    model.info.integer[j] = true;
+                }
+            }
+        }
+    }
+    if !ended {
+        return Err(format!("line {last}: missing ENDATA"));
+    }
+    if model.info.objective.is_empty() {
+        return Err(format!("line {last}: no objective row"));
+    }
+    // l <= a'x <= u from the range R of row i, the other side goes on a copy of the row
+    for (i, range) in ranges.into_iter().enumerate() {
+        let Some(r) = range else {
+            continue;
+        };
+        let rhs = model.b[i];
+        let (relation, other) = match model.relations[i] {
+            Relation::LessEqual => (Relation::GreaterEqual, rhs - r.abs()),
+            Relation::GreaterEqual => (Relation::LessEqual, rhs + r.abs()),
+            Relation::Equal if r > 0f32 => {
+                model.relations[i] = Relation::GreaterEqual;
+                (Relation::LessEqual, rhs + r)
+            }
+            Relation::Equal if r < 0f32 => {
+                model.relations[i] = Relation::LessEqual;
+                (Relation::GreaterEqual, rhs + r)
+            }
+            Relation::Equal => continue,
+        };
+        let name = format!("{}_range", model.info.constraints[i]);
+        let copy = model.add_row(&name, relation, other)?;
+        let row: Vec<(usize, usize, f32)> = model
+            .entries
+            .iter()
+            .filter(|e| e.0 == i)
+            .map(|&(_, j, v)| (copy, j, v))
+            .collect();
+        model.entries.extend(row);
+    }
+    Ok(model.build())
+}
+
+/// write_mps
+/// free format mps, integer columns are wrapped in markers
+pub fn write_mps(lp: &LinearProgram, info: &ModelInfo) -> String {
+    let (m, n) = (lp.a.dims[0], lp.a.dims[1]);
+    let mut out = String::new();
+    writeln!(out, "NAME {}", info.name).unwrap();
+    if lp.sense == Sense::Maximize {
+        writeln!(out, "OBJSENSE\n    MAX").unwrap();
+    }
+    writeln!(out, "ROWS\n N  {}", info.objective).unwrap();
+    for (name, relation) in info.constraints.iter().zip(&lp.relations) {
+        let kind = match relation {
+            Relation::LessEqual => "L",
+            Relation::GreaterEqual => "G",
+            Relation::Equal => "E",
+        };
+        writeln!(out, " {kind}  {name}").unwrap();
+    }
+    writeln!(out, "COLUMNS").unwrap();
+    let mut integer_block = false;
+    let mut markers = 0;
+    for j in 0..n {
+        if info.integer[j] != integer_block {
+            let marker = if info.integer[j] {
+                "'INTORG'"
+            } else {
+                "'INTEND'"
+            };
+            writeln!(out, "    MARKER{markers} 'MARKER' {marker}").unwrap();
+            markers += 1;
+            integer_block = info.integer[j];
+        }
+        let name = &info.variables[j];
+        // keep empty columns so that they are declared
+        if lp.c[j] != 0f32 || (0..m).all(|i| lp.a.data[i * n + j] == 0f32) {
+            writeln!(out, "    {name}  {}  {}", info.objective, lp.c[j]).unwrap();
+        }
+        for i in 0..m {
+            let v = lp.a.data[i * n + j];
+            if v != 0f32 {
+                writeln!(out, "    {name}  {}  {v}", info.constraints[i]).unwrap();
+            }
+        }
+    }
+    if integer_block {
+        writeln!(out, "    MARKER{markers} 'MARKER' 'INTEND'").unwrap();
+    }
+    writeln!(out, "RHS").unwrap();
+    for (name, &b) in info.constraints.iter().zip(&lp.b) {
+        if b != 0f32 {
+            writeln!(out, "    RHS  {name}  {b}").unwrap();
+        }
+    }
+    writeln!(out, "BOUNDS").unwrap();
+    for j in 0..n {
+        let (l, u, name) = (lp.lower[j], lp.upper[j], &info.variables[j]);
+        if l == u {
+            writeln!(out, " FX BND  {name}  {l}").unwrap();
+            continue;
+        }
+        match (l.is_finite(), u.is_finite()) {
+            (false, false) => writeln!(out, " FR BND  {name}").unwrap(),
+            (false, true) => writeln!(out, " MI BND  {name}\n UP BND  {name}  {u}").unwrap(),
+            (true, _) => {
+                // an explicit lower bound stops a negative upper bound from freeing it
+                if l != 0f32 || u < 0f32 {
+                    writeln!(out, " LO BND  {name}  {l}").unwrap();
+                }
+                if u.is_finite() {
+                    writeln!(out, " UP BND  {name}  {u}").unwrap();
+                }
+            }
+        }
+    }
+    writeln!(out, "ENDATA").unwrap();
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Keyword {
+    Objective(Sense),
+    SubjectTo,
+    Bounds,
+    General,
+    Binary,
+    End,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f32),
+    Name(String),
+    Sign(f32),
+    Relation(Relation),
+    Colon,
+    Section(Keyword),
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!\"#$%&()/,.;?@_`'{}|~".contains(c)
+}
+
+fn keyword(word: &str) -> Option<Keyword> {
+    let keyword = match word.to_ascii_lowercase().as_str() {
+        "st" | "s.t." | "st." => Keyword::SubjectTo,
+        "bounds" | "bound" => Keyword::Bounds,
+        "general" | "generals" | "gen" => Keyword::General,
+        "binary" | "binaries" | "bin" => Keyword::Binary,
+        "end" => Keyword::End,
+        other => Keyword::Objective(parse_sense(other)?),
+    };
+    Some(keyword)
+}
+
+// tokens with their line, keywords only count as the first word of a line
+fn tokenize_lp(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = vec![];
+    for (k, raw) in text.lines().enumerate() {
+        let line = k + 1;
+        let content = raw.split('\\').next().unwrap_or("");
+        let chars: Vec<char> = content.chars().collect();
+        let start = tokens.len();
+        let mut p = 0;
+        while p < chars.len() {
+            let c = chars[p];
+            let next = chars.get(p + 1).copied();
+            if c.is_whitespace() {
+                p += 1;
+            } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|d| d.is_ascii_digit())) {
+                let begin = p;
+                while p < chars.len() && (chars[p].is_ascii_digit() || chars[p] == '.') {
+                    p += 1;
+                }
+                if p < chars.len() && (chars[p] == 'e' || chars[p] == 'E') {
+                    let mut q = p + 1;
+                    if q < chars.len() && (chars[q] == '+' || chars[q] == '-') {
+                        q += 1;
+                    }
+                    if q < chars.len() && chars[q].is_ascii_digit() {
+                        p = q;
+                        while p < chars.len() && chars[p].is_ascii_digit() {
+                            p += 1;
+                        }
+                    }
+                }
+                let word: String = chars[begin..p].iter().collect();
+                tokens.push((Token::Number(parse_number(&word, line)?), line));
+            } else if c == '+' || c == '-' {
+                let sign = if c == '+' { 1f32 } else { -1f32 };
+                tokens.push((Token::Sign(sign), line));
+                p += 1;
+            } else if c == '<' || c == '>' || c == '=' {
+                let relation = match (c, next) {
+                    ('<', _) | ('=', Some('<')) => Relation::LessEqual,
+                    ('>', _) | ('=', Some('>')) => Relation::GreaterEqual,
+                    _ => Relation::Equal,
+                };
+                p += 1;
+                if matches!(chars.get(p), Some('=' | '<' | '>')) {
+                    p += 1;
+                }
+                tokens.push((Token::Relation(relation), line));
+            } else if c == ':' {
+                tokens.push((Token::Colon, line));
+                p += 1;
+            } else if is_name_char(c) && c != '.' {
+                let begin = p;
+                while p < chars.len() && is_name_char(chars[p]) {
+                    p += 1;
+                }
+                tokens.push((Token::Name(chars[begin..p].iter().collect()), line));
+            } else {
+                return Err(format!("line {line}: unexpected character '{c}'"));
+            }
+        }
+        // subject to and such that span two words
+        let words: Vec<String> = tokens[start..]
+            .iter()
+            .take(2)
+            .map(|(t, _)| match t {
+                Token::Name(name) => name.to_ascii_lowercase(),
+                _ => String::new(),
+            })
+            .collect();
+        let pair = words.iter().map(String::as_str).collect::<Vec<&str>>();
+        if matches!(pair[..], ["subject", "to"] | ["such", "that"]) {
+            tokens.drain(start..start + 2);
+            tokens.insert(start, (Token::Section(Keyword::SubjectTo), line));
+        } else if let Some(section) = tokens.get(start).and_then(|(t, _)| match t {
+            Token::Name(word) => keyword(word),
+            _ => None,
+        }) {
+            tokens[start].0 = Token::Section(section);
+        }
+    }
+    Ok(tokens)
+}
+
+// cursor over the lp tokens
+struct LpParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    model: ModelBuilder,
+}
+
+impl LpParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |&(_, line)| line)
+    }
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("line {}: {message}", self.line()))
+    }
+    fn error_at<T>(line: usize, message: &str) -> Result<T, String> {
+        Err(format!("line {line}: {message}"))
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+    fn at_section(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Section(_)))
+    }
+    // name followed by a colon
+    fn label(&mut self) -> Option<String> {
+        match (self.peek(), self.tokens.get(self.pos + 1)) {
+            (Some(Token::Name(name)), Some((Token::Colon, _))) => {
+                let name = name.clone();
+                self.pos += 2;
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+    // signed number or +-inf
+    fn value(&mut self) -> Result<f32, String> {
+        let mut sign = 1f32;
+        while let Some(Token::Sign(s)) = self.peek() {
+            sign *= s;
+            self.pos += 1;
+        }
+        match self.next() {
+            Some(Token::Number(v)) => Ok(sign * v),
+            Some(Token::Name(name))
+                if matches!(name.to_ascii_lowercase().as_str(), "inf" | "infinity") =>
+            {
+                Ok(sign * f32::INFINITY)
+            }
+            _ => {
+                self.pos -= 1;
+                self.error("expected a number")
+            }
+        }
+    }
+    // sum of [sign] [coefficient] name terms up to a relation or a section
+    fn expression(&mut self) -> Result<Vec<(usize, f32)>, String> {
+        let mut terms = vec![];
+        while !self.at_section() && !matches!(self.peek(), Some(Token::Relation(_))) {
+            let line = self.line();
+            let mut coefficient = 1f32;
+            while let Some(Token::Sign(s)) = self.peek() {
+                coefficient *= s;
+                self.pos += 1;
+            }
+            if let Some(Token::Number(v)) = self.peek() {
+                coefficient *= v;
+                self.pos += 1;
+            }
+            match self.next() {
+                Some(Token::Name(name)) => terms.push((self.model.column(&name), coefficient)),
+                _ => return Self::error_at(line, "expected a variable"),
+            }
+        }
+        Ok(terms)
+    }
+    fn constraints(&mut self) -> Result<(), String> {
+        while !self.at_section() {
+            let count = self.model.relations.len();
+            let name = self.label().unwrap_or(format!("R{}", count + 1));
+            let terms = self.expression()?;
+            let Some(Token::Relation(relation)) = self.next() else {
+                self.pos -= 1;
+                return self.error("expected a relation");
+            };
+            let rhs = self.value()?;
+            let i = match self.model.add_row(&name, relation, rhs) {
+                Ok(i) => i,
+                Err(e) => return self.error(&e),
+            };
+            self.model
+                .entries
+                .extend(terms.into_iter().map(|(j, v)| (i, j, v)));
+        }
+        Ok(())
+    }
+    fn bounds(&mut self) -> Result<(), String> {
+        while !self.at_section() {
+            if let Some(Token::Name(name)) = self.peek().cloned() {
+                // x free, x <= u, x >= l or x = v
+                self.pos += 1;
+                let j = self.model.column(&name);
+                if matches!(self.peek(), Some(Token::Name(word)) if word.eq_ignore_ascii_case("free"))
+                {
+                    self.pos += 1;
+                    self.model.lower[j] = f32::NEG_INFINITY;
+                    self.model.upper[j] = f32::INFINITY;
+                    continue;
+                }
+                let Some(Token::Relation(relation)) = self.next() else {
+                    self.pos -= 1;
+                    return self.error("expected a relation or free");
+                };
+                let v = self.value()?;
+                self.set_bound(j, relation, v, false);
+            } else {
+                // l <= x [<= u]
+                let v = self.value()?;
+                let Some(Token::Relation(relation)) = self.next() else {
+                    self.pos -= 1;
+                    return self.error("expected a relation");
+                };
+                let Some(Token::Name(name)) = self.next() else {
+                    self.pos -= 1;
+                    return self.error("expected a variable");
+                };
+                let j = self.model.column(&name);
+                self.set_bound(j, relation, v, true);
+                if let Some(Token::Relation(relation)) = self.peek().cloned() {
+                    self.pos += 1;
+                    let v = self.value()?;
+                    self.set_bound(j, relation, v, false);
+                }
+            }
+        }
+        Ok(())
+    }
+    // x relation v, or v relation x when flipped
+    fn set_bound(&mut self, j: usize, relation: Relation, v: f32, flipped: bool) {
+        let relation = match (relation, flipped) {
+            (Relation::LessEqual, true) => Relation::GreaterEqual,
+            (Relation::GreaterEqual, true) => Relation::LessEqual,
+            (relation, _) => relation,
+        };
+        match relation {
+            Relation::LessEqual => self.model.upper[j] = v,
+            Relation::GreaterEqual => self.model.lower[j] = v,
+            Relation::Equal => {
+                self.model.lower[j] = v;
+                self.model.upper[j] = v;
+            }
+        }
+    }
+    fn integers(&mut self, binary: bool) -> Result<(), String> {
+        while !self.at_section() {
+            let Some(Token::Name(name)) = self.next() else {
+                self.pos -= 1;
+                return self.error("expected a variable");
+            };
+            let j = self.model.column(&name);
+            self.model.info.integer[j] = true;
+            if binary {
+                self.model.lower[j] = 0f32;
+                self.model.upper[j] = 1f32;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// read_lp
+/// cplex lp format with objective, subject to, bounds, generals and binaries sections,
+/// unnamed rows are called R1, R2, ..
+pub fn read_lp(text: &str) -> Result<(LinearProgram, ModelInfo), String> {
+    let mut parser = LpParser {
+        tokens: tokenize_lp(text)?,
+        pos: 0,
+        model: ModelBuilder::new(),
+    };
+    let Some(Token::Section(Keyword::Objective(sense))) = parser.next() else {
+        parser.pos = 0;
+        return parser.error("expected minimize or maximize");
+    };
+    parser.model.sense = sense;
+    parser.model.info.objective = parser.label().unwrap_or("obj".to_string());
+    for (j, v) in parser.expression()? {
+        parser.model.c[j] += v;
+    }
+    let mut ended = false;
+    while let Some(token) = parser.next() {
+        match token {
+            Token::Section(Keyword::SubjectTo) => parser.constraints()?,
+            Token::Section(Keyword::Bounds) => parser.bounds()?,
+            Token::Section(Keyword::General) => parser.integers(false)?,
+            Token::Section(Keyword::Binary) => parser.integers(true)?,
+            Token::Section(Keyword::End) => {
+                ended = true;
+                break;
+            }
+            _ => {
+                parser.pos -= 1;
+                return parser.error("expected a section");
+            }
+        }
+    }
+    if !ended {
+        return parser.error("missing End");
+    }
+    Ok(parser.model.build())
+}
+
+fn write_expression(out: &mut String, terms: impl Iterator<Item = (f32, String)>) {
+    let mut written = 0;
+    for (v, name) in terms {
+        if written > 0 && written % TERMS_PER_LINE == 0 {
+            write!(out, "\n   ").unwrap();
+        }
+        let sign = if v < 0f32 {
+            "-"
+        } else if written > 0 {
+            "+"
+        } else {
+            ""
+        };
+        write!(out, " {sign} {} {name}", v.abs()).unwrap();
+        written += 1;
+    }
+}
+
+/// write_lp
+/// cplex lp format, integer columns are listed under generals
+pub fn write_lp(lp: &LinearProgram, info: &ModelInfo) -> String {
+    let (m, n) = (lp.a.dims[0], lp.a.dims[1]);
+    let mut out = String::new();
+    writeln!(out, "\\ Problem name: {}", info.name).unwrap();
+    match lp.sense {
+        Sense::Minimize => writeln!(out, "Minimize").unwrap(),
+        Sense::Maximize => writeln!(out, "Maximize").unwrap(),
+    }
+    // every column is listed in the objective so that the column order survives a round trip
+    write!(out, " {}:", info.objective).unwrap();
+    write_expression(
+        &mut out,
+        (0..n).map(|j| (lp.c[j], info.variables[j].clone())),
+    );
+    writeln!(out, "\nSubject To").unwrap();
+    for i in 0..m {
+        write!(out, " {}:", info.constraints[i]).unwrap();
+        let row = &lp.a.data[i * n..(i + 1) * n];
+        let mut terms: Vec<(f32, String)> = (0..n)
+            .filter(|&j| row[j] != 0f32)
+            .map(|j| (row[j], info.variables[j].clone()))
+            .collect();
+        if terms.is_empty() && n > 0 {
+            terms.push((0f32, info.variables[0].clone()));
+        }
+        write_expression(&mut out, terms.into_iter());
+        let relation = match lp.relations[i] {
+            Relation::LessEqual => "<=",
+            Relation::GreaterEqual => ">=",
+            Relation::Equal => "=",
+        };
+        writeln!(out, " {relation} {}", lp.b[i]).unwrap();
+    }
+    writeln!(out, "Bounds").unwrap();
+    for j in 0..n {
+        let (l, u, name) = (lp.lower[j], lp.upper[j], &info.variables[j]);
+        if l == u {
+            writeln!(out, " {name} = {l}").unwrap();
+            continue;
+        }
+        match (l.is_finite(), u.is_finite()) {
+            (false, false) => writeln!(out, " {name} free").unwrap(),
+            (false, true) => writeln!(out, " -inf <= {name} <= {u}").unwrap(),
+            (true, true) => writeln!(out, " {l} <= {name} <= {u}").unwrap(),
+            (true, false) if l != 0f32 => writeln!(out, " {name} >= {l}").unwrap(),
+            (true, false) => {}
+        }
+    }
+    if info.integer.iter().any(|&z| z) {
+        writeln!(out, "Generals").unwrap();
+        for j in (0..n).filter(|&j| info.integer[j]) {
+            writeln!(out, " {}", info.variables[j]).unwrap();
+        }
+    }
+    writeln!(out, "End").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod test_lp_format {
+    use super::*;
+    use crate::solver::linear::{LpStatus, RevisedSimplex};
+
+    const EXAMPLE: &str = "\
+* every section with a ranged row and integer markers
+NAME          EXAMPLE
+OBJSENSE
+    MAX
+ROWS
+ N  PROFIT
+ L  LIM1
+ G  LIM2
+ E  MYEQN
+ N  UNUSED
+COLUMNS
+    MARKER                 'MARKER'                 'INTORG'
+    X1        PROFIT         1.0   LIM1           1.0
+    X1        LIM2           1.0
+    MARKER                 'MARKER'                 'INTEND'
+    X2        PROFIT         2.0   LIM1           1.0
+    X2        MYEQN         -1.0   UNUSED         5.0
+    X3        PROFIT        -1.0   MYEQN          1.0
+RHS
+    RHS       LIM1           4.0   LIM2           1.0
+    RHS       MYEQN          7.0
+RANGES
+    RNG       LIM1           2.5
+BOUNDS
+ UP BND       X1             4.0
+ MI BND       X2
+ UP BND       X2             1.0
+ UP BND       X3             1e30
+ENDATA
+";
+
+    fn assert_same(x: &LinearProgram, y: &LinearProgram) {
+        assert_eq!(x.sense, y.sense);
+        assert_eq!(x.a.dims, y.a.dims);
+        assert_eq!(x.a.data, y.a.data);
+        assert_eq!(x.c, y.c);
+        assert_eq!(x.b, y.b);
+        assert_eq!(x.relations, y.relations);
+        assert_eq!(x.lower, y.lower);
+        assert_eq!(x.upper, y.upper);
+    }
+
+    #[test]
+    fn test_read_mps() {
+        let (lp, info) = read_mps(EXAMPLE).unwrap();
+        assert_eq!(info.name, "EXAMPLE");
+        assert_eq!(info.objective, "PROFIT");
+        assert_eq!(info.variables, ["X1", "X2", "X3"]);
+        assert_eq!(info.constraints, ["LIM1", "LIM2", "MYEQN", "LIM1_range"]);
+        assert_eq!(info.integer, [true, false, false]);
+        assert_eq!(lp.sense, Sense::Maximize);
+        assert_eq!(lp.c, [1f32, 2f32, -1f32]);
+        assert_eq!(lp.b, [4f32, 1f32, 7f32, 1.5f32]);
+        assert_eq!(lp.relations[3], Relation::GreaterEqual);
+        assert_eq!(&lp.a.data[9..], &[1f32, 1f32, 0f32]);
+        assert_eq!(lp.lower, [0f32, f32::NEG_INFINITY, 0f32]);
+        assert_eq!(lp.upper, [4f32, 1f32, f32::INFINITY]);
+        // x3 = 7 + x2 turns the objective into x1 + x2 - 7 with x1 + x2 <= 4
+        let form = lp.standard_form();
+        let mut simplex = RevisedSimplex::new(&form);
+        assert_eq!(simplex.run_phase_one(), Ok(()));
+        assert_eq!(simplex.run_phase_two(), Ok(()));
+        let x = form.recover(&simplex.primal());
+        let objective: f32 = lp.c.iter().zip(&x).map(|(c, x)| c * x).sum();
+        assert!((objective + 3f32).abs() < 1e-4);
+    }
+    #[test]
+    fn test_round_trip() {
+        let lp = LinearProgram::new(Sense::Minimize, vec![1f32, -2.5f32, 0f32, 3f32])
+            .with_constraint(vec![1f32, 1f32, 1f32, 0f32], Relation::Equal, 4f32)
+            .with_constraint(vec![0f32, -1f32, 2f32, 1f32], Relation::GreaterEqual, -3f32)
+            .with_constraint(vec![1f32, 0f32, 0f32, 0.25f32], Relation::LessEqual, 6f32)
+            .with_bounds(0, 1f32, 5f32)
+            .with_free(1)
+            .with_bounds(2, f32::NEG_INFINITY, 2f32)
+            .with_bounds(3, 0f32, -1f32);
+        let mut info = ModelInfo::new(&lp);
+        info.integer[2] = true;
+        for text in [write_mps(&lp, &info), write_lp(&lp, &info)] {
+            let (read, read_info) = if text.starts_with("NAME") {
+                read_mps(&text).unwrap()
+            } else {
+                read_lp(&text).unwrap()
+            };
+            assert_same(&lp, &read);
+            assert_eq!(info.variables, read_info.variables);
+            assert_eq!(info.constraints, read_info.constraints);
+            assert_eq!(info.integer, read_info.integer);
+        }
+        // the infeasible bound 0 <= x3 <= -1 survives and is reported
+        assert_eq!(lp.solve().status, LpStatus::Infeasible);
+    }
+    #[test]
+    fn test_read_lp() {
+        let text = "\\ textbook production problem
+Maximize
+ profit: 3 x + 5y
+Subject To
+ c1: x <= 4
+ c2: 2 y <= 12
+ 3 x + 2 y
+   <= 18
+Bounds
+ -inf <= x <= 10
+ y >= -1
+Generals
+ y
+End
+";
+        let (lp, info) = read_lp(text).unwrap();
+        assert_eq!(info.objective, "profit");
+        assert_eq!(info.constraints, ["c1", "c2", "R3"]);
+        assert_eq!(info.integer, [false, true]);
+        assert_eq!(lp.lower, [f32::NEG_INFINITY, -1f32]);
+        assert_eq!(lp.upper, [10f32, f32::INFINITY]);
+        let solution = lp.solve();
+        assert_eq!(solution.status, LpStatus::Optimal);
+        assert!((solution.objective - 36f32).abs() < 1e-3);
+    }
+    #[test]
+    fn test_errors_report_lines() {
+        let mps = EXAMPLE.replace("X2        MYEQN", "X2        NOROW");
+        let error = read_mps(&mps).unwrap_err();
+        assert!(error.starts_with("line 17:"), "{error}");
+        let mps = EXAMPLE.replace(" UP BND       X3             1e30", " SC BND       X3   1");
+        let error = read_mps(&mps).unwrap_err();
+        assert!(error.starts_with("line 28:"), "{error}");
+        let lp = "Minimize\n obj: x + y\nSubject To\n c1: x + * y >= 2\nEnd\n";
+        let error = read_lp(lp).unwrap_err();
+        assert!(error.starts_with("line 4:"), "{error}");
+        let lp = "Minimize\n obj: x + y\nSubject To\n c1: x + y 2\nEnd\n";
+        let error = read_lp(lp).unwrap_err();
+        assert!(error.starts_with("line 4:"), "{error}");
+    }
+}
@@ -0,0 +1,291 @@
+use crate::algebra::ndmethods::{
+    combine, create_identity_matrix, frobenius, matrix_mult, symmetrize,
+};
+use crate::decomposition::lower_upper::LuPivotDecompose;
+use crate::decomposition::schur::{real_schur, schur_blocks, small_solve};
+use crate::structure::ndarray::NdArray;
+
+/// EquationSolution
+/// solution of a matrix equation together with its residual
+///
+/// * x: solution matrix
+/// * residual: frobenius norm of the equation evaluated at x
+pub struct EquationSolution {
+    pub x: NdArray,
+    pub residual: f32,
+}
+
+// A^-1 B
+fn solve(a: &NdArray, mut b: NdArray) -> NdArray {
+    let mut workspace = vec![0f32; a.dims[0]];
+    LuPivotDecompose::new(a.clone(), &mut workspace).solve_inplace(&mut b);
+    b
+}
+
+/// quasi_sylvester
+/// block back substitution for TaY + YTb = F with quasi triangular Ta and Tb
+///
+/// * ta: quasi triangular matrix ~ m x m
+/// * tb: quasi triangular matrix ~ n x n
+/// * row_blocks: (start, size) of the diagonal blocks of ta
+/// * col_blocks: (start, size) of the diagonal blocks of tb
+/// * y: F on entry, overwritten by Y ~ m x n
+fn quasi_sylvester(
+    ta: &[f64],
+    tb: &[f64],
+    row_blocks: &[(usize, usize)],
+    col_blocks: &[(usize, usize)],
+    y: &mut [f64],
+    m: usize,
+    n: usize,
+) {
+    for &(j, q) in col_blocks {
+        for &(i, p) in row_blocks.iter().rev() {
+            let size = p * q;
+            let mut rhs = [0f64; 4];
+            let mut k = [0f64; 16];
+            for a in 0..p {
+                for b in 0..q {
+                    let row = a * q + b;
+                    let mut v = y[(i + a) * n + j + b];
+                    for l in i + p..m {
+                        v -= ta[(i + a) * m + l] * y[l * n + j + b];
+                    }
+                    for l in 0..j {
+                        v -= y[(i + a) * n + l] * tb[l * n + j + b];
+                    }
+                    rhs[row] = v;
+                    for c in 0..p {
+                        k[row * size + c * q + b] += ta[(i + a) * m + i + c];
+                    }
+                    for d in 0..q {
+                        k[row * size + a * q + d] += tb[(j + d) * n + j + b];
+                    }
+                }
+            }
+            small_solve(&mut k[..size * size], &mut rhs[..size], size);
+            for a in 0..p {
+                for b in 0..q {
+                    y[(i + a) * n + j + b] = rhs[a * q + b];
+                }
+            }
+        }
+    }
+}
+
+/// sylvester
+/// bartels stewart solver for AX + XB = C on the real schur forms of A and B
+///
+/// * a: square matrix ~ m x m
+/// * b: square matrix ~ n x n, -B must not share an eigenvalue with A
+/// * c: right hand side ~ m x n
+pub fn sylvester(a: &NdArray, b: &NdArray, c: &NdArray) -> EquationSolution {
+    let (m, n) = (a.dims[0], b.dims[0]);
+    debug_assert_eq!(c.dims, vec![m, n], "sylvester requires C ~ m x n");
+    let (mut sa, _, a_converged) = real_schur(a);
+    let (mut sb, _, b_converged) = real_schur(b);
+    debug_assert!(a_converged && b_converged, "schur form did not converge");
+    let row_blocks = schur_blocks(&mut sa.kernel.data, m);
+    let col_blocks = schur_blocks(&mut sb.kernel.data, n);
+    // A = Ra'TaRa, B = Rb'TbRb => TaY + YTb = RaCRb' with Y = RaXRb'
+    let f = matrix_mult(&matrix_mult(&sa.rotation, c), &sb.rotation.transpose());
+    let ta: Vec<f64> = sa.kernel.data.iter().map(|&v| v as f64).collect();
+    let tb: Vec<f64> = sb.kernel.data.iter().map(|&v| v as f64).collect();
+    let mut y: Vec<f64> = f.data.iter().map(|&v| v as f64).collect();
+    quasi_sylvester(&ta, &tb, &row_blocks, &col_blocks, &mut y, m, n);
+    let y = NdArray::new(vec![m, n], y.into_iter().map(|v| v as f32).collect());
+    let x = matrix_mult(&matrix_mult(&sa.rotation.transpose(), &y), &sb.rotation);
+    let lhs = combine(&[(1f32, &matrix_mult(a, &x)), (1f32, &matrix_mult(&x, b))]);
+    let residual = frobenius(&combine(&[(1f32, &lhs), (-1f32, c)]));
+    EquationSolution { x, residual }
+}
+
+/// lyapunov
+/// continuous lyapunov equation AX + XA' + Q = 0 as a sylvester equation
+///
+/// * a: square matrix, no pair of eigenvalues may sum to zero
+/// * q: symmetric matrix
+pub fn lyapunov(a: &NdArray, q: &NdArray) -> EquationSolution {
+    let mut rhs = q.clone();
+    rhs.data.iter_mut().for_each(|v| *v = -*v);
+    let at = a.transpose();
+    let mut x = sylvester(a, &at, &rhs).x;
+    symmetrize(&mut x);
+    let lhs = combine(&[(1f32, &matrix_mult(a, &x)), (1f32, &matrix_mult(&x, &at))]);
+    let residual = frobenius(&combine(&[(1f32, &lhs), (1f32, q)]));
+    EquationSolution { x, residual }
+}
+
+// X = U2 U1^-1 from the stacked basis [U1; U2] ~ 2n x n
+fn stacked_solution(basis: &NdArray, n: usize) -> Result<NdArray, String> {
+    if basis.dims[1] != n {
+        return Err(format!(
+            "expected {n} stable eigenvalues found {}",
+            basis.dims[1]
+        ));
+    }
+    let u1 = NdArray::new(vec![n, n], basis.data[..n * n].to_vec());
+    let u2 = NdArray::new(vec![n, n], basis.data[n * n..].to_vec());
+    // U1'X' = U2'
+    let mut x = solve(&u1.transpose(), u2.transpose()).transpose();
+    if x.data.iter().any(|v| !v.is_finite()) {
+        return Err("stable invariant subspace is singular".to_string());
+    }
+    symmetrize(&mut x);
+    Ok(x)
+}
+
+// G = BR^-1B'
+fn gain_matrix(b: &NdArray, r: &NdArray) -> NdArray {
+    matrix_mult(b, &solve(r, b.transpose()))
+}
+
+/// care
+/// continuous algebraic riccati equation A'X + XA - XBR^-1B'X + Q = 0 by the schur vectors
+/// of the stable invariant subspace of the hamiltonian [A -G; -Q -A']
+///
+/// * a: state matrix ~ n x n
+/// * b: input matrix ~ n x m
+/// * q: symmetric positive semidefinite state weight ~ n x n
+/// * r: symmetric positive definite input weight ~ m x m
+pub fn care(
+    a: &NdArray,
+    b: &NdArray,
+    q: &NdArray,
+    r: &NdArray,
+) -> Result<EquationSolution, String> {
+    let n = a.dims[0];
+    let g = gain_matrix(b, r);
+    let mut h = NdArray::new(vec![2 * n, 2 * n], vec![0f32; 4 * n * n]);
+    for i in 0..n {
+        for j in 0..n {
+            h.data[i * 2 * n + j] = a.data[i * n + j];
+            h.data[i * 2 * n + n + j] = -g.data[i * n + j];
+            h.data[(n + i) * 2 * n + j] = -q.data[i * n + j];
+            h.data[(n + i) * 2 * n + n + j] = -a.data[j * n + i];
+        }
+    }
+    let (mut schur, _, converged) = real_schur(&h);
+    if !converged {
+        return Err("schur form of the hamiltonian did not converge".to_string());
+    }
+    let basis = schur.reorder(|lambda| lambda.real() < 0f32);
+    let x = stacked_solution(&basis, n)?;
+    let at = a.transpose();
+    let xgx = matrix_mult(&matrix_mult(&x, &g), &x);
+    #[rustfmt::skip]
+    let lhs = combine(&[(1f32, &matrix_mult(&at, &x)), (1f32, &matrix_mult(&x, a)), (-1f32, &xgx), (1f32, q)]);
+    let residual = frobenius(&lhs);
+    Ok(EquationSolution { x, residual })
+}
+
+/// dare
+/// discrete algebraic riccati equation A'XA - X - A'XB(R + B'XB)^-1B'XA + Q = 0 by the schur
+/// vectors of the stable invariant subspace of the symplectic [A + GA^-TQ  -GA^-T; -A^-TQ  A^-T]
+///
+/// * a: nonsingular state matrix ~ n x n
+/// * b: input matrix ~ n x m
+/// * q: symmetric positive semidefinite state weight ~ n x n
+/// * r: symmetric positive definite input weight ~ m x m
+pub fn dare(
+    a: &NdArray,
+    b: &NdArray,
+    q: &NdArray,
+    r: &NdArray,
+) -> Result<EquationSolution, String> {
+    let n = a.dims[0];
+    let g = gain_matrix(b, r);
+    let at = a.transpose();
+    // A^-T and A^-TQ
+    let ait = solve(&at, create_identity_matrix(n));
+    let aitq = matrix_mult(&ait, q);
+    let top_left = combine(&[(1f32, a), (1f32, &matrix_mult(&g, &aitq))]);
+    let top_right = matrix_mult(&g, &ait);
+    let mut z = NdArray::new(vec![2 * n, 2 * n], vec![0f32; 4 * n * n]);
+    for i in 0..n {
+        for j in 0..n {
+            z.data[i * 2 * n + j] = top_left.data[i * n + j];
+            z.data[i * 2 * n + n + j] = -top_right.data[i * n + j];
+            z.data[(n + i) * 2 * n + j] = -aitq.data[i * n + j];
+            z.data[(n + i) * 2 * n + n + j] = ait.data[i * n + j];
+        }
+    }
+    let (mut schur, _, converged) = real_schur(&z);
+    if !converged {
+        return Err("schur form of the symplectic matrix did not converge".to_string());
+    }
+    let basis = schur.reorder(|lambda| lambda.norm() < 1f32);
+    let x = stacked_solution(&basis, n)?;
+    let xa = matrix_mult(&x, a);
+    let btx = matrix_mult(&b.transpose(), &x);
+    let inner = combine(&[(1f32, r), (1f32, &matrix_mult(&btx, b))]);
+    let correction = matrix_mult(
+        &matrix_mult(&at, &btx.transpose()),
+        &solve(&inner, matrix_mult(&btx, a)),
+    );
+    let lhs = combine(&[
+        (1f32, &matrix_mult(&at, &xa)),
+        (-1f32, &x),
+        (-1f32, &correction),
+        (1f32, q),
+    ]);
+    let residual = frobenius(&lhs);
+    Ok(EquationSolution { x, residual })
+}
+
+#[cfg(test)]
+mod test_matrix_equation {
+    use super::*;
+    use crate::decomposition::eigen::general::Eigen;
+    use crate::random::generation::generate_random_matrix;
+
+    fn shifted(n: usize, shift: f32) -> NdArray {
+        let mut a = generate_random_matrix(n, n);
+        for i in 0..n {
+            a.data[i * n + i] += shift;
+        }
+        a
+    }
+    #[test]
+    fn test_sylvester_lyapunov() {
+        let (m, n) = (6, 4);
+        let a = shifted(m, 4f32);
+        let b = shifted(n, 4f32);
+        let c = generate_random_matrix(m, n);
+        let solution = sylvester(&a, &b, &c);
+        assert!(solution.residual < 1e-3 * frobenius(&c).max(1f32));
+        // stable A gives a positive definite X
+        let a = shifted(m, -6f32);
+        let q = create_identity_matrix(m);
+        let solution = lyapunov(&a, &q);
+        assert!(solution.residual < 1e-3);
+        let eig = Eigen::compute(&solution.x);
+        assert!(eig.values.iter().all(|v| v.real() > 0f32));
+    }
+    #[test]
+    fn test_riccati() {
+        let (n, m) = (4, 2);
+        let a = generate_random_matrix(n, n);
+        let b = generate_random_matrix(n, m);
+        let q = create_identity_matrix(n);
+        let r = create_identity_matrix(m);
+        let solution = care(&a, &b, &q, &r).unwrap();
+        assert!(solution.residual < 1e-3 * frobenius(&solution.x).max(1f32));
+        // A - BR^-1B'X is stable
+        let closed = combine(&[
+            (1f32, &a),
+            (-1f32, &matrix_mult(&gain_matrix(&b, &r), &solution.x)),
+        ]);
+        assert!(
+            Eigen::compute(&closed)
+                .values
+                .iter()
+                .all(|v| v.real() < 0f32)
+        );
+        // the discrete case on a nonsingular A
+        let mut a = generate_random_matrix(n, n);
+        a.data.iter_mut().for_each(|v| *v *= 0.5);
+        let solution = dare(&a, &b, &q, &r).unwrap();
+        assert!(solution.residual < 1e-3 * frobenius(&solution.x).max(1f32));
+    }
+}
@@ -0,0 +1,498 @@
+use crate::solver::linear::{
+    LinearProgram, LpStatus, Relation, RevisedSimplex, Sense, StandardForm,
+};
+use std::time::{Duration, Instant};
+
+// distance to the nearest integer below which a value counts as integral
+const INTEGER_TOLERANCE: f32 = 1e-4;
+// gomory source rows need a fractional part in [CUT_MARGIN, 1 - CUT_MARGIN]
+const CUT_MARGIN: f32 = 1e-2;
+// most fractional source rows used by a round of cuts
+const CUTS_PER_ROUND: usize = 8;
+
+/// NodeSelection
+/// order in which open nodes are explored
+///
+/// * BestBound: node with the best relaxation bound, proves optimality with the fewest nodes
+/// * DepthFirst: most recently created node, finds incumbents early and keeps few nodes open
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeSelection {
+    BestBound,
+    DepthFirst,
+}
+
+/// MilpStatus
+///
+/// * Optimal: the incumbent is within the gap tolerance of the bound
+/// * Infeasible: no integer point satisfies the constraints
+/// * Unbounded: the root relaxation is unbounded
+/// * NodeLimit: nodes were left open by the node limit, or a relaxation ran out of pivots
+/// * TimeLimit: nodes were left open by the time limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MilpStatus {
+    Optimal,
+    Infeasible,
+    Unbounded,
+    NodeLimit,
+    TimeLimit,
+}
+
+/// MilpSolution
+///
+/// * status: optimal, infeasible, unbounded or stopped by a limit
+/// * x: best integer point found, empty without an incumbent
+/// * objective: c'x of the incumbent, +inf when minimizing and -inf when maximizing without one
+/// * bound: best bound on the optimal objective over the nodes left open
+/// * gap: |objective - bound| / max(1, |objective|)
+/// * nodes: relaxations solved
+/// * cuts: gomory cuts added at the root
+#[derive(Clone, Debug)]
+pub struct MilpSolution {
+    pub status: MilpStatus,
+    pub x: Vec<f32>,
+    pub objective: f32,
+    pub bound: f32,
+    pub gap: f32,
+    pub nodes: usize,
+    pub cuts: usize,
+}
+
+/// BranchAndBound
+/// branch and bound over the relaxations of a LinearProgram with integer variables, a child
+/// appends x_j <= floor(v) or x_j >= ceil(v) on the most fractional variable to the standard
+/// form of its parent and restarts from the parent basis with the dual simplex
+///
+/// * integer: integrality of every variable ~ n
+/// * selection: node selection rule
+/// * gomory_rounds: rounds of gomory mixed integer cuts at the root
+/// * max_nodes: limit on the relaxations solved
+/// * max_pivots: pivot limit of every relaxation, none for the simplex default
+/// * time_limit: wall clock limit, none for no limit
+/// * gap_tolerance: relative gap at which a node cannot improve the incumbent
+pub struct BranchAndBound {
+    pub integer: Vec<bool>,
+    pub selection: NodeSelection,
+    pub gomory_rounds: usize,
+    pub max_nodes: usize,
+    pub max_pivots: Option<usize>,
+    pub time_limit: Option<Duration>,
+    pub gap_tolerance: f32,
+}
+
+// relaxation of a node, bound is the objective of the parent relaxation
+struct Node {
+    form: StandardForm,
+    basis: Option<Vec<usize>>,
+    bound: f32,
+}
+
+// solves a relaxation from the given basis with the dual simplex, cold with two phases when
+// there is no basis or the warm start runs out of pivots
+fn relax(
+    form: &StandardForm,
+    basis: Option<Vec<usize>>,
+    max_pivots: Option<usize>,
+) -> (Result<(), LpStatus>, RevisedSimplex) {
+    let limit = |simplex: RevisedSimplex| match max_pivots {
+        Some(max_pivots) => simplex.with_max_iterations(max_pivots),
+        None => simplex,
+    };
+    if let Some(basis) = basis {
+        let mut simplex = limit(RevisedSimplex::from_basis(form, basis));
+        let result = simplex.run_dual().and_then(|_| simplex.run_phase_two());
+        if result != Err(LpStatus::IterationLimit) {
+            return (result, simplex);
+        }
+    }
+    let mut simplex = limit(RevisedSimplex::new(form));
+    let result = simplex
+        .run_phase_one()
+        .and_then(|_| simplex.run_phase_two());
+    (result, simplex)
+}
+
+// basis of a solved relaxation with the slacks of the rows appended after it, none while
+// artificials are basic
+fn extend_basis(simplex: &RevisedSimplex, cols: usize, added: usize) -> Option<Vec<usize>> {
+    if simplex.basis.iter().any(|&j| j >= cols) {
+        return None;
+    }
+    let mut basis = simplex.basis.clone();
+    basis.extend(cols..cols + added);
+    Some(basis)
+}
+
+// gomory mixed integer cuts sum_j g_j z_j >= 1 from the rows of basic integer columns with
+// fractional values, z_i + sum_j a_j z_j = v gives g_j = f_j / f0 or (1 - f_j) / (1 - f0)
+// for integer columns and a_j / f0 or -a_j / (1 - f0) for continuous ones
+fn gomory_cuts(simplex: &RevisedSimplex, form: &StandardForm, integral: &[bool]) -> Vec<Vec<f32>> {
+    let cols = form.a.dims[1];
+    let is_integral = |j: usize| j < integral.len() && integral[j];
+    let z = simplex.primal();
+    let mut sources: Vec<(usize, f32)> = simplex
+        .basis
+        .iter()
+        .enumerate()
+        .filter(|&(_, &j)| j < cols && is_integral(j))
+        .map(|(r, &j)| (r, z[j] - z[j].floor()))
+        .filter(|&(_, f)| (CUT_MARGIN..=1f32 - CUT_MARGIN).contains(&f))
+        .collect();
+    sources.sort_by(|a, b| (a.1 - 0.5).abs().total_cmp(&(b.1 - 0.5).abs()));
+    let mut basic = vec![false; cols];
+    simplex
+        .basis
+        .iter()
+        .filter(|&&j| j < cols)
+        .for_each(|&j| basic[j] = true);
+    sources
+        .into_iter()
+        .take(CUTS_PER_ROUND)
+        .map(|(r, f0)| {
+            let row = simplex.tableau_row(r);
+            (0..cols)
+                .map(|j| {
+                    let a = row[j];
+                    if basic[j] || a.abs() < 1e-7 {
+                        0f32
+                    } else if is_integral(j) {
+                        let f = a - a.floor();
+                        if f <= f0 {
+                            f / f0
+                        } else {
+                            (1f32 - f) / (1f32 - f0)
+                        }
+                    } else if a > 0f32 {
+                        a / f0
+                    } else {
+                        -a / (1f32 - f0)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl BranchAndBound {
+    pub fn new(integer: Vec<bool>) -> Self {
+        Self {
+            integer,
+            selection: NodeSelection::BestBound,
+            gomory_rounds: 0,
+            max_nodes: 10000,
+            max_pivots: None,
+            time_limit: None,
+            gap_tolerance: 1e-4,
+        }
+    }
+    pub fn with_selection(mut self, selection: NodeSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+    pub fn with_gomory_rounds(mut self, gomory_rounds: usize) -> Self {
+        self.gomory_rounds = gomory_rounds;
+        self
+    }
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+    pub fn with_max_pivots(mut self, max_pivots: usize) -> Self {
+        self.max_pivots = Some(max_pivots);
+        self
+    }
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+    pub fn with_gap_tolerance(mut self, gap_tolerance: f32) -> Self {
+        self.gap_tolerance = gap_tolerance;
+        self
+    }
+    fn select(&self, open: &mut Vec<Node>) -> Option<Node> {
+        match self.selection {
+            NodeSelection::DepthFirst => open.pop(),
+            NodeSelection::BestBound => {
+                let k = (0..open.len()).min_by(|&i, &j| open[i].bound.total_cmp(&open[j].bound))?;
+                Some(open.swap_remove(k))
+            }
+        }
+    }
+    pub fn solve(&self, lp: &LinearProgram) -> MilpSolution {
+        debug_assert_eq!(self.integer.len(), lp.c.len());
+        let start = Instant::now();
+        let root = lp.standard_form();
+        let base_cols = root.a.dims[1];
+        // relaxations minimize c_std'z and c'x = c'o + sign c_std'z with x = o + Tz
+        let (t, offset) = root.embedding();
+        let sign = match lp.sense {
+            Sense::Minimize => 1f32,
+            Sense::Maximize => -1f32,
+        };
+        let constant: f32 = lp.c.iter().zip(&offset).map(|(c, o)| c * o).sum();
+        // shifted or mirrored integer variables with integral offsets give integer columns
+        let mut integral = vec![false; base_cols];
+        for (j, row) in t.data.chunks(base_cols.max(1)).enumerate() {
+            let nonzero: Vec<usize> = (0..base_cols).filter(|&k| row[k] != 0f32).collect();
+            if self.integer[j] && nonzero.len() == 1 && offset[j] == offset[j].round() {
+                integral[nonzero[0]] = true;
+            }
+        }
+
+        let mut open = vec![Node {
+            form: root,
+            basis: None,
+            bound: f32::NEG_INFINITY,
+        }];
+        // incumbent point and its relaxation objective
+        let mut incumbent: Option<(Vec<f32>, f32)> = None;
+        let mut unresolved = f32::INFINITY;
+        let (mut nodes, mut cuts) = (0, 0);
+        let mut status = None;
+        while let Some(node) = self.select(&mut open) {
+            let cutoff = incumbent.as_ref().map_or(f32::INFINITY, |(x, value)| {
+                let objective: f32 = lp.c.iter().zip(x).map(|(c, x)| c * x).sum();
+                value - self.gap_tolerance * objective.abs().max(1f32)
+            });
+            if node.bound >= cutoff {
+                continue;
+            }
+            if nodes >= self.max_nodes {
+                open.push(node);
+                status = Some(MilpStatus::NodeLimit);
+                break;
+            }
+            if self
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit)
+            {
+                open.push(node);
+                status = Some(MilpStatus::TimeLimit);
+                break;
+            }
+            nodes += 1;
+            let mut form = node.form;
+            let (mut result, mut simplex) = relax(&form, node.basis, self.max_pivots);
+            if nodes == 1 {
+                for _ in 0..self.gomory_rounds {
+                    if result.is_err() {
+                        break;
+                    }
+                    let round = gomory_cuts(&simplex, &form, &integral);
+                    if round.is_empty() {
+                        break;
+                    }
+                    let cols = form.a.dims[1];
+                    for cut in &round {
+                        let mut row = cut.clone();
+                        row.resize(form.a.dims[1], 0f32);
+                        form = form.with_row(&row, Relation::GreaterEqual, 1f32);
+                    }
+                    cuts += round.len();
+                    let basis = extend_basis(&simplex, cols, round.len());
+                    (result, simplex) = relax(&form, basis, self.max_pivots);
+                }
+            }
+            match result {
+                Ok(()) => {}
+                Err(LpStatus::Unbounded) if nodes == 1 => {
+                    status = Some(MilpStatus::Unbounded);
+                    break;
+                }
+                Err(LpStatus::IterationLimit) => {
+                    unresolved = unresolved.min(node.bound);
+                    continue;
+                }
+                Err(_) => continue,
+            }
+            let z = simplex.primal();
+            let value: f32 = form.c.iter().zip(&z).map(|(c, z)| c * z).sum();
+            if value >= cutoff {
+                continue;
+            }
+            let mut x = form.recover(&z);
+            let fractional = (0..x.len())
+                .filter(|&j| self.integer[j] && (x[j] - x[j].round()).abs() > INTEGER_TOLERANCE)
+                .max_by(|&i, &j| {
+                    let distance = |v: f32| (v - v.floor() - 0.5).abs();
+                    distance(x[j]).total_cmp(&distance(x[i]))
+                });
+            let Some(j) = fractional else {
+                (0..x.len())
+                    .filter(|&j| self.integer[j])
+                    .for_each(|j| x[j] = x[j].round());
+                incumbent = Some((x, value));
+                continue;
+            };
+            // T_j z <= floor(x_j) - o_j and T_j z >= ceil(x_j) - o_j
+            let cols = form.a.dims[1];
+            let mut row = t.data[j * base_cols..(j + 1) * base_cols].to_vec();
+            row.resize(cols, 0f32);
+            let down = (
+                form.with_row(&row, Relation::LessEqual, x[j].floor() - offset[j]),
+                value,
+            );
+            let up = (
+                form.with_row(&row, Relation::GreaterEqual, x[j].ceil() - offset[j]),
+                value,
+            );
+            // the child on the side x_j rounds to is pushed last and explored first depth first
+            let children = if x[j] - x[j].floor() < 0.5 {
+                [up, down]
+            } else {
+                [down, up]
+            };
+            for (form, bound) in children {
+                open.push(Node {
+                    form,
+                    basis: extend_basis(&simplex, cols, 1),
+                    bound,
+                });
+            }
+        }
+
+        let incumbent_value = incumbent
+            .as_ref()
+            .map_or(f32::INFINITY, |(_, value)| *value);
+        let bound = open
+            .iter()
+            .map(|node| node.bound)
+            .fold(unresolved.min(incumbent_value), f32::min);
+        let bound = constant + sign * bound;
+        let (x, objective) = match incumbent {
+            Some((x, _)) => {
+                let objective = lp.c.iter().zip(&x).map(|(c, x)| c * x).sum();
+                (x, objective)
+            }
+            None => (vec![], sign * f32::INFINITY),
+        };
+        let gap = if x.is_empty() {
+            f32::INFINITY
+        } else {
+            (objective - bound).abs() / objective.abs().max(1f32)
+        };
+        // the root relaxation has no bound, an unresolved root leaves it at -inf
+        let status = status.unwrap_or(if unresolved < f32::INFINITY && gap > self.gap_tolerance {
+            MilpStatus::NodeLimit
+        } else if x.is_empty() {
+            MilpStatus::Infeasible
+        } else {
+            MilpStatus::Optimal
+        });
+        MilpSolution {
+            status,
+            x,
+            objective,
+            bound,
+            gap,
+            nodes,
+            cuts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_milp {
+    use super::*;
+
+    // max sum v_i x_i s.t. sum w_i x_i <= capacity, x binary
+    fn knapsack(values: &[f32], weights: &[f32], capacity: f32) -> LinearProgram {
+        let mut lp = LinearProgram::new(Sense::Maximize, values.to_vec()).with_constraint(
+            weights.to_vec(),
+            Relation::LessEqual,
+            capacity,
+        );
+        for j in 0..values.len() {
+            lp = lp.with_bounds(j, 0f32, 1f32);
+        }
+        lp
+    }
+
+    #[test]
+    fn test_knapsack_matches_enumeration() {
+        let values = [
+            10f32, 13f32, 7f32, 8f32, 12f32, 5f32, 9f32, 11f32, 6f32, 4f32,
+        ];
+        let weights = [5f32, 7f32, 4f32, 4f32, 6f32, 3f32, 5f32, 6f32, 4f32, 2f32];
+        let capacity = 21f32;
+        let n = values.len();
+        let total = |mask: usize, data: &[f32]| -> f32 {
+            (0..n).filter(|j| mask >> j & 1 == 1).map(|j| data[j]).sum()
+        };
+        let best = (0..1usize << n)
+            .filter(|&mask| total(mask, &weights) <= capacity)
+            .map(|mask| total(mask, &values))
+            .fold(0f32, f32::max);
+        let lp = knapsack(&values, &weights, capacity);
+        for selection in [NodeSelection::BestBound, NodeSelection::DepthFirst] {
+            for rounds in [0, 2] {
+                let solution = BranchAndBound::new(vec![true; n])
+                    .with_selection(selection)
+                    .with_gomory_rounds(rounds)
+                    .solve(&lp);
+                assert_eq!(solution.status, MilpStatus::Optimal);
+                assert!(
+                    (solution.objective - best).abs() < 1e-3,
+                    "{selection:?} {rounds}"
+                );
+                assert!(solution.x.iter().all(|&v| v == 0f32 || v == 1f32));
+                let weight: f32 = solution.x.iter().zip(&weights).map(|(x, w)| x * w).sum();
+                assert!(weight <= capacity);
+                assert!(solution.gap <= 1e-4);
+            }
+        }
+    }
+    #[test]
+    fn test_gomory_cuts_and_mixed_columns() {
+        // max x1 s.t. 3x0 + 2x1 <= 6, -3x0 + 2x1 <= 0 has the relaxation optimum (1, 1.5) and
+        // the integer optimum x1 = 1, y is continuous with -2 <= y <= 0.5 and y <= x1 - 0.5
+        let lp = LinearProgram::new(Sense::Maximize, vec![0f32, 1f32, 1f32])
+            .with_constraint(vec![3f32, 2f32, 0f32], Relation::LessEqual, 6f32)
+            .with_constraint(vec![-3f32, 2f32, 0f32], Relation::LessEqual, 0f32)
+            .with_constraint(vec![0f32, -1f32, 1f32], Relation::LessEqual, -0.5f32)
+            .with_bounds(2, -2f32, 0.5f32);
+        let integer = vec![true, true, false];
+        let plain = BranchAndBound::new(integer.clone()).solve(&lp);
+        let cut = BranchAndBound::new(integer)
+            .with_gomory_rounds(3)
+            .solve(&lp);
+        for solution in [&plain, &cut] {
+            assert_eq!(solution.status, MilpStatus::Optimal);
+            assert!((solution.objective - 1.5f32).abs() < 1e-3, "{solution:?}");
+            assert!((solution.x[1] - 1f32).abs() < 1e-4 && (solution.x[2] - 0.5f32).abs() < 1e-3);
+        }
+        assert!(cut.cuts > 0 && cut.nodes <= plain.nodes);
+    }
+    #[test]
+    fn test_infeasible_and_limits() {
+        // 2x = 1 has no integer solution although the relaxation does
+        let lp = LinearProgram::new(Sense::Minimize, vec![1f32])
+            .with_constraint(vec![2f32], Relation::Equal, 1f32)
+            .with_bounds(0, -5f32, 5f32);
+        let solution = BranchAndBound::new(vec![true]).solve(&lp);
+        assert_eq!(solution.status, MilpStatus::Infeasible);
+        assert!(solution.x.is_empty() && solution.objective == f32::INFINITY);
+
+        let values = [10f32, 13f32, 7f32, 8f32, 12f32, 5f32, 9f32, 11f32];
+        let weights = [5f32, 7f32, 4f32, 4f32, 6f32, 3f32, 5f32, 6f32];
+        let lp = knapsack(&values, &weights, 17.5f32);
+        let solution = BranchAndBound::new(vec![true; 8])
+            .with_selection(NodeSelection::DepthFirst)
+            .with_max_nodes(3)
+            .solve(&lp);
+        assert_eq!(solution.status, MilpStatus::NodeLimit);
+        assert_eq!(solution.nodes, 3);
+        // the open nodes still bound the maximum from above
+        assert!(solution.bound >= solution.objective);
+        let solution = BranchAndBound::new(vec![true; 8])
+            .with_time_limit(Duration::ZERO)
+            .solve(&lp);
+        assert_eq!(solution.status, MilpStatus::TimeLimit);
+        assert_eq!(solution.nodes, 0);
+        // relaxations out of pivots leave the feasible knapsack unresolved, not infeasible
+        let solution = BranchAndBound::new(vec![true; 8])
+            .with_max_pivots(1)
+            .solve(&lp);
+        assert_eq!(solution.status, MilpStatus::NodeLimit);
+        assert!(solution.x.is_empty() && solution.bound == f32::INFINITY);
+    }
+}
@@ -1,5 +1,15 @@
+pub mod constrained;
 pub mod eigenvector;
 pub mod ekf_filter;
+pub mod interior_point;
+pub mod krylov;
+pub mod lanczos;
+pub mod least_squares;
 pub mod linear;
+pub mod lp_format;
+pub mod matrix_equation;
+pub mod milp;
 pub mod multi_armed_bandit;
+pub mod optimize;
+pub mod preconditioner;
 pub mod randomized_svd;
@@ -0,0 +1,771 @@
+use crate::algebra::vector::{axpy, dot_product, magnitude, norm_inf};
+use std::cell::Cell;
+use std::collections::VecDeque;
+
+// cbrt of the f32 machine epsilon, balances truncation and rounding of central differences
+pub(crate) const DIFFERENCE_STEP: f32 = 4.9e-3;
+// curvature s'y below this fraction of ||s|| ||y|| skips a quasi newton update
+const CURVATURE_TOLERANCE: f32 = 1e-6;
+
+/// Objective
+/// smooth function f: R^n -> R to be minimized, only value is required, the gradient and
+/// hessian vector products fall back to central differences
+///
+/// * value: f(x)
+/// * gradient: g <- grad f(x)
+/// * value_gradient: g <- grad f(x) and returns f(x), override when both share work
+/// * hessian_vector: hv <- H(x) v
+pub trait Objective {
+    fn value(&self, x: &[f32]) -> f32;
+    fn gradient(&self, x: &[f32], g: &mut [f32]) {
+        let mut probe = x.to_vec();
+        for i in 0..x.len() {
+            let h = DIFFERENCE_STEP * x[i].abs().max(1f32);
+            probe[i] = x[i] + h;
+            let forward = self.value(&probe);
+            probe[i] = x[i] - h;
+            let backward = self.value(&probe);
+            probe[i] = x[i];
+            g[i] = (forward - backward) / (2f32 * h);
+        }
+    }
+    fn value_gradient(&self, x: &[f32], g: &mut [f32]) -> f32 {
+        self.gradient(x, g);
+        self.value(x)
+    }
+    fn hessian_vector(&self, x: &[f32], v: &[f32], hv: &mut [f32]) {
+        let v_norm = magnitude(v);
+        if v_norm == 0f32 {
+            hv.fill(0f32);
+            return;
+        }
+        let h = DIFFERENCE_STEP * (1f32 + magnitude(x)) / v_norm;
+        let forward: Vec<f32> = x.iter().zip(v).map(|(x, v)| x + h * v).collect();
+        let backward: Vec<f32> = x.iter().zip(v).map(|(x, v)| x - h * v).collect();
+        let mut g_backward = vec![0f32; x.len()];
+        self.gradient(&forward, hv);
+        self.gradient(&backward, &mut g_backward);
+        hv.iter_mut()
+            .zip(&g_backward)
+            .for_each(|(f, b)| *f = (*f - b) / (2f32 * h));
+    }
+}
+
+/// Termination
+///
+/// * Converged: gradient or nelder mead simplex within the tolerance
+/// * Stalled: the last step decreased f by no more than rounding
+/// * LineSearchFailed: no step along the search direction met the sufficient decrease condition
+/// * MaxIterations: iteration limit reached
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Termination {
+    Converged,
+    Stalled,
+    LineSearchFailed,
+    MaxIterations,
+}
+
+/// OptimizeResult
+///
+/// * x: final iterate
+/// * value: f(x)
+/// * gradient: grad f(x), empty for nelder mead
+/// * iterations: line search steps or nelder mead simplex updates
+/// * evaluations: calls to value, including those made with a gradient
+/// * gradient_evaluations: calls to gradient
+/// * hessian_products: hessian vector products of newton cg
+/// * termination: reason the iteration stopped
+/// * history: f at the start and after every iteration
+#[derive(Clone, Debug)]
+pub struct OptimizeResult {
+    pub x: Vec<f32>,
+    pub value: f32,
+    pub gradient: Vec<f32>,
+    pub iterations: usize,
+    pub evaluations: usize,
+    pub gradient_evaluations: usize,
+    pub hessian_products: usize,
+    pub termination: Termination,
+    pub history: Vec<f32>,
+}
+
+/// LineSearch
+/// strong wolfe line search, the step grows until the minimum is bracketed and the bracket
+/// is then shrunk with safeguarded cubic interpolation
+///
+/// * c1: sufficient decrease f(x + a d) <= f(x) + c1 a g'd
+/// * c2: curvature |g(x + a d)'d| <= c2 |g'd|, 0 < c1 < c2 < 1
+/// * max_evals: evaluations of f and its gradient per search
+#[derive(Clone, Copy, Debug)]
+pub struct LineSearch {
+    pub c1: f32,
+    pub c2: f32,
+    pub max_evals: usize,
+}
+
+/// LineStep
+/// accepted point of a line search
+///
+/// * alpha: step length along the direction
+/// * x: x + alpha d
+/// * value: f at x
+/// * gradient: grad f at x
+pub struct LineStep {
+    pub alpha: f32,
+    pub x: Vec<f32>,
+    pub value: f32,
+    pub gradient: Vec<f32>,
+}
+
+/// Optimizer
+/// settings shared by the unconstrained minimizers
+///
+/// * tolerance: stop once ||grad f||_inf <= tolerance, nelder mead stops once the simplex and
+///   its values both spread less than tolerance
+/// * max_iters: maximum number of iterations
+/// * memory: correction pairs kept by l-bfgs
+/// * line_search: line search of the gradient based methods
+pub struct Optimizer {
+    pub tolerance: f32,
+    pub max_iters: usize,
+    pub memory: usize,
+    pub line_search: LineSearch,
+}
+
+// counts the calls made by a minimizer
+struct Counted<'a> {
+    objective: &'a dyn Objective,
+    values: Cell<usize>,
+    gradients: Cell<usize>,
+    products: Cell<usize>,
+}
+
+impl Objective for Counted<'_> {
+    fn value(&self, x: &[f32]) -> f32 {
+        self.values.set(self.values.get() + 1);
+        self.objective.value(x)
+    }
+    fn gradient(&self, x: &[f32], g: &mut [f32]) {
+        self.gradients.set(self.gradients.get() + 1);
+        self.objective.gradient(x, g)
+    }
+    fn value_gradient(&self, x: &[f32], g: &mut [f32]) -> f32 {
+        self.values.set(self.values.get() + 1);
+        self.gradients.set(self.gradients.get() + 1);
+        self.objective.value_gradient(x, g)
+    }
+    fn hessian_vector(&self, x: &[f32], v: &[f32], hv: &mut [f32]) {
+        self.products.set(self.products.get() + 1);
+        self.objective.hessian_vector(x, v, hv)
+    }
+}
+
+// phi(a) = f(x + a d) with its slope phi'(a) = g(x + a d)'d
+struct Trial {
+    alpha: f32,
+    x: Vec<f32>,
+    value: f32,
+    gradient: Vec<f32>,
+    slope: f32,
+}
+
+impl Default for LineSearch {
+    fn default() -> Self {
+        Self {
+            c1: 1e-4,
+            c2: 0.9,
+            max_evals: 30,
+        }
+    }
+}
+
+impl LineSearch {
+    pub fn new(c1: f32, c2: f32) -> Self {
+        debug_assert!(0f32 < c1 && c1 < c2 && c2 < 1f32);
+        Self {
+            c1,
+            c2,
+            ..Self::default()
+        }
+    }
+    pub fn with_max_evals(mut self, max_evals: usize) -> Self {
+        self.max_evals = max_evals;
+        self
+    }
+    fn trial(objective: &dyn Objective, x: &[f32], d: &[f32], alpha: f32) -> Trial {
+        let x: Vec<f32> = x.iter().zip(d).map(|(x, d)| x + alpha * d).collect();
+        let mut gradient = vec![0f32; x.len()];
+        let value = objective.value_gradient(&x, &mut gradient);
+        let slope = dot_product(&gradient, d);
+        Trial {
+            alpha,
+            x,
+            value,
+            gradient,
+            slope,
+        }
+    }
+    /// search
+    /// step along a descent direction d from x with f(x) = value and g = grad f(x), alpha0
+    /// is the first trial step, none when no step decreases f sufficiently, a step that only
+    /// decreases f sufficiently is returned when the evaluations run out
+    pub fn search(
+        &self,
+        objective: &dyn Objective,
+        x: &[f32],
+        value: f32,
+        g: &[f32],
+        d: &[f32],
+        alpha0: f32,
+    ) -> Option<LineStep> {
+        let slope0 = dot_product(g, d);
+        if slope0 >= 0f32 {
+            return None;
+        }
+        let armijo = |t: &Trial| t.value <= value + self.c1 * t.alpha * slope0;
+        let curvature = |t: &Trial| t.slope.abs() <= -self.c2 * slope0;
+        let mut previous = Trial {
+            alpha: 0f32,
+            x: x.to_vec(),
+            value,
+            gradient: g.to_vec(),
+            slope: slope0,
+        };
+        let mut alpha = alpha0;
+        let mut evals = 0;
+        // bracketing, the bracket is (lo, hi) with lo the best point meeting sufficient decrease
+        let (mut lo, mut hi) = loop {
+            if evals >= self.max_evals {
+                return (previous.alpha > 0f32).then(|| previous.into());
+            }
+            let trial = Self::trial(objective, x, d, alpha);
+            evals += 1;
+            if !trial.value.is_finite() {
+                // shrink back towards the last finite point
+                alpha = 0.5 * (previous.alpha + alpha);
+                continue;
+            }
+            if !armijo(&trial) || (previous.alpha > 0f32 && trial.value >= previous.value) {
+                break (previous, trial);
+            }
+            if curvature(&trial) {
+                return Some(trial.into());
+            }
+            if trial.slope >= 0f32 {
+                break (trial, previous);
+            }
+            alpha = 2f32 * trial.alpha;
+            previous = trial;
+        };
+        // zoom
+        while evals < self.max_evals {
+            let alpha = cubic_step(&lo, &hi);
+            if (hi.alpha - lo.alpha).abs() <= f32::EPSILON * lo.alpha.abs().max(1f32) {
+                break;
+            }
+            let trial = Self::trial(objective, x, d, alpha);
+            evals += 1;
+            if !armijo(&trial) || trial.value >= lo.value {
+                hi = trial;
+            } else {
+                if curvature(&trial) {
+                    return Some(trial.into());
+                }
+                if trial.slope * (hi.alpha - lo.alpha) >= 0f32 {
+                    hi = lo;
+                }
+                lo = trial;
+            }
+        }
+        (lo.alpha > 0f32).then(|| lo.into())
+    }
+}
+
+impl From<Trial> for LineStep {
+    fn from(trial: Trial) -> Self {
+        Self {
+            alpha: trial.alpha,
+            x: trial.x,
+            value: trial.value,
+            gradient: trial.gradient,
+        }
+    }
+}
+
+// minimizer of the cubic through the values and slopes at both ends of the bracket, kept
+// away from the ends and replaced by bisection when the cubic has no minimizer
+fn cubic_step(lo: &Trial, hi: &Trial) -> f32 {
+    let (a, b) = (lo.alpha, hi.alpha);
+    let d1 = lo.slope + hi.slope - 3f32 * (lo.value - hi.value) / (a - b);
+    let discriminant = d1 * d1 - lo.slope * hi.slope;
+    let (left, right) = (a.min(b), a.max(b));
+    let margin = 0.1 * (right - left);
+    if discriminant >= 0f32 {
+        let d2 = (b - a).signum() * discriminant.sqrt();
+        let step = b - (b - a) * (hi.slope + d2 - d1) / (hi.slope - lo.slope + 2f32 * d2);
+        if step.is_finite() && step >= left + margin && step <= right - margin {
+            return step;
+        }
+    }
+    0.5 * (a + b)
+}
+
+impl Optimizer {
+    pub fn new(tolerance: f32, max_iters: usize) -> Self {
+        Self {
+            tolerance,
+            max_iters,
+            memory: 10,
+            line_search: LineSearch::default(),
+        }
+    }
+    pub fn with_memory(mut self, memory: usize) -> Self {
+        self.memory = memory.max(1);
+        self
+    }
+    pub fn with_line_search(mut self, line_search: LineSearch) -> Self {
+        self.line_search = line_search;
+        self
+    }
+
+    // shared loop of the line search methods, direction gets the objective, x, g and the
+    // step s = x_k+1 - x_k with y = g_k+1 - g_k of the last iteration, directions that do
+    // not descend are replaced by -g, unit is whether 1 is the natural step length
+    fn descend<F>(
+        &self,
+        objective: &dyn Objective,
+        x0: &[f32],
+        unit: bool,
+        mut direction: F,
+    ) -> OptimizeResult
+    where
+        F: FnMut(&dyn Objective, &[f32], &[f32], Option<(&[f32], &[f32])>) -> Vec<f32>,
+    {
+        let counted = Counted {
+            objective,
+            values: Cell::new(0),
+            gradients: Cell::new(0),
+            products: Cell::new(0),
+        };
+        let mut x = x0.to_vec();
+        let mut g = vec![0f32; x.len()];
+        let mut value = counted.value_gradient(&x, &mut g);
+        let mut history = vec![value];
+        let mut update: Option<(Vec<f32>, Vec<f32>)> = None;
+        // alpha * g'd of the last step for the step guess of gradient descent
+        let mut last_decrease: Option<f32> = None;
+        let mut iterations = 0;
+        let mut termination = Termination::MaxIterations;
+        loop {
+            if norm_inf(&g) <= self.tolerance {
+                termination = Termination::Converged;
+                break;
+            }
+            if iterations >= self.max_iters {
+                break;
+            }
+            let step = update.as_ref().map(|(s, y)| (s.as_slice(), y.as_slice()));
+            let mut d = direction(&counted, &x, &g, step);
+            let mut slope = dot_product(&g, &d);
+            if slope >= 0f32 || slope.is_nan() {
+                d = g.iter().map(|v| -v).collect();
+                slope = -dot_product(&g, &g);
+            }
+            let alpha0 = match last_decrease {
+                _ if unit && update.is_some() => 1f32,
+                Some(decrease) => (decrease / slope).min(1f32),
+                None => (1f32 / norm_inf(&d)).min(1f32),
+            };
+            let Some(step) = self.line_search.search(&counted, &x, value, &g, &d, alpha0) else {
+                termination = Termination::LineSearchFailed;
+                break;
+            };
+            iterations += 1;
+            last_decrease = Some(step.alpha * slope);
+            let s: Vec<f32> = step.x.iter().zip(&x).map(|(a, b)| a - b).collect();
+            let y: Vec<f32> = step.gradient.iter().zip(&g).map(|(a, b)| a - b).collect();
+            let decrease = value - step.value;
+            (x, g, value) = (step.x, step.gradient, step.value);
+            history.push(value);
+            update = Some((s, y));
+            if decrease <= f32::EPSILON * value.abs().max(1f32) && norm_inf(&g) > self.tolerance {
+                termination = Termination::Stalled;
+                break;
+            }
+        }
+        OptimizeResult {
+            x,
+            value,
+            gradient: g,
+            iterations,
+            evaluations: counted.values.get(),
+            gradient_evaluations: counted.gradients.get(),
+            hessian_products: counted.products.get(),
+            termination,
+            history,
+        }
+    }
+
+    /// gradient_descent
+    /// steepest descent d = -g, the first trial step keeps the decrease of the last step
+    pub fn gradient_descent(&self, objective: &dyn Objective, x0: &[f32]) -> OptimizeResult {
+        self.descend(objective, x0, false, |_, _, g, _| {
+            g.iter().map(|v| -v).collect()
+        })
+    }
+
+    /// bfgs
+    /// dense inverse hessian approximation H with d = -Hg, H starts as (s'y / y'y) I after
+    /// the first step and updates are skipped when s'y is not safely positive
+    pub fn bfgs(&self, objective: &dyn Objective, x0: &[f32]) -> OptimizeResult {
+        let n = x0.len();
+        let mut h: Option<Vec<f32>> = None;
+        self.descend(objective, x0, true, |_, _, g, step| {
+            if let Some((s, y)) = step {
+                let sy = dot_product(s, y);
+                if sy > CURVATURE_TOLERANCE * magnitude(s) * magnitude(y) {
+                    let h = h.get_or_insert_with(|| {
+                        let mut h = vec![0f32; n * n];
+                        (0..n).for_each(|i| h[i * n + i] = sy / dot_product(y, y));
+                        h
+                    });
+                    // H <- H - rho (s (Hy)' + (Hy) s') + (rho^2 y'Hy + rho) s s'
+                    let rho = 1f32 / sy;
+                    let hy: Vec<f32> = h.chunks(n).map(|row| dot_product(row, y)).collect();
+                    let scale = rho * rho * dot_product(y, &hy) + rho;
+                    for i in 0..n {
+                        for j in 0..n {
+                            h[i * n + j] +=
+                                scale * s[i] * s[j] - rho * (s[i] * hy[j] + hy[i] * s[j]);
+                        }
+                    }
+                }
+            }
+            match &h {
+                Some(h) => h.chunks(n).map(|row| -dot_product(row, g)).collect(),
+                None => g.iter().map(|v| -v).collect(),
+            }
+        })
+    }
+
+    /// lbfgs
+    /// limited memory bfgs, d = -Hg by the two loop recursion over the last memory pairs
+    /// (s, y) with H0 = (s'y / y'y) I
+    pub fn lbfgs(&self, objective: &dyn Objective, x0: &[f32]) -> OptimizeResult {
+        let mut pairs: VecDeque<(Vec<f32>, Vec<f32>, f32)> = VecDeque::with_capacity(self.memory);
+        self.descend(objective, x0, true, |_, _, g, step| {
+            if let Some((s, y)) = step {
+                let sy = dot_product(s, y);
+                if sy > CURVATURE_TOLERANCE * magnitude(s) * magnitude(y) {
+                    if pairs.len() == self.memory {
+                        pairs.pop_front();
+                    }
+                    pairs.push_back((s.to_vec(), y.to_vec(), 1f32 / sy));
+                }
+            }
+            let mut q = g.to_vec();
+            let mut alphas = vec![0f32; pairs.len()];
+            for (k, (s, y, rho)) in pairs.iter().enumerate().rev() {
+                alphas[k] = rho * dot_product(s, &q);
+                q.iter_mut().zip(y).for_each(|(q, y)| *q -= alphas[k] * y);
+            }
+            let gamma = pairs
+                .back()
+                .map_or(1f32, |(_, y, rho)| 1f32 / (rho * dot_product(y, y)));
+            q.iter_mut().for_each(|q| *q *= gamma);
+            for (k, (s, y, rho)) in pairs.iter().enumerate() {
+                let beta = rho * dot_product(y, &q);
+                q.iter_mut()
+                    .zip(s)
+                    .for_each(|(q, s)| *q += (alphas[k] - beta) * s);
+            }
+            q.iter_mut().for_each(|q| *q = -*q);
+            q
+        })
+    }
+
+    /// newton_cg
+    /// truncated newton, conjugate gradient on H d = -g stops at the relative residual
+    /// min(0.5, sqrt ||g||) or at negative curvature, the hessian only enters through
+    /// hessian_vector
+    pub fn newton_cg(&self, objective: &dyn Objective, x0: &[f32]) -> OptimizeResult {
+        let n = x0.len();
+        self.descend(objective, x0, true, |objective, x, g, _| {
+            let g_norm = magnitude(g);
+            let forcing = 0.5f32.min(g_norm.sqrt()) * g_norm;
+            let mut d = vec![0f32; n];
+            let mut r: Vec<f32> = g.iter().map(|v| -v).collect();
+            let mut p = r.clone();
+            let mut hp = vec![0f32; n];
+            let mut rr = dot_product(&r, &r);
+            for k in 0..2 * n.max(1) {
+                objective.hessian_vector(x, &p, &mut hp);
+                let curvature = dot_product(&p, &hp);
+                if curvature <= f32::EPSILON * dot_product(&p, &p) {
+                    // negative curvature, the steepest descent direction when nothing is built
+                    if k == 0 {
+                        d = r;
+                    }
+                    break;
+                }
+                let alpha = rr / curvature;
+                axpy(alpha, &p, &mut d);
+                axpy(-alpha, &hp, &mut r);
+                let rr_next = dot_product(&r, &r);
+                if rr_next.sqrt() <= forcing {
+                    break;
+                }
+                let beta = rr_next / rr;
+                p.iter_mut().zip(&r).for_each(|(p, r)| *p = r + beta * *p);
+                rr = rr_next;
+            }
+            d
+        })
+    }
+
+    /// nelder_mead
+    /// derivative free downhill simplex with the dimension adapted coefficients of gao and han,
+    /// the initial simplex perturbs every coordinate of x0 by 5% or 2.5e-4 at zero
+    pub fn nelder_mead(&self, objective: &dyn Objective, x0: &[f32]) -> OptimizeResult {
+        let n = x0.len();
+        let nf = n.max(1) as f32;
+        let (expansion, contraction, shrink) =
+            (1f32 + 2f32 / nf, 0.75 - 0.5 / nf, 1f32 - 1f32 / nf);
+        let mut evaluations = 0;
+        let mut evaluate = |x: &[f32]| {
+            evaluations += 1;
+            objective.value(x)
+        };
+        let mut simplex: Vec<(Vec<f32>, f32)> = Vec::with_capacity(n + 1);
+        simplex.push((x0.to_vec(), evaluate(x0)));
+        for i in 0..n {
+            let mut x = x0.to_vec();
+            x[i] = if x[i] != 0f32 { 1.05 * x[i] } else { 2.5e-4 };
+            let value = evaluate(&x);
+            simplex.push((x, value));
+        }
+        let mut history = vec![];
+        let mut iterations = 0;
+        let mut termination = Termination::MaxIterations;
+        loop {
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            history.push(simplex[0].1);
+            let (best, best_value) = (&simplex[0].0, simplex[0].1);
+            let spread = simplex[1..].iter().fold(0f32, |acc, (x, value)| {
+                let distance = x
+                    .iter()
+                    .zip(best)
+                    .fold(0f32, |m, (a, b)| m.max((a - b).abs()));
+                acc.max(distance).max((value - best_value).abs())
+            });
+            if spread <= self.tolerance {
+                termination = Termination::Converged;
+                break;
+            }
+            if iterations >= self.max_iters {
+                break;
+            }
+            iterations += 1;
+            let worst = simplex[n].clone();
+            let mut centroid = vec![0f32; n];
+            for (x, _) in &simplex[..n] {
+                centroid.iter_mut().zip(x).for_each(|(c, x)| *c += x / nf);
+            }
+            // c + t (c - worst)
+            let along = |t: f32| -> Vec<f32> {
+                centroid
+                    .iter()
+                    .zip(&worst.0)
+                    .map(|(c, w)| c + t * (c - w))
+                    .collect()
+            };
+            let reflected = along(1f32);
+            let reflected_value = evaluate(&reflected);
+            if reflected_value < simplex[0].1 {
+                let expanded = along(expansion);
+                let expanded_value = evaluate(&expanded);
+                simplex[n] = if expanded_value < reflected_value {
+                    (expanded, expanded_value)
+                } else {
+                    (reflected, reflected_value)
+                };
+                continue;
+            }
+            if reflected_value < simplex[n - 1].1 {
+                simplex[n] = (reflected, reflected_value);
+                continue;
+            }
+            // outside contraction towards the reflection, inside towards the worst vertex
+            let (contracted, bar) = if reflected_value < worst.1 {
+                (along(contraction), reflected_value)
+            } else {
+                (along(-contraction), worst.1)
+            };
+            let contracted_value = evaluate(&contracted);
+            if contracted_value < bar {
+                simplex[n] = (contracted, contracted_value);
+                continue;
+            }
+            let best = simplex[0].0.clone();
+            for (x, value) in simplex.iter_mut().skip(1) {
+                x.iter_mut()
+                    .zip(&best)
+                    .for_each(|(x, b)| *x = b + shrink * (*x - b));
+                *value = evaluate(x);
+            }
+        }
+        let (x, value) = simplex.swap_remove(0);
+        OptimizeResult {
+            x,
+            value,
+            gradient: vec![],
+            iterations,
+            evaluations,
+            gradient_evaluations: 0,
+            hessian_products: 0,
+            termination,
+            history,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_optimize {
+    use super::*;
+
+    // sum 100 (x_2i+1 - x_2i^2)^2 + (1 - x_2i)^2 with the minimum at x = 1
+    struct Rosenbrock;
+
+    impl Objective for Rosenbrock {
+        fn value(&self, x: &[f32]) -> f32 {
+            x.chunks(2)
+                .map(|p| 100f32 * (p[1] - p[0] * p[0]).powi(2) + (1f32 - p[0]).powi(2))
+                .sum()
+        }
+        fn gradient(&self, x: &[f32], g: &mut [f32]) {
+            for (p, g) in x.chunks(2).zip(g.chunks_mut(2)) {
+                let t = p[1] - p[0] * p[0];
+                g[0] = -400f32 * p[0] * t - 2f32 * (1f32 - p[0]);
+                g[1] = 200f32 * t;
+            }
+        }
+        fn hessian_vector(&self, x: &[f32], v: &[f32], hv: &mut [f32]) {
+            for ((p, v), hv) in x.chunks(2).zip(v.chunks(2)).zip(hv.chunks_mut(2)) {
+                let h00 = 1200f32 * p[0] * p[0] - 400f32 * p[1] + 2f32;
+                let h01 = -400f32 * p[0];
+                hv[0] = h00 * v[0] + h01 * v[1];
+                hv[1] = h01 * v[0] + 200f32 * v[1];
+            }
+        }
+    }
+
+    // 1/2 sum d_i x_i^2 - x_i with x* = 1 / d, value only
+    struct Quadratic(Vec<f32>);
+
+    impl Objective for Quadratic {
+        fn value(&self, x: &[f32]) -> f32 {
+            x.iter()
+                .zip(&self.0)
+                .map(|(x, d)| 0.5 * d * x * x - x)
+                .sum()
+        }
+    }
+
+    #[test]
+    fn test_rosenbrock_quasi_newton() {
+        let x0: Vec<f32> = (0..6)
+            .map(|i| if i % 2 == 0 { -1.2 } else { 1f32 })
+            .collect();
+        let optimizer = Optimizer::new(1e-3, 500);
+        let results = [
+            optimizer.bfgs(&Rosenbrock, &x0),
+            optimizer.lbfgs(&Rosenbrock, &x0),
+            optimizer.with_memory(3).lbfgs(&Rosenbrock, &x0),
+            Optimizer::new(1e-3, 500).newton_cg(&Rosenbrock, &x0),
+        ];
+        for result in &results {
+            assert_eq!(result.termination, Termination::Converged);
+            assert!(
+                result.x.iter().all(|v| (v - 1f32).abs() < 1e-2),
+                "{:?}",
+                result.x
+            );
+            assert!(result.value < 1e-4);
+            assert_eq!(result.history.len(), result.iterations + 1);
+            assert!(result.history.windows(2).all(|w| w[1] <= w[0]));
+        }
+        assert!(results[3].hessian_products > 0);
+    }
+    #[test]
+    fn test_difference_derivatives() {
+        // condition number 100, the gradient and hessian products come from differences
+        let d: Vec<f32> = (0..8).map(|i| 1f32 + 99f32 * i as f32 / 7f32).collect();
+        let objective = Quadratic(d.clone());
+        let x0 = vec![0f32; 8];
+        let mut g = vec![0f32; 8];
+        objective.gradient(&[1f32; 8], &mut g);
+        assert!(
+            g.iter()
+                .zip(&d)
+                .all(|(g, d)| (g - (d - 1f32)).abs() < 1e-2 * d)
+        );
+
+        // f32 values resolve the gradient of the differences to about 1e-2
+        let descent = Optimizer::new(1e-2, 2000).gradient_descent(&objective, &x0);
+        let newton = Optimizer::new(1e-2, 50).newton_cg(&objective, &x0);
+        for result in [&descent, &newton] {
+            assert_eq!(result.termination, Termination::Converged);
+            // g = Dx - 1 gives x_i d_i - 1 = g_i
+            assert!(
+                result
+                    .x
+                    .iter()
+                    .zip(&d)
+                    .all(|(x, d)| (x * d - 1f32).abs() <= 1e-2)
+            );
+        }
+        // inexact newton steps converge superlinearly, steepest descent zig zags
+        assert!(newton.iterations < 10 && descent.iterations > 10 * newton.iterations);
+    }
+    #[test]
+    fn test_nelder_mead() {
+        let result = Optimizer::new(1e-5, 2000).nelder_mead(&Rosenbrock, &[-1.2f32, 1f32]);
+        assert_eq!(result.termination, Termination::Converged);
+        assert!(
+            result.x.iter().all(|v| (v - 1f32).abs() < 1e-2),
+            "{:?}",
+            result.x
+        );
+        assert!(result.gradient.is_empty() && result.gradient_evaluations == 0);
+        let quadratic = Quadratic(vec![1f32, 4f32, 9f32]);
+        let result = Optimizer::new(1e-6, 2000).nelder_mead(&quadratic, &[0f32; 3]);
+        for (x, d) in result.x.iter().zip([1f32, 4f32, 9f32]) {
+            assert!((x * d - 1f32).abs() < 1e-2);
+        }
+    }
+    #[test]
+    fn test_strong_wolfe() {
+        let x = [-1.2f32, 1f32];
+        let mut g = [0f32; 2];
+        let value = Rosenbrock.value_gradient(&x, &mut g);
+        let d = [-g[0], -g[1]];
+        let slope = dot_product(&g, &d);
+        for (search, alpha0) in [
+            (LineSearch::default(), 1f32),
+            (LineSearch::new(1e-4, 0.1), 1e-4),
+        ] {
+            let step = search
+                .search(&Rosenbrock, &x, value, &g, &d, alpha0)
+                .unwrap();
+            assert!(step.value <= value + search.c1 * step.alpha * slope);
+            assert!(dot_product(&step.gradient, &d).abs() <= -search.c2 * slope);
+        }
+        // an ascent direction has no step
+        let up = [g[0], g[1]];
+        assert!(
+            LineSearch::default()
+                .search(&Rosenbrock, &x, value, &g, &up, 1f32)
+                .is_none()
+        );
+    }
+}
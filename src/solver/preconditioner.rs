@@ -0,0 +1,415 @@
+use crate::decomposition::lower_upper::LuPivotDecompose;
+use crate::structure::csr::CsrMatrix;
+use crate::structure::ndarray::NdArray;
+use crate::structure::operator::LinearOperator;
+
+const EPSILON: f32 = 1e-8;
+
+/// Preconditioner
+/// approximate inverse M ~ A^-1 used inside iterative solvers
+///
+/// * precondition: z <- Mr
+pub trait Preconditioner {
+    fn precondition(&self, r: &[f32], z: &mut [f32]);
+}
+
+// any explicit operator acts as its own preconditioner
+impl<T: LinearOperator> Preconditioner for T {
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        self.apply(r, z)
+    }
+}
+
+// guards against zero pivots on singular or indefinite inputs
+fn safe_inverse(v: f32) -> f32 {
+    if v.abs() > EPSILON { 1f32 / v } else { 1f32 }
+}
+
+/// Jacobi
+/// diagonal scaling M = D^-1
+///
+/// * inverse_diagonal: 1 / a_ii
+pub struct Jacobi {
+    pub inverse_diagonal: Vec<f32>,
+}
+
+impl Jacobi {
+    pub fn new(a: &CsrMatrix) -> Self {
+        Self {
+            inverse_diagonal: a.diagonal().into_iter().map(safe_inverse).collect(),
+        }
+    }
+}
+
+impl Preconditioner for Jacobi {
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        for ((zi, ri), di) in z.iter_mut().zip(r).zip(&self.inverse_diagonal) {
+            *zi = ri * di;
+        }
+    }
+}
+
+/// BlockJacobi
+/// block diagonal of A factored by lu, couplings between blocks are dropped
+///
+/// * blocks: (offset, factorization) of every diagonal block
+pub struct BlockJacobi {
+    pub blocks: Vec<(usize, LuPivotDecompose)>,
+}
+
+impl BlockJacobi {
+    pub fn new(a: &CsrMatrix, block_size: usize) -> Self {
+        let n = a.rows;
+        let block_size = block_size.max(1);
+        let mut workspace = vec![0f32; block_size];
+        let blocks = (0..n)
+            .step_by(block_size)
+            .map(|offset| {
+                let size = block_size.min(n - offset);
+                let mut block = NdArray::new(vec![size, size], vec![0f32; size * size]);
+                for i in 0..size {
+                    let (cols, values) = a.row(offset + i);
+                    for (&j, &v) in cols.iter().zip(values) {
+                        if (offset..offset + size).contains(&j) {
+                            block.data[i * size + j - offset] = v;
+                        }
+                    }
+                }
+                (offset, LuPivotDecompose::new(block, &mut workspace))
+            })
+            .collect();
+        Self { blocks }
+    }
+}
+
+impl Preconditioner for BlockJacobi {
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        z.copy_from_slice(r);
+        for (offset, lu) in &self.blocks {
+            let size = lu.matrix.dims[0];
+            lu.solve_inplace_vec(&mut z[*offset..*offset + size]);
+        }
+    }
+}
+
+/// Ssor
+/// symmetric successive over relaxation,
+/// A ~ (D / w + L) (D / w)^-1 (D / w + U) / (2 - w)
+///
+/// * matrix: A, kept for the triangular sweeps
+/// * diagonal: a_ii
+/// * omega: relaxation factor in (0, 2)
+pub struct Ssor {
+    pub matrix: CsrMatrix,
+    pub diagonal: Vec<f32>,
+    pub omega: f32,
+}
+
+impl Ssor {
+    pub fn new(a: &CsrMatrix, omega: f32) -> Self {
+        debug_assert!(omega > 0f32 && omega < 2f32);
+        Self {
+            matrix: a.clone(),
+            diagonal: a.diagonal(),
+            omega,
+        }
+    }
+}
+
+impl Preconditioner for Ssor {
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        let n = self.matrix.rows;
+        let w = self.omega;
+        // (D / w + L) y = r
+        for i in 0..n {
+            let (cols, values) = self.matrix.row(i);
+            let mut sum = r[i];
+            for (&j, v) in cols.iter().zip(values).take_while(|&(&j, _)| j < i) {
+                sum -= v * z[j];
+            }
+            z[i] = sum * w * safe_inverse(self.diagonal[i]);
+        }
+        // y <- D / w y, then (D / w + U) z = y
+        for i in 0..n {
+            z[i] *= self.diagonal[i] / w;
+        }
+        for i in (0..n).rev() {
+            let (cols, values) = self.matrix.row(i);
+            let mut sum = z[i];
+            for (&j, v) in cols.iter().zip(values).filter(|&(&j, _)| j > i) {
+                sum -= v * z[j];
+            }
+            z[i] = sum * w * safe_inverse(self.diagonal[i]);
+        }
+        z.iter_mut().for_each(|zi| *zi *= 2f32 - w);
+    }
+}
+
+/// IncompleteLu
+/// ilu(0), L and U restricted to the sparsity pattern of A and stored in place of it
+///
+/// * factors: unit lower L below the diagonal, U on and above it
+/// * diagonal: position of the diagonal entry of every row in factors
+pub struct IncompleteLu {
+    pub factors: CsrMatrix,
+    pub diagonal: Vec<usize>,
+}
+
+fn diagonal_positions(a: &CsrMatrix) -> Vec<usize> {
+    (0..a.rows)
+        .map(|i| {
+            let (cols, _) = a.row(i);
+            a.row_ptr[i]
+                + cols
+                    .binary_search(&i)
+                    .expect("incomplete factorization requires a stored diagonal")
+        })
+        .collect()
+}
+
+impl IncompleteLu {
+    pub fn new(a: &CsrMatrix) -> Self {
+        debug_assert_eq!(a.rows, a.cols);
+        let n = a.rows;
+        let mut f = a.clone();
+        let diagonal = diagonal_positions(&f);
+        // position of column j within the current row, usize::MAX outside the pattern
+        let mut marker = vec![usize::MAX; n];
+        for i in 1..n {
+            let (start, end) = (f.row_ptr[i], f.row_ptr[i + 1]);
+            for p in start..end {
+                marker[f.col_idx[p]] = p;
+            }
+            // ikj variant: a_ik /= u_kk, a_ij -= a_ik u_kj inside the pattern
+            for p in start..diagonal[i] {
+                let k = f.col_idx[p];
+                f.values[p] *= safe_inverse(f.values[diagonal[k]]);
+                let lik = f.values[p];
+                for q in diagonal[k] + 1..f.row_ptr[k + 1] {
+                    let target = marker[f.col_idx[q]];
+                    if target != usize::MAX {
+                        f.values[target] -= lik * f.values[q];
+                    }
+                }
+            }
+            for p in start..end {
+                marker[f.col_idx[p]] = usize::MAX;
+            }
+        }
+        Self {
+            factors: f,
+            diagonal,
+        }
+    }
+    pub fn forward_solve_inplace_vec(&self, y: &mut [f32]) {
+        // Lz = y, unit diagonal
+        let f = &self.factors;
+        for i in 0..f.rows {
+            for p in f.row_ptr[i]..self.diagonal[i] {
+                y[i] -= f.values[p] * y[f.col_idx[p]];
+            }
+        }
+    }
+    pub fn backward_solve_inplace_vec(&self, z: &mut [f32]) {
+        // Ux = z
+        let f = &self.factors;
+        for i in (0..f.rows).rev() {
+            for p in self.diagonal[i] + 1..f.row_ptr[i + 1] {
+                z[i] -= f.values[p] * z[f.col_idx[p]];
+            }
+            z[i] *= safe_inverse(f.values[self.diagonal[i]]);
+        }
+    }
+}
+
+impl Preconditioner for IncompleteLu {
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        z.copy_from_slice(r);
+        self.forward_solve_inplace_vec(z);
+        self.backward_solve_inplace_vec(z);
+    }
+}
+
+/// IncompleteCholesky
+/// ic(0) of a symmetric positive definite matrix, L restricted to the lower pattern of A
+///
+/// * lower: L with the diagonal as the last entry of every row
+pub struct IncompleteCholesky {
+    pub lower: CsrMatrix,
+}
+
+impl IncompleteCholesky {
+    pub fn new(a: &CsrMatrix) -> Self {
+        debug_assert_eq!(a.rows, a.cols);
+        let n = a.rows;
+        let mut row_ptr = vec![0];
+        let (mut col_idx, mut values) = (Vec::new(), Vec::new());
+        for i in 0..n {
+            let (cols, vals) = a.row(i);
+            for (&j, &v) in cols.iter().zip(vals).filter(|&(&j, _)| j <= i) {
+                col_idx.push(j);
+                values.push(v);
+            }
+            debug_assert_eq!(col_idx.last(), Some(&i), "ic(0) requires a stored diagonal");
+            row_ptr.push(col_idx.len());
+        }
+        let mut l = CsrMatrix::new(n, n, row_ptr, col_idx, values);
+        let mut marker = vec![usize::MAX; n];
+        for i in 0..n {
+            let (start, end) = (l.row_ptr[i], l.row_ptr[i + 1]);
+            for p in start..end {
+                marker[l.col_idx[p]] = p;
+            }
+            // l_ij = (a_ij - sum_k<j l_ik l_jk) / l_jj, l_ii = sqrt(a_ii - sum_k<i l_ik^2)
+            for p in start..end {
+                let j = l.col_idx[p];
+                let mut sum = l.values[p];
+                for q in l.row_ptr[j]..l.row_ptr[j + 1] - 1 {
+                    let k = marker[l.col_idx[q]];
+                    if k != usize::MAX && k < p {
+                        sum -= l.values[k] * l.values[q];
+                    }
+                }
+                l.values[p] = if j < i {
+                    sum * safe_inverse(l.values[l.row_ptr[j + 1] - 1])
+                } else {
+                    sum.max(EPSILON).sqrt()
+                };
+            }
+            for p in start..end {
+                marker[l.col_idx[p]] = usize::MAX;
+            }
+        }
+        Self { lower: l }
+    }
+    pub fn forward_solve_inplace_vec(&self, y: &mut [f32]) {
+        // Lz = y
+        let l = &self.lower;
+        for i in 0..l.rows {
+            let last = l.row_ptr[i + 1] - 1;
+            for p in l.row_ptr[i]..last {
+                y[i] -= l.values[p] * y[l.col_idx[p]];
+            }
+            y[i] /= l.values[last];
+        }
+    }
+    pub fn backward_solve_inplace_vec(&self, z: &mut [f32]) {
+        // L'x = z, column oriented over the rows of L
+        let l = &self.lower;
+        for i in (0..l.rows).rev() {
+            let last = l.row_ptr[i + 1] - 1;
+            z[i] /= l.values[last];
+            for p in l.row_ptr[i]..last {
+                z[l.col_idx[p]] -= l.values[p] * z[i];
+            }
+        }
+    }
+}
+
+impl Preconditioner for IncompleteCholesky {
+    fn precondition(&self, r: &[f32], z: &mut [f32]) {
+        z.copy_from_slice(r);
+        self.forward_solve_inplace_vec(z);
+        self.backward_solve_inplace_vec(z);
+    }
+}
+
+#[cfg(test)]
+mod test_preconditioner {
+    use super::*;
+    use crate::decomposition::cholesky::Cholesky;
+    use crate::random::generation::generate_random_vector;
+    use crate::solver::krylov::Krylov;
+
+    // five point laplacian on a k x k grid with an optional convection term
+    fn grid(k: usize, convection: f32) -> CsrMatrix {
+        let n = k * k;
+        let mut row_ptr = vec![0];
+        let (mut col_idx, mut values) = (Vec::new(), Vec::new());
+        for i in 0..n {
+            let (r, c) = (i / k, i % k);
+            let mut push = |j: usize, v: f32| {
+                col_idx.push(j);
+                values.push(v);
+            };
+            if r > 0 {
+                push(i - k, -1f32);
+            }
+            if c > 0 {
+                push(i - 1, -1f32 - convection);
+            }
+            push(i, 4f32);
+            if c + 1 < k {
+                push(i + 1, -1f32 + convection);
+            }
+            if r + 1 < k {
+                push(i + k, -1f32);
+            }
+            row_ptr.push(col_idx.len());
+        }
+        CsrMatrix::new(n, n, row_ptr, col_idx, values)
+    }
+
+    #[test]
+    fn test_exact_on_tridiagonal() {
+        // no fill in for a tridiagonal pattern, both incomplete factorizations are exact
+        let n: usize = 30;
+        let mut row_ptr = vec![0];
+        let (mut col_idx, mut values) = (Vec::new(), Vec::new());
+        for i in 0..n {
+            for j in i.saturating_sub(1)..(i + 2).min(n) {
+                col_idx.push(j);
+                values.push(if i == j { 3f32 } else { -1f32 });
+            }
+            row_ptr.push(col_idx.len());
+        }
+        let t = CsrMatrix::new(n, n, row_ptr, col_idx, values);
+        let b = generate_random_vector(n);
+        let mut x = vec![0f32; n];
+        let mut check = vec![0f32; n];
+        let ic = IncompleteCholesky::new(&t);
+        let exact = Cholesky::new(t.to_dense());
+        for i in 0..n {
+            assert!(
+                (ic.lower.values[ic.lower.row_ptr[i + 1] - 1] - exact.l.data[i * n + i]).abs()
+                    < 1e-5
+            );
+        }
+        for m in [&IncompleteLu::new(&t) as &dyn Preconditioner, &ic] {
+            m.precondition(&b, &mut x);
+            t.apply(&x, &mut check);
+            assert!(check.iter().zip(&b).all(|(p, q)| (p - q).abs() < 1e-4));
+        }
+    }
+    #[test]
+    fn test_preconditioned_iterations() {
+        let a = grid(16, 0f32);
+        let n = a.rows;
+        let b = generate_random_vector(n);
+        let plain = Krylov::new(1e-5, 500).cg(&a, &b, None);
+        let jacobi = Jacobi::new(&a);
+        let block = BlockJacobi::new(&a, 16);
+        let ssor = Ssor::new(&a, 1.2);
+        let ic = IncompleteCholesky::new(&a);
+        for m in [&jacobi as &dyn Preconditioner, &block, &ssor, &ic] {
+            let result = Krylov::new(1e-5, 500)
+                .with_preconditioner(m)
+                .cg(&a, &b, None);
+            assert!(result.converged);
+            assert!(result.iterations <= plain.iterations);
+        }
+        let ic_iterations = Krylov::new(1e-5, 500)
+            .with_preconditioner(&ic)
+            .cg(&a, &b, None)
+            .iterations;
+        assert!(ic_iterations < plain.iterations);
+        // nonsymmetric convection diffusion with ilu(0) and gmres
+        let c = grid(16, 0.4);
+        let unpreconditioned = Krylov::new(1e-5, 500).gmres(&c, &b, None);
+        let ilu = IncompleteLu::new(&c);
+        let result = Krylov::new(1e-5, 500)
+            .with_preconditioner(&ilu)
+            .gmres(&c, &b, None);
+        assert!(result.converged && result.iterations < unpreconditioned.iterations);
+    }
+}
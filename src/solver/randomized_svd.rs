@@ -1,9 +1,13 @@
-use crate::algebra::ndmethods::{lt_matrix_mult, matrix_mult};
+use crate::algebra::ndmethods::{create_identity_rectangle, lt_matrix_mult, matrix_mult};
 use crate::decomposition::givens::{SingularValueDecomp, full_givens_iteration, givens_iteration};
 use crate::decomposition::golub_kahan::{full_golub_kahan, golub_kahan};
+use crate::decomposition::qr::QrDecomposition;
 use crate::decomposition::qr_matrix::QrDecomp;
+use crate::decomposition::svd::decomp::{Svd, SvdMode};
 use crate::random::generation::generate_random_matrix;
 use crate::structure::ndarray::NdArray;
+use rand::prelude::*;
+use rand_distr::StandardNormal;
 
 // NOTE: should be able to left apply only up to k
 // split at mut to do better memory management
@@ -117,6 +121,246 @@ impl RandomizedSvd {
     }
 }
 
+/// Sketch
+/// test matrix for the range finder
+///
+/// * Gaussian: dense standard normal entries
+/// * SparseSign: every row holds the given number of +-1 entries in random columns
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sketch {
+    Gaussian,
+    SparseSign(usize),
+}
+
+/// RandomizedSvdBuilder
+/// halko martinsson tropp randomized svd of a rectangular matrix
+///
+/// * rank: number of singular triplets k
+/// * oversampling: extra sketch columns p, the range finder uses k + p
+/// * power_iterations: q passes of (AA') with re-orthonormalization in between
+/// * sketch: distribution of the test matrix
+/// * seed: rng seed for reproducible sketches, thread rng if none
+/// * probes: gaussian probes r of the error estimate, it holds with probability 1 - 10^-r
+pub struct RandomizedSvdBuilder {
+    pub rank: usize,
+    pub oversampling: usize,
+    pub power_iterations: usize,
+    pub sketch: Sketch,
+    pub seed: Option<u64>,
+    pub probes: usize,
+}
+
+/// SketchedSvd
+///
+/// * svd: A ~ USV' with U ~ rows x k and V ~ cols x k
+/// * error_estimate: probabilistic upper bound on ||A - USV'||_2
+pub struct SketchedSvd {
+    pub svd: Svd,
+    pub error_estimate: f32,
+}
+
+// thin Q of the householder QR, rank deficient columns still get orthonormal directions
+fn orthonormalize(y: &NdArray) -> NdArray {
+    let (rows, cols) = (y.dims[0], y.dims[1]);
+    debug_assert!(rows >= cols, "orthonormalize requires rows >= cols");
+    let qr = QrDecomposition::new(y.clone());
+    let mut q = create_identity_rectangle(rows, cols);
+    qr.left_apply_q(&mut q);
+    q
+}
+
+impl RandomizedSvd {
+    pub fn builder(rank: usize) -> RandomizedSvdBuilder {
+        RandomizedSvdBuilder::new(rank)
+    }
+}
+
+impl RandomizedSvdBuilder {
+    pub fn new(rank: usize) -> Self {
+        Self {
+            rank,
+            oversampling: 10,
+            power_iterations: 2,
+            sketch: Sketch::Gaussian,
+            seed: None,
+            probes: 10,
+        }
+    }
+    pub fn oversampling(mut self, oversampling: usize) -> Self {
+        self.oversampling = oversampling;
+        self
+    }
+    pub fn power_iterations(mut self, power_iterations: usize) -> Self {
+        self.power_iterations = power_iterations;
+        self
+    }
+    pub fn sketch(mut self, sketch: Sketch) -> Self {
+        self.sketch = sketch;
+        self
+    }
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    pub fn probes(mut self, probes: usize) -> Self {
+        self.probes = probes.max(1);
+        self
+    }
+    fn test_matrix(&self, rng: &mut StdRng, n: usize, l: usize) -> NdArray {
+        let mut omega = NdArray::new(vec![n, l], vec![0f32; n * l]);
+        match self.sketch {
+            Sketch::Gaussian => omega
+                .data
+                .iter_mut()
+                .for_each(|x| *x = rng.sample(StandardNormal)),
+            Sketch::SparseSign(nonzeros) => {
+                let nonzeros = nonzeros.clamp(1, l);
+                let scale = 1f32 / (nonzeros as f32).sqrt();
+                let mut columns: Vec<usize> = (0..l).collect();
+                for row in omega.data.chunks_mut(l) {
+                    let (chosen, _) = columns.partial_shuffle(rng, nonzeros);
+                    for &j in chosen.iter() {
+                        row[j] = if rng.random::<bool>() { scale } else { -scale };
+                    }
+                }
+            }
+        }
+        omega
+    }
+    /// compute
+    ///
+    /// * a: matrix ~ rows x cols
+    pub fn compute(&self, a: &NdArray) -> SketchedSvd {
+        let (rows, cols) = (a.dims[0], a.dims[1]);
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let l = (self.rank + self.oversampling).min(rows).min(cols).max(1);
+        let at = a.transpose();
+        // range finder Q = orth((AA')^q A W)
+        let mut q = orthonormalize(&matrix_mult(a, &self.test_matrix(&mut rng, cols, l)));
+        for _ in 0..self.power_iterations {
+            let z = orthonormalize(&matrix_mult(&at, &q));
+            q = orthonormalize(&matrix_mult(a, &z));
+        }
+        // B = Q'A = WSV', U = QW
+        let k = self.rank.min(l);
+        let small = Svd::compute(&matrix_mult(&q.transpose(), a), SvdMode::Truncated(k));
+        let u = matrix_mult(&q, small.u.as_ref().unwrap());
+        let v = small.v.unwrap();
+        // ||A - USV'||_2 <= 10 sqrt(2 / pi) max_i ||(A - USV')w_i|| for gaussian w_i
+        let probes = generate_probes(&mut rng, cols, self.probes);
+        let mut sv = matrix_mult(&v.transpose(), &probes);
+        for (i, row) in sv.data.chunks_mut(self.probes).enumerate() {
+            row.iter_mut().for_each(|x| *x *= small.s[i]);
+        }
+        let mut residual = matrix_mult(a, &probes);
+        let fitted = matrix_mult(&u, &sv);
+        residual
+            .data
+            .iter_mut()
+            .zip(&fitted.data)
+            .for_each(|(r, f)| *r -= f);
+        let worst = (0..self.probes)
+            .map(|j| {
+                (0..rows)
+                    .map(|i| residual.data[i * self.probes + j].powi(2))
+                    .sum::<f32>()
+                    .sqrt()
+            })
+            .fold(0f32, f32::max);
+        SketchedSvd {
+            svd: Svd {
+                rows,
+                cols,
+                s: small.s,
+                u: Some(u),
+                v: Some(v),
+            },
+            error_estimate: 10f32 * (2f32 / std::f32::consts::PI).sqrt() * worst,
+        }
+    }
+}
+
+fn generate_probes(rng: &mut StdRng, n: usize, r: usize) -> NdArray {
+    let data = (0..n * r).map(|_| rng.sample(StandardNormal)).collect();
+    NdArray::new(vec![n, r], data)
+}
+
+#[cfg(test)]
+mod test_randomized_svd {
+    use super::*;
+    use crate::random::generation::generate_seeded_matrix;
+
+    const SEED: u64 = 43;
+
+    // A = U diag(s) V' with orthonormal U, V from seeded random matrices
+    fn spectrum(rows: usize, cols: usize, s: &[f32]) -> NdArray {
+        let k = s.len();
+        let u = orthonormalize(&generate_seeded_matrix(rows, k, SEED));
+        let mut v = orthonormalize(&generate_seeded_matrix(cols, k, SEED + 1));
+        for row in v.data.chunks_mut(k) {
+            row.iter_mut().zip(s).for_each(|(x, si)| *x *= si);
+        }
+        matrix_mult(&u, &v.transpose())
+    }
+
+    #[test]
+    fn test_low_rank_recovery() {
+        let (rows, cols) = (80, 50);
+        let s = [9f32, 7f32, 4f32, 2f32, 1f32];
+        let a = spectrum(rows, cols, &s);
+        for sketch in [Sketch::Gaussian, Sketch::SparseSign(3)] {
+            let result = RandomizedSvd::builder(5)
+                .power_iterations(0)
+                .sketch(sketch)
+                .seed(7)
+                .compute(&a);
+            assert_eq!(result.svd.u.as_ref().unwrap().dims, vec![rows, 5]);
+            assert_eq!(result.svd.v.as_ref().unwrap().dims, vec![cols, 5]);
+            for (x, y) in result.svd.s.iter().zip(&s) {
+                assert!((x - y).abs() < 1e-3);
+            }
+            // exact rank k, the estimate only sees f32 roundoff
+            assert!(result.error_estimate < 1e-3 * s[0]);
+        }
+        // the same seed reproduces the sketch
+        let first = RandomizedSvd::builder(3).seed(11).compute(&a);
+        let second = RandomizedSvd::builder(3).seed(11).compute(&a);
+        assert_eq!(first.svd.s, second.svd.s);
+    }
+    #[test]
+    fn test_error_estimate_bounds() {
+        // slowly decaying spectrum, the estimate bounds the true spectral error
+        let s: Vec<f32> = (0..40).map(|i| 1f32 / (1f32 + i as f32)).collect();
+        let a = spectrum(60, 90, &s);
+        let result = RandomizedSvd::builder(6)
+            .oversampling(5)
+            .seed(SEED)
+            .compute(&a);
+        let (u, v) = (
+            result.svd.u.as_ref().unwrap(),
+            result.svd.v.as_ref().unwrap(),
+        );
+        let mut sv = v.transpose();
+        for (i, row) in sv.data.chunks_mut(90).enumerate() {
+            row.iter_mut().for_each(|x| *x *= result.svd.s[i]);
+        }
+        let mut residual = a.clone();
+        let fitted = matrix_mult(u, &sv);
+        residual
+            .data
+            .iter_mut()
+            .zip(&fitted.data)
+            .for_each(|(r, f)| *r -= f);
+        let actual = Svd::compute(&residual, SvdMode::ValuesOnly).s[0];
+        // optimal error is s_7 = 1 / 7
+        assert!(actual >= s[6] - 1e-3 && actual < 1.5 * s[6]);
+        assert!(result.error_estimate >= actual);
+    }
+}
+
 // use std::hint::black_box;
 // use std::time::{Duration, Instant};
 // use stellar::random::generation::generate_random_matrix;
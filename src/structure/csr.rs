@@ -0,0 +1,126 @@
+use crate::structure::ndarray::NdArray;
+use crate::structure::operator::LinearOperator;
+
+/// CsrMatrix
+/// compressed sparse row storage, column indices sorted within every row
+///
+/// * rows: number of rows
+/// * cols: number of columns
+/// * row_ptr: row i occupies row_ptr[i]..row_ptr[i + 1] ~ rows + 1
+/// * col_idx: column of every stored entry
+/// * values: value of every stored entry
+#[derive(Clone, Debug)]
+pub struct CsrMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub row_ptr: Vec<usize>,
+    pub col_idx: Vec<usize>,
+    pub values: Vec<f32>,
+}
+
+impl CsrMatrix {
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        row_ptr: Vec<usize>,
+        mut col_idx: Vec<usize>,
+        mut values: Vec<f32>,
+    ) -> Self {
+        debug_assert_eq!(row_ptr.len(), rows + 1);
+        debug_assert_eq!(col_idx.len(), values.len());
+        for i in 0..rows {
+            let range = row_ptr[i]..row_ptr[i + 1];
+            if col_idx[range.clone()].is_sorted() {
+                continue;
+            }
+            let mut entries: Vec<(usize, f32)> = col_idx[range.clone()]
+                .iter()
+                .copied()
+                .zip(values[range.clone()].iter().copied())
+                .collect();
+            entries.sort_by_key(|&(j, _)| j);
+            for (k, (j, v)) in range.zip(entries) {
+                col_idx[k] = j;
+                values[k] = v;
+            }
+        }
+        Self {
+            rows,
+            cols,
+            row_ptr,
+            col_idx,
+            values,
+        }
+    }
+    pub fn from_dense(a: &NdArray) -> Self {
+        let (rows, cols) = (a.dims[0], a.dims[1]);
+        let mut row_ptr = Vec::with_capacity(rows + 1);
+        let (mut col_idx, mut values) = (Vec::new(), Vec::new());
+        row_ptr.push(0);
+        for row in a.data.chunks(cols) {
+            for (j, &v) in row.iter().enumerate() {
+                if v != 0f32 {
+                    col_idx.push(j);
+                    values.push(v);
+                }
+            }
+            row_ptr.push(col_idx.len());
+        }
+        Self {
+            rows,
+            cols,
+            row_ptr,
+            col_idx,
+            values,
+        }
+    }
+    pub fn to_dense(&self) -> NdArray {
+        let mut data = vec![0f32; self.rows * self.cols];
+        for i in 0..self.rows {
+            for k in self.row_ptr[i]..self.row_ptr[i + 1] {
+                data[i * self.cols + self.col_idx[k]] += self.values[k];
+            }
+        }
+        NdArray::new(vec![self.rows, self.cols], data)
+    }
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+    /// row
+    /// column indices and values of row i
+    pub fn row(&self, i: usize) -> (&[usize], &[f32]) {
+        let range = self.row_ptr[i]..self.row_ptr[i + 1];
+        (&self.col_idx[range.clone()], &self.values[range])
+    }
+    pub fn diagonal(&self) -> Vec<f32> {
+        (0..self.rows.min(self.cols))
+            .map(|i| {
+                let (cols, values) = self.row(i);
+                cols.binary_search(&i).map_or(0f32, |k| values[k])
+            })
+            .collect()
+    }
+}
+
+impl LinearOperator for CsrMatrix {
+    fn dims(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+    fn apply(&self, x: &[f32], y: &mut [f32]) {
+        debug_assert!(x.len() == self.cols && y.len() == self.rows);
+        for (i, yi) in y.iter_mut().enumerate() {
+            let (cols, values) = self.row(i);
+            *yi = cols.iter().zip(values).map(|(&j, v)| v * x[j]).sum();
+        }
+    }
+    fn apply_transpose(&self, x: &[f32], y: &mut [f32]) {
+        debug_assert!(x.len() == self.rows && y.len() == self.cols);
+        y.fill(0f32);
+        for (i, xi) in x.iter().enumerate() {
+            let (cols, values) = self.row(i);
+            for (&j, v) in cols.iter().zip(values) {
+                y[j] += v * xi;
+            }
+        }
+    }
+}
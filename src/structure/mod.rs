@@ -1,2 +1,4 @@
+pub mod csr;
 pub mod ndarray;
 pub mod ndsignal;
+pub mod operator;
@@ -101,6 +101,18 @@ impl Complex {
         self.r *= alpha;
         self.i *= alpha;
     }
+    pub fn real(&self) -> f32 {
+        self.r
+    }
+    pub fn imag(&self) -> f32 {
+        self.i
+    }
+    pub fn conj(&self) -> Complex {
+        Complex::new(self.r, -self.i)
+    }
+    pub fn norm(&self) -> f32 {
+        self.r.hypot(self.i)
+    }
 }
 
 impl NdSignal {
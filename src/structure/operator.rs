@@ -0,0 +1,100 @@
+use crate::structure::ndarray::NdArray;
+
+/// LinearOperator
+/// matrix free linear map, only products with A and A' are required
+///
+/// * dims: (rows, cols) of A
+/// * apply: y <- Ax
+/// * apply_transpose: y <- A'x
+pub trait LinearOperator {
+    fn dims(&self) -> (usize, usize);
+    fn apply(&self, x: &[f32], y: &mut [f32]);
+    fn apply_transpose(&self, x: &[f32], y: &mut [f32]);
+}
+
+impl LinearOperator for NdArray {
+    fn dims(&self) -> (usize, usize) {
+        (self.dims[0], self.dims[1])
+    }
+    fn apply(&self, x: &[f32], y: &mut [f32]) {
+        debug_assert!(x.len() == self.dims[1] && y.len() == self.dims[0]);
+        for (yi, row) in y.iter_mut().zip(self.data.chunks(self.dims[1])) {
+            *yi = row.iter().zip(x).map(|(a, b)| a * b).sum();
+        }
+    }
+    fn apply_transpose(&self, x: &[f32], y: &mut [f32]) {
+        debug_assert!(x.len() == self.dims[0] && y.len() == self.dims[1]);
+        y.fill(0f32);
+        for (xi, row) in x.iter().zip(self.data.chunks(self.dims[1])) {
+            for (yj, a) in y.iter_mut().zip(row) {
+                *yj += xi * a;
+            }
+        }
+    }
+}
+
+/// FunctionOperator
+/// linear operator backed by closures, for sparse or implicit matrices
+///
+/// * rows: output dimension
+/// * cols: input dimension
+/// * forward: y <- Ax
+/// * transpose: y <- A'x, forward is reused for symmetric operators
+pub struct FunctionOperator<F, G = F>
+where
+    F: Fn(&[f32], &mut [f32]),
+    G: Fn(&[f32], &mut [f32]),
+{
+    pub rows: usize,
+    pub cols: usize,
+    forward: F,
+    transpose: Option<G>,
+}
+
+impl<F, G> FunctionOperator<F, G>
+where
+    F: Fn(&[f32], &mut [f32]),
+    G: Fn(&[f32], &mut [f32]),
+{
+    pub fn new(rows: usize, cols: usize, forward: F, transpose: G) -> Self {
+        Self {
+            rows,
+            cols,
+            forward,
+            transpose: Some(transpose),
+        }
+    }
+}
+
+impl<F> FunctionOperator<F>
+where
+    F: Fn(&[f32], &mut [f32]),
+{
+    pub fn symmetric(n: usize, forward: F) -> Self {
+        Self {
+            rows: n,
+            cols: n,
+            forward,
+            transpose: None,
+        }
+    }
+}
+
+impl<F, G> LinearOperator for FunctionOperator<F, G>
+where
+    F: Fn(&[f32], &mut [f32]),
+    G: Fn(&[f32], &mut [f32]),
+{
+    fn dims(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+    fn apply(&self, x: &[f32], y: &mut [f32]) {
+        (self.forward)(x, y)
+    }
+    fn apply_transpose(&self, x: &[f32], y: &mut [f32]) {
+        match &self.transpose {
+            Some(transpose) => transpose(x, y),
+            None => (self.forward)(x, y),
+        }
+    }
+}
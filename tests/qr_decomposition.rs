@@ -195,4 +195,39 @@ mod qr_decomposition {
         qr.left_apply_q(&mut y);
         assert!(approx_vector_eq(&y.data, &y_clone.data));
     }
+    #[test]
+    fn reconstruction_negative_pivot() {
+        // largest entry of the first column is negative and followed by a zero
+        let x = NdArray {
+            dims: vec![3, 3],
+            data: vec![1.0, 2.0, 0.5, -4.0, 1.0, 3.0, 0.0, 2.0, -1.0],
+        };
+        zeroing_below_diagonal(x.clone());
+        reconstruction(x)
+    }
+    #[test]
+    fn reconstruction_skipped_reflection() {
+        // the first column needs no reflection, later columns still do
+        let x = NdArray {
+            dims: vec![3, 3],
+            data: vec![0.0, 1.0, 2.0, 0.0, 3.0, 4.0, 0.0, 5.0, 6.0],
+        };
+        reconstruction(x)
+    }
+    #[test]
+    fn reconstruction_wide() {
+        // columns past the last reflection must still be reflected into R
+        let x = NdArray {
+            dims: vec![2, 4],
+            data: vec![3.0, -1.0, 2.0, 5.0, 4.0, 2.0, -3.0, 1.0],
+        };
+        reconstruction(x);
+        reconstruction(generate_random_matrix(4, 7))
+    }
+    #[test]
+    fn reconstruction_tall() {
+        let x = generate_random_matrix(7, 4);
+        let qr = QrDecomposition::new(x);
+        assert_eq!(qr.triangle.dims, vec![4, 4]);
+    }
 }